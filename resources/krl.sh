@@ -0,0 +1,29 @@
+#!/bin/bash
+
+set -e
+
+rm -f krl krl-revoked*
+
+# Create keys to be revoked explicitly and by hash
+
+echo '\n\n' | ssh-keygen -t ed25519 -f krl-revoked1
+echo '\n\n' | ssh-keygen -t ed25519 -f krl-revoked2
+rm krl-revoked2
+
+# Create KRL (revokes the host cert by key id)
+
+cat > krl.txt << SPEC
+serial: 10-20
+serial: 1000-100000
+serial: 100
+serial: 102
+serial: 107
+id: cert2
+key: $(cat krl-revoked1.pub)
+hash: $(ssh-keygen -l -E sha256 -f krl-revoked2.pub | cut -d' ' -f2)
+SPEC
+
+ssh-keygen -k -f krl -s ed25519-ca.pub -z 1 krl.txt
+rm krl.txt
+
+ssh-keygen -Q -l -f krl
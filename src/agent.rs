@@ -1,6 +1,12 @@
+mod key_store;
 mod local;
+mod restricted;
+#[cfg(test)]
+mod testing;
 
+pub use self::key_store::*;
 pub use self::local::*;
+pub use self::restricted::*;
 
 use crate::identity::*;
 use crate::util::*;
@@ -23,6 +29,13 @@ pub trait AuthAgent: std::fmt::Debug + Send + Sync + 'static {
         data: &[u8],
         flags: u32,
     ) -> AuthAgentFuture<Option<Signature>>;
+    /// Handle an extension request (like `session-bind@openssh.com`).
+    ///
+    /// Returns the extension specific response data or `None` if the extension is not
+    /// supported or the request failed. Extensions are not supported by default.
+    fn extension(&self, _name: &str, _data: &[u8]) -> AuthAgentFuture<Option<Vec<u8>>> {
+        Box::pin(async { Ok(None) })
+    }
 }
 
 /// The unit agent neither offers identities nor will it sign anything.
@@ -36,6 +49,26 @@ impl AuthAgent for () {
     }
 }
 
+/// A shared agent is an agent, too.
+impl AuthAgent for Arc<dyn AuthAgent> {
+    fn identities(&self) -> AuthAgentFuture<Vec<(Identity, String)>> {
+        self.as_ref().identities()
+    }
+
+    fn signature(
+        &self,
+        id: &Identity,
+        data: &[u8],
+        flags: u32,
+    ) -> AuthAgentFuture<Option<Signature>> {
+        self.as_ref().signature(id, data, flags)
+    }
+
+    fn extension(&self, name: &str, data: &[u8]) -> AuthAgentFuture<Option<Vec<u8>>> {
+        self.as_ref().extension(name, data)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AuthAgentError(ArcError);
 
@@ -0,0 +1,216 @@
+use super::*;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Supplies passphrases for encrypted private key files.
+pub trait PassphrasePrompt: std::fmt::Debug + Send + Sync + 'static {
+    /// Request the passphrase for the key file at `path`.
+    ///
+    /// Returns `None` in order to skip the key.
+    fn passphrase(&self, path: &Path) -> BoxFuture<Option<String>>;
+}
+
+/// The unit prompt never supplies a passphrase.
+impl PassphrasePrompt for () {
+    fn passphrase(&self, _: &Path) -> BoxFuture<Option<String>> {
+        Box::pin(async { None })
+    }
+}
+
+/// An in-process key store that serves as [AuthAgent].
+///
+/// This is an alternative to a running `ssh-agent`, e.g. for servers signing with their host
+/// keys or clients using keys from files (like OpenSSH's `IdentityFile`). Clones share the
+/// same keys.
+#[derive(Clone, Debug, Default)]
+pub struct KeyStore {
+    keys: Arc<Mutex<Vec<(Identity, PrivateKey, String)>>>,
+}
+
+impl KeyStore {
+    /// The number of passphrases requested before giving up on a file.
+    const PASSPHRASE_ATTEMPTS: usize = 3;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key (replaces a key with the same identity).
+    pub fn add(&self, key: PrivateKey, comment: &str) {
+        self.insert(key.identity(), key, comment)
+    }
+
+    /// Add a key to be offered with a certificate (replaces the same certificate).
+    ///
+    /// The certificate is not checked to match the key.
+    pub fn add_certificate(&self, key: PrivateKey, cert: Identity, comment: &str) {
+        self.insert(cert, key, comment)
+    }
+
+    /// Remove the key or certificate with the given identity (returns `false` if there was none).
+    pub fn remove(&self, identity: &Identity) -> bool {
+        let mut keys = self.keys.lock().unwrap();
+        let len = keys.len();
+        keys.retain(|(i, _, _)| i != identity);
+        keys.len() != len
+    }
+
+    /// Remove all keys.
+    pub fn remove_all(&self) {
+        self.keys.lock().unwrap().clear()
+    }
+
+    /// Load all keys from a private key file (`openssh-key-v1` or PKCS#8 PEM).
+    ///
+    /// The `prompt` is asked for a passphrase if the file is encrypted. Keys without comment
+    /// get the path as comment. Returns the identities of the loaded keys.
+    pub async fn load<P: AsRef<Path>>(
+        &self,
+        path: P,
+        prompt: &dyn PassphrasePrompt,
+    ) -> Result<Vec<Identity>, KeyStoreError> {
+        let path = path.as_ref();
+        let text = tokio::fs::read_to_string(path).await?;
+        let mut result = PrivateKey::parse(&text, None);
+        for _ in 0..Self::PASSPHRASE_ATTEMPTS {
+            match result {
+                Err(PrivateKeyError::PassphraseRequired | PrivateKeyError::InvalidPassphrase) => {
+                    let passphrase = match prompt.passphrase(path).await {
+                        Some(passphrase) => zeroize::Zeroizing::new(passphrase),
+                        None => break,
+                    };
+                    result = PrivateKey::parse(&text, Some(&passphrase));
+                }
+                _ => break,
+            }
+        }
+        let keys = result?;
+        let identities = keys.iter().map(|(k, _)| k.identity()).collect();
+        for (key, comment) in keys {
+            if comment.is_empty() {
+                self.add(key, &path.to_string_lossy());
+            } else {
+                self.add(key, &comment);
+            }
+        }
+        Ok(identities)
+    }
+
+    fn insert(&self, identity: Identity, key: PrivateKey, comment: &str) {
+        let mut keys = self.keys.lock().unwrap();
+        keys.retain(|(i, _, _)| *i != identity);
+        keys.push((identity, key, comment.into()));
+    }
+}
+
+impl AuthAgent for KeyStore {
+    fn identities(&self) -> AuthAgentFuture<Vec<(Identity, String)>> {
+        let keys = self.keys.lock().unwrap();
+        let ids = keys
+            .iter()
+            .map(|(i, _, c)| (i.clone(), c.clone()))
+            .collect();
+        Box::pin(async { Ok(ids) })
+    }
+
+    fn signature(&self, id: &Identity, data: &[u8], _: u32) -> AuthAgentFuture<Option<Signature>> {
+        let keys = self.keys.lock().unwrap();
+        let sig = keys
+            .iter()
+            .find(|(i, _, _)| i == id)
+            .map(|(_, k, _)| k.sign(data));
+        Box::pin(async { Ok(sig) })
+    }
+}
+
+impl AgentBackend for KeyStore {
+    fn add_identity(
+        &self,
+        key: PrivateKey,
+        cert: Option<Identity>,
+        comment: String,
+        _: Vec<KeyConstraint>,
+    ) -> AuthAgentFuture<bool> {
+        match cert {
+            Some(cert) => self.add_certificate(key, cert, &comment),
+            None => self.add(key, &comment),
+        }
+        Box::pin(async { Ok(true) })
+    }
+
+    fn remove_identity(&self, id: &Identity) -> AuthAgentFuture<bool> {
+        let removed = self.remove(id);
+        Box::pin(async move { Ok(removed) })
+    }
+
+    fn remove_all_identities(&self) -> AuthAgentFuture<bool> {
+        self.remove_all();
+        Box::pin(async { Ok(true) })
+    }
+}
+
+#[derive(Debug)]
+pub enum KeyStoreError {
+    IoError(std::io::Error),
+    PrivateKeyError(PrivateKeyError),
+}
+
+impl From<std::io::Error> for KeyStoreError {
+    fn from(e: std::io::Error) -> Self {
+        Self::IoError(e)
+    }
+}
+
+impl From<PrivateKeyError> for KeyStoreError {
+    fn from(e: PrivateKeyError) -> Self {
+        Self::PrivateKeyError(e)
+    }
+}
+
+impl Error for KeyStoreError {}
+
+impl std::fmt::Display for KeyStoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "IO error: {}", e),
+            Self::PrivateKeyError(e) => write!(f, "Private key: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::testing::*;
+
+    #[tokio::test]
+    async fn test_add_remove_01() {
+        let store = KeyStore::new();
+        store.add(key(1), "one");
+        store.add(key(2), "two");
+        store.add(key(1), "one again");
+        let ids = store.identities().await.unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[0].1, "two");
+        assert_eq!(ids[1].1, "one again");
+        assert!(store.remove(&key(2).identity()));
+        assert!(!store.remove(&key(2).identity()));
+        assert_eq!(store.identities().await.unwrap().len(), 1);
+        store.remove_all();
+        assert!(store.identities().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_signature_01() {
+        let store = KeyStore::new();
+        store.add(key(1), "one");
+        let id = key(1).identity();
+        let sig = store.signature(&id, b"data", 0).await.unwrap().unwrap();
+        assert!(sig.verify(&id, b"data").is_ok());
+        let sig = store
+            .signature(&key(2).identity(), b"data", 0)
+            .await
+            .unwrap();
+        assert!(sig.is_none());
+    }
+}
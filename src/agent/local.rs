@@ -1,49 +1,222 @@
+mod constraint;
+mod forwarded;
 mod frame;
+mod msg_add_identity;
+mod msg_extension;
+mod msg_extension_failure;
+mod msg_extension_response;
 mod msg_failure;
 mod msg_identities_answer;
 mod msg_identities_request;
+mod msg_lock;
+mod msg_remove_all_identities;
+mod msg_remove_identity;
 mod msg_sign_request;
 mod msg_sign_response;
 mod msg_success;
+mod server;
+mod session_bind;
 mod transmitter;
 
+pub use self::constraint::KeyConstraint;
+pub use self::forwarded::*;
+pub use self::server::*;
+pub use self::session_bind::SessionBind;
+
 use self::frame::*;
+use self::msg_add_identity::*;
+use self::msg_extension::*;
+use self::msg_extension_failure::*;
+use self::msg_extension_response::*;
 use self::msg_failure::*;
 use self::msg_identities_answer::*;
 use self::msg_identities_request::*;
+use self::msg_lock::*;
+use self::msg_remove_all_identities::*;
+use self::msg_remove_identity::*;
 use self::msg_sign_request::*;
 use self::msg_sign_response::*;
+use self::msg_success::*;
 use self::transmitter::*;
 use super::AuthAgent;
 use super::AuthAgentError;
 use super::AuthAgentFuture;
+use super::AuthAgentResult;
 use super::Signature;
-use crate::identity::Identity;
-use std::convert::TryFrom;
+use crate::identity::{Identity, PrivateKey};
+use crate::util::codec::*;
 use std::path::{Path, PathBuf};
-use tokio::net::UnixStream;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// A client for the local `ssh-agent`.
+///
+/// The client keeps a single connection to the agent which is shared by all clones and all
+/// concurrent requests. It is established on first use and re-established after errors (a
+/// request is retried if the agent closed the connection while idle).
+/// Session bindings ([AuthAgent::extension]) are therefore not passed on to the agent: They
+/// would restrict the shared connection for all sessions. Binding the agent during user
+/// authentication is a no-op with this client and keys are not restricted to destinations.
 #[derive(Debug, Clone)]
 pub struct LocalAgent {
-    path: PathBuf,
+    queue: Arc<TransmitterQueue>,
 }
 
 impl LocalAgent {
     const SSH_AUTH_SOCK: &'static str = "SSH_AUTH_SOCK";
+    const TIMEOUT: Duration = Duration::from_secs(30);
 
     /// Create a new agent client by path designating the unix domain socket.
     pub fn new(path: &Path) -> Self {
-        Self { path: path.into() }
+        Self {
+            queue: Arc::new(TransmitterQueue::new(path.into(), Self::TIMEOUT)),
+        }
     }
 
     /// Create a new agent client using the value of `SSH_AUTH_SOCK` as path.
     pub fn new_env() -> Option<Self> {
         let s = std::env::var_os(Self::SSH_AUTH_SOCK)?;
+        Self::new(&PathBuf::from(s)).into()
+    }
+
+    /// Set the time to wait for each response of the agent (defaults to 30 seconds).
+    ///
+    /// An agent that does not respond in time causes an [AuthAgentError] and the connection to
+    /// be re-established. Be aware that the agent might be waiting for a user to confirm the
+    /// use of a key.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        let path = self.queue.path().clone();
         Self {
-            path: TryFrom::try_from(s).ok()?,
+            queue: Arc::new(TransmitterQueue::new(path, timeout)),
+        }
+    }
+
+    /// Add a private key to the agent.
+    ///
+    /// Returns `false` if the agent refused the key (it might not support the constraints).
+    pub async fn add_identity(
+        &self,
+        key: &PrivateKey,
+        comment: &str,
+        constraints: &[KeyConstraint],
+    ) -> AuthAgentResult<bool> {
+        let msg = MsgAddIdentity {
+            key: key.clone(),
+            cert: None,
+            comment: comment.into(),
+            constraints: constraints.into(),
+        };
+        self.request::<_, Result<MsgSuccess, MsgFailure>>(&msg)
+            .await
+            .map(|x| x.is_ok())
+    }
+
+    /// Add a private key together with a certificate for it to the agent.
+    ///
+    /// The agent will offer the certificate as identity and sign with the key.
+    /// Returns `false` if the agent refused the key (it might not support the constraints).
+    pub async fn add_certificate(
+        &self,
+        key: &PrivateKey,
+        cert: &Identity,
+        comment: &str,
+        constraints: &[KeyConstraint],
+    ) -> AuthAgentResult<bool> {
+        let msg = MsgAddIdentity {
+            key: key.clone(),
+            cert: Some(cert.clone()),
+            comment: comment.into(),
+            constraints: constraints.into(),
+        };
+        self.request::<_, Result<MsgSuccess, MsgFailure>>(&msg)
+            .await
+            .map(|x| x.is_ok())
+    }
+
+    /// Remove an identity (key or certificate) from the agent.
+    ///
+    /// Returns `false` if the agent did not hold the identity.
+    pub async fn remove_identity(&self, identity: &Identity) -> AuthAgentResult<bool> {
+        let msg = MsgRemoveIdentity {
+            identity: identity.clone(),
+        };
+        self.request::<_, Result<MsgSuccess, MsgFailure>>(&msg)
+            .await
+            .map(|x| x.is_ok())
+    }
+
+    /// Remove all identities from the agent.
+    pub async fn remove_all_identities(&self) -> AuthAgentResult<bool> {
+        self.request::<_, Result<MsgSuccess, MsgFailure>>(&MsgRemoveAllIdentities)
+            .await
+            .map(|x| x.is_ok())
+    }
+
+    /// Lock the agent with a passphrase.
+    ///
+    /// A locked agent does not list or use any of its identities until unlocked.
+    pub async fn lock(&self, passphrase: &str) -> AuthAgentResult<bool> {
+        let msg = MsgLock {
+            lock: true,
+            passphrase: passphrase.into(),
+        };
+        self.request::<_, Result<MsgSuccess, MsgFailure>>(&msg)
+            .await
+            .map(|x| x.is_ok())
+    }
+
+    /// Unlock the agent with the passphrase it has been locked with.
+    pub async fn unlock(&self, passphrase: &str) -> AuthAgentResult<bool> {
+        let msg = MsgLock {
+            lock: false,
+            passphrase: passphrase.into(),
+        };
+        self.request::<_, Result<MsgSuccess, MsgFailure>>(&msg)
+            .await
+            .map(|x| x.is_ok())
+    }
+
+    /// Send an extension request to the agent over the shared connection.
+    ///
+    /// Returns the extension specific response data or `None` if the agent does not support the
+    /// extension or the request failed. Do not use this for `session-bind@openssh.com`: The
+    /// binding would apply to the connection shared by all clones.
+    pub async fn extension_shared(
+        &self,
+        name: &str,
+        data: &[u8],
+    ) -> AuthAgentResult<Option<Vec<u8>>> {
+        let msg = MsgExtension {
+            name: name.into(),
+            data: data.into(),
+        };
+        type Response = Result<MsgExtensionResponse, Result<MsgFailure, MsgExtensionFailure>>;
+        self.request::<_, Response>(&msg)
+            .await
+            .map(|x| x.ok().map(|y| y.data))
+    }
+
+    /// Query the names of the extensions supported by the agent.
+    ///
+    /// Returns an empty list if the agent does not support the `query` extension.
+    pub async fn query_extensions(&self) -> AuthAgentResult<Vec<String>> {
+        let data = self.extension_shared(MsgExtension::QUERY, &[]).await?;
+        let data = data.unwrap_or_default();
+        let mut d = RefDecoder::new(&data);
+        // The response repeats the extension name before the list (but not in all versions)
+        let mut names = vec![];
+        while d.expect_eoi().is_none() {
+            let name = d.take_str_framed().ok_or(SshCodecError::DecodingFailed);
+            names.push(String::from(name.map_err(AuthAgentError::new)?));
+        }
+        if names.first().map(String::as_str) == Some(MsgExtension::QUERY) {
+            names.remove(0);
         }
-        .into()
+        Ok(names)
+    }
+
+    async fn request<Req: SshEncode, Res: SshDecode>(&self, msg: &Req) -> AuthAgentResult<Res> {
+        self.queue.request(msg).await
     }
 }
 
@@ -51,9 +224,8 @@ impl AuthAgent for LocalAgent {
     fn identities(&self) -> AuthAgentFuture<Vec<(Identity, String)>> {
         let self_ = self.clone();
         Box::pin(async move {
-            let mut t: Transmitter = UnixStream::connect(&self_.path).await?.into();
-            t.send(&MsgIdentitiesRequest {}).await?;
-            t.receive::<MsgIdentitiesAnswer>()
+            self_
+                .request::<_, MsgIdentitiesAnswer>(&MsgIdentitiesRequest {})
                 .await
                 .map(|x| x.identities)
         })
@@ -66,19 +238,93 @@ impl AuthAgent for LocalAgent {
         flags: u32,
     ) -> AuthAgentFuture<Option<Signature>> {
         let self_ = self.clone();
-        let id = id.clone();
-        let data = Vec::from(data);
+        let msg = MsgSignRequest {
+            id: id.clone(),
+            data: data.into(),
+            flags,
+        };
         Box::pin(async move {
-            let msg = MsgSignRequest {
-                id: &id,
-                data: &data,
-                flags,
-            };
-            let mut t: Transmitter = UnixStream::connect(&self_.path).await?.into();
-            t.send(&msg).await?;
-            t.receive::<Result<MsgSignResponse, MsgFailure>>()
+            self_
+                .request::<_, Result<MsgSignResponse, MsgFailure>>(&msg)
                 .await
                 .map(|x| x.ok().map(|y| y.signature))
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::testing::*;
+    use crate::agent::KeyStore;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::net::{UnixListener, UnixStream};
+
+    /// Serve a key store and count the accepted connections.
+    fn serve(listener: UnixListener) -> Arc<AtomicUsize> {
+        let store = KeyStore::new();
+        store.add(key(1), "one");
+        let server = AgentServer::new(Arc::new(store), Arc::new(()));
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let accepted_ = accepted.clone();
+        drop(tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                accepted_.fetch_add(1, Ordering::SeqCst);
+                let server = server.clone();
+                drop(tokio::spawn(async move { server.serve(socket).await }));
+            }
+        }));
+        accepted
+    }
+
+    #[tokio::test]
+    async fn test_reuse_01() {
+        let (listener, path) = listen();
+        let accepted = serve(listener);
+        let agent = LocalAgent::new(&path);
+        let tasks: Vec<_> = (0..16)
+            .map(|_| {
+                let agent = agent.clone();
+                tokio::spawn(async move { agent.identities().await })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap().len(), 1);
+        }
+        assert_eq!(agent.identities().await.unwrap().len(), 1);
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_01() {
+        let (listener, path) = listen();
+        // Answer only the first request of each connection
+        drop(tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let mut t: Transmitter<UnixStream> = socket.into();
+                let _ = t.receive_frame().await;
+                let msg = MsgIdentitiesAnswer { identities: vec![] };
+                let _ = t.send(&msg).await;
+            }
+        }));
+        let agent = LocalAgent::new(&path);
+        // Each request after the first one is retried on a new connection
+        assert!(agent.identities().await.is_ok());
+        assert!(agent.identities().await.is_ok());
+        assert!(agent.identities().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_01() {
+        let (listener, path) = listen();
+        let accepted = serve(listener);
+        let (hung, hung_path) = listen();
+        let agent = LocalAgent::new(&hung_path).with_timeout(Duration::from_millis(50));
+        let e = agent.identities().await.unwrap_err();
+        assert_eq!(e.to_string(), "agent did not respond");
+        drop(hung);
+        let agent = LocalAgent::new(&path).with_timeout(Duration::from_millis(500));
+        assert!(agent.identities().await.is_ok());
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::util::codec::*;
+
+/// A constraint on the use of a key added to an agent.
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeyConstraint {
+    /// Remove the key after the given number of seconds (like `ssh-add -t`).
+    Lifetime(u32),
+    /// Require confirmation for each use of the key (like `ssh-add -c`).
+    Confirm,
+    /// An extension constraint with name and extension specific data.
+    ///
+    /// The data is not framed, so an extension must be the last constraint when decoding.
+    Extension(String, Vec<u8>),
+}
+
+impl KeyConstraint {
+    const LIFETIME: u8 = 1;
+    const CONFIRM: u8 = 2;
+    const EXTENSION: u8 = 255;
+}
+
+impl SshEncode for KeyConstraint {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        match self {
+            Self::Lifetime(secs) => {
+                e.push_u8(Self::LIFETIME)?;
+                e.push_u32be(*secs)
+            }
+            Self::Confirm => e.push_u8(Self::CONFIRM),
+            Self::Extension(name, data) => {
+                e.push_u8(Self::EXTENSION)?;
+                e.push_str_framed(name)?;
+                e.push_bytes(data)
+            }
+        }
+    }
+}
+
+impl SshDecode for KeyConstraint {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        match d.take_u8()? {
+            Self::LIFETIME => Some(Self::Lifetime(d.take_u32be()?)),
+            Self::CONFIRM => Some(Self::Confirm),
+            Self::EXTENSION => {
+                let name = d.take_str_framed()?.into();
+                let data = d.take_bytes_all()?.into();
+                Some(Self::Extension(name, data))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_01() {
+        let c = KeyConstraint::Lifetime(300);
+        assert_eq!(SshCodec::encode(&c).unwrap(), [1, 0, 0, 1, 44]);
+        assert_eq!(SshCodec::encode(&KeyConstraint::Confirm).unwrap(), [2]);
+        let c = KeyConstraint::Extension("x".into(), vec![7, 8]);
+        assert_eq!(SshCodec::encode(&c).unwrap(), [255, 0, 0, 0, 1, b'x', 7, 8]);
+    }
+
+    #[test]
+    fn test_decode_01() {
+        let c: KeyConstraint = SshCodec::decode(&[1, 0, 0, 1, 44][..]).unwrap();
+        assert_eq!(c, KeyConstraint::Lifetime(300));
+        let c: KeyConstraint = SshCodec::decode(&[255, 0, 0, 0, 1, b'x', 7, 8][..]).unwrap();
+        assert_eq!(c, KeyConstraint::Extension("x".into(), vec![7, 8]));
+        assert!(SshCodec::decode::<KeyConstraint>(&[3][..]).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use super::*;
+use crate::connection::{AuthAgentChannel, Connection};
+
+/// An agent forwarded by the peer of a connection (server side of agent forwarding).
+///
+/// Each request opens a new `auth-agent@openssh.com` channel on the connection. The peer
+/// rejects the channel unless it requested agent forwarding for one of its sessions.
+#[derive(Debug, Clone)]
+pub struct ForwardedAgent {
+    connection: Connection,
+}
+
+impl ForwardedAgent {
+    pub fn new(connection: &Connection) -> Self {
+        Self {
+            connection: connection.clone(),
+        }
+    }
+
+    async fn connect(&self) -> AuthAgentResult<Transmitter<AuthAgentChannel>> {
+        let channel = self.connection.open_auth_agent().await;
+        let channel = channel.map_err(AuthAgentError::new)?;
+        Ok(channel.map_err(AuthAgentError::new)?.into())
+    }
+}
+
+impl AuthAgent for ForwardedAgent {
+    fn identities(&self) -> AuthAgentFuture<Vec<(Identity, String)>> {
+        let self_ = self.clone();
+        Box::pin(async move {
+            let mut t = self_.connect().await?;
+            t.request::<_, MsgIdentitiesAnswer>(&MsgIdentitiesRequest {})
+                .await
+                .map(|x| x.identities)
+        })
+    }
+
+    fn signature(
+        &self,
+        id: &Identity,
+        data: &[u8],
+        flags: u32,
+    ) -> AuthAgentFuture<Option<Signature>> {
+        let self_ = self.clone();
+        let msg = MsgSignRequest {
+            id: id.clone(),
+            data: data.into(),
+            flags,
+        };
+        Box::pin(async move {
+            let mut t = self_.connect().await?;
+            t.request::<_, Result<MsgSignResponse, MsgFailure>>(&msg)
+                .await
+                .map(|x| x.ok().map(|y| y.signature))
+        })
+    }
+}
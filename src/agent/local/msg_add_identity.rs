@@ -0,0 +1,132 @@
+use super::*;
+use crate::identity::PrivateKey;
+use crate::transport::Message;
+use crate::util::codec::*;
+
+/// `SSH_AGENTC_ADD_IDENTITY` (or `SSH_AGENTC_ADD_ID_CONSTRAINED` with constraints).
+#[derive(Clone, Debug)]
+pub struct MsgAddIdentity {
+    pub key: PrivateKey,
+    /// The certificate for the key (if any).
+    pub cert: Option<Identity>,
+    pub comment: String,
+    pub constraints: Vec<KeyConstraint>,
+}
+
+impl MsgAddIdentity {
+    const NUMBER_CONSTRAINED: u8 = 25;
+    const CERT_SUFFIX: &'static str = "-cert-v01@openssh.com";
+}
+
+impl Message for MsgAddIdentity {
+    const NUMBER: u8 = 17;
+}
+
+impl SshEncode for MsgAddIdentity {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        if self.constraints.is_empty() {
+            e.push_u8(<Self as Message>::NUMBER)?;
+        } else {
+            e.push_u8(Self::NUMBER_CONSTRAINED)?;
+        }
+        if let Some(cert) = &self.cert {
+            e.push_str_framed(cert.algorithm())?;
+            e.push(cert)?;
+            self.key.encode_fields(e)?;
+        } else {
+            e.push(&self.key)?;
+        }
+        e.push_str_framed(&self.comment)?;
+        for constraint in &self.constraints {
+            e.push(constraint)?;
+        }
+        Some(())
+    }
+}
+
+impl SshDecode for MsgAddIdentity {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        let constrained = match d.take_u8()? {
+            <Self as Message>::NUMBER => false,
+            Self::NUMBER_CONSTRAINED => true,
+            _ => return None,
+        };
+        let algorithm = d.take_str_framed()?;
+        let (key, cert) = if algorithm.ends_with(Self::CERT_SUFFIX) {
+            let cert: Identity = d.take()?;
+            let key = PrivateKey::decode_fields(cert.key().algorithm(), d).ok()?;
+            (key, Some(cert))
+        } else {
+            (PrivateKey::decode_fields(algorithm, d).ok()?, None)
+        };
+        let comment = d.take_str_framed()?.into();
+        let mut constraints = vec![];
+        while constrained && d.expect_eoi().is_none() {
+            constraints.push(d.take()?);
+        }
+        Some(Self {
+            key,
+            cert,
+            comment,
+            constraints,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::ssh_ed25519::SshEd25519PrivateKey;
+
+    fn key() -> PrivateKey {
+        SshEd25519PrivateKey::new([1; 32]).into()
+    }
+
+    #[test]
+    fn test_encode_decode_01() {
+        let msg = MsgAddIdentity {
+            key: key(),
+            cert: None,
+            comment: "comment".into(),
+            constraints: vec![],
+        };
+        let buf = SshCodec::encode(&msg).unwrap();
+        assert_eq!(buf[0], 17);
+        assert_eq!(buf.len(), 1 + 4 + 11 + 4 + 32 + 4 + 64 + 4 + 7);
+        let msg: MsgAddIdentity = SshCodec::decode(&buf).unwrap();
+        assert_eq!(msg.key.identity(), key().identity());
+        assert_eq!(msg.cert, None);
+        assert_eq!(msg.comment, "comment");
+    }
+
+    #[test]
+    fn test_encode_decode_02() {
+        let cert = include_str!("../../../resources/ed25519-user-cert.pub");
+        let cert: Identity = cert.parse().unwrap();
+        let msg = MsgAddIdentity {
+            key: key(),
+            cert: Some(cert.clone()),
+            comment: "comment".into(),
+            constraints: vec![KeyConstraint::Lifetime(60), KeyConstraint::Confirm],
+        };
+        let buf = SshCodec::encode(&msg).unwrap();
+        assert_eq!(buf[0], 25);
+        let msg: MsgAddIdentity = SshCodec::decode(&buf).unwrap();
+        assert_eq!(msg.key.identity(), key().identity());
+        assert_eq!(msg.cert, Some(cert));
+        assert_eq!(msg.constraints.len(), 2);
+    }
+
+    #[test]
+    fn test_decode_01() {
+        let msg = MsgAddIdentity {
+            key: key(),
+            cert: None,
+            comment: "".into(),
+            constraints: vec![],
+        };
+        let mut buf = SshCodec::encode(&msg).unwrap();
+        buf.push(1);
+        assert!(SshCodec::decode::<MsgAddIdentity>(&buf).is_err());
+    }
+}
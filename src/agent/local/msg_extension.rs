@@ -0,0 +1,51 @@
+use crate::transport::Message;
+use crate::util::codec::*;
+
+/// `SSH_AGENTC_EXTENSION` with extension name and extension specific data.
+#[derive(Debug, PartialEq)]
+pub struct MsgExtension {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl MsgExtension {
+    /// The extension to request the list of supported extensions.
+    pub const QUERY: &'static str = "query";
+}
+
+impl Message for MsgExtension {
+    const NUMBER: u8 = 27;
+}
+
+impl SshEncode for MsgExtension {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_u8(<Self as Message>::NUMBER)?;
+        e.push_str_framed(&self.name)?;
+        e.push_bytes(&self.data)
+    }
+}
+
+impl SshDecode for MsgExtension {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_u8(<Self as Message>::NUMBER)?;
+        let name = d.take_str_framed()?.into();
+        let data = d.take_bytes_all()?.into();
+        Some(Self { name, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_01() {
+        let msg = MsgExtension {
+            name: MsgExtension::QUERY.into(),
+            data: vec![],
+        };
+        let buf = [27, 0, 0, 0, 5, b'q', b'u', b'e', b'r', b'y'];
+        assert_eq!(SshCodec::encode(&msg).unwrap(), buf);
+        assert_eq!(SshCodec::decode(&buf[..]), Ok(msg));
+    }
+}
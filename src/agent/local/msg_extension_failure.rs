@@ -0,0 +1,51 @@
+use crate::transport::Message;
+use crate::util::codec::*;
+
+#[derive(Debug, PartialEq)]
+pub struct MsgExtensionFailure;
+
+impl Message for MsgExtensionFailure {
+    const NUMBER: u8 = 28;
+}
+
+impl SshEncode for MsgExtensionFailure {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_u8(<Self as Message>::NUMBER)
+    }
+}
+
+impl SshDecode for MsgExtensionFailure {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_u8(<Self as Message>::NUMBER)?;
+        Some(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_01() {
+        let mut buf = [0; 1];
+        let mut enc = RefEncoder::new(buf.as_mut());
+        assert_eq!(
+            SshEncode::encode(&MsgExtensionFailure {}, &mut enc),
+            Some(())
+        );
+        assert_eq!([28], buf);
+    }
+
+    #[test]
+    fn test_decode_01() {
+        let buf = [28];
+        let res = Ok(MsgExtensionFailure {});
+        assert_eq!(res, SshCodec::decode(buf.as_ref()));
+    }
+
+    #[test]
+    fn test_decode_02() {
+        let buf = [0];
+        assert!(SshCodec::decode::<MsgExtensionFailure>(buf.as_ref()).is_err());
+    }
+}
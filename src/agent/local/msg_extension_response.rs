@@ -0,0 +1,42 @@
+use crate::transport::Message;
+use crate::util::codec::*;
+
+/// `SSH_AGENT_SUCCESS` followed by extension specific data.
+#[derive(Debug, PartialEq)]
+pub struct MsgExtensionResponse {
+    pub data: Vec<u8>,
+}
+
+impl Message for MsgExtensionResponse {
+    const NUMBER: u8 = 6;
+}
+
+impl SshEncode for MsgExtensionResponse {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_u8(<Self as Message>::NUMBER)?;
+        e.push_bytes(&self.data)
+    }
+}
+
+impl SshDecode for MsgExtensionResponse {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_u8(<Self as Message>::NUMBER)?;
+        let data = d.take_bytes_all()?.into();
+        Some(Self { data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_01() {
+        let msg = MsgExtensionResponse { data: vec![1, 2] };
+        let buf = [6, 1, 2];
+        assert_eq!(SshCodec::encode(&msg).unwrap(), buf);
+        assert_eq!(SshCodec::decode(&buf[..]), Ok(msg));
+        let msg = MsgExtensionResponse { data: vec![] };
+        assert_eq!(SshCodec::decode(&[6][..]), Ok(msg));
+    }
+}
@@ -0,0 +1,73 @@
+use crate::transport::Message;
+use crate::util::codec::*;
+
+/// `SSH_AGENTC_LOCK` (or `SSH_AGENTC_UNLOCK` if `lock` is `false`).
+#[derive(Debug, PartialEq)]
+pub struct MsgLock {
+    pub lock: bool,
+    pub passphrase: String,
+}
+
+impl MsgLock {
+    const NUMBER_UNLOCK: u8 = 23;
+}
+
+impl Message for MsgLock {
+    const NUMBER: u8 = 22;
+}
+
+impl SshEncode for MsgLock {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        if self.lock {
+            e.push_u8(<Self as Message>::NUMBER)?;
+        } else {
+            e.push_u8(Self::NUMBER_UNLOCK)?;
+        }
+        e.push_str_framed(&self.passphrase)
+    }
+}
+
+impl SshDecode for MsgLock {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        let lock = match d.take_u8()? {
+            <Self as Message>::NUMBER => true,
+            Self::NUMBER_UNLOCK => false,
+            _ => return None,
+        };
+        let passphrase = d.take_str_framed()?.into();
+        Some(Self { lock, passphrase })
+    }
+}
+
+impl Drop for MsgLock {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.passphrase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_01() {
+        let msg = MsgLock {
+            lock: true,
+            passphrase: "pw".into(),
+        };
+        let buf = [22, 0, 0, 0, 2, b'p', b'w'];
+        assert_eq!(SshCodec::encode(&msg).unwrap(), buf);
+        assert_eq!(SshCodec::decode(&buf[..]), Ok(msg));
+    }
+
+    #[test]
+    fn test_encode_decode_02() {
+        let msg = MsgLock {
+            lock: false,
+            passphrase: "pw".into(),
+        };
+        let buf = [23, 0, 0, 0, 2, b'p', b'w'];
+        assert_eq!(SshCodec::encode(&msg).unwrap(), buf);
+        assert_eq!(SshCodec::decode(&buf[..]), Ok(msg));
+    }
+}
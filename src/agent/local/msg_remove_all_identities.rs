@@ -0,0 +1,51 @@
+use crate::transport::Message;
+use crate::util::codec::*;
+
+#[derive(Debug, PartialEq)]
+pub struct MsgRemoveAllIdentities;
+
+impl Message for MsgRemoveAllIdentities {
+    const NUMBER: u8 = 19;
+}
+
+impl SshEncode for MsgRemoveAllIdentities {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_u8(<Self as Message>::NUMBER)
+    }
+}
+
+impl SshDecode for MsgRemoveAllIdentities {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_u8(<Self as Message>::NUMBER)?;
+        Some(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_01() {
+        let mut buf = [0; 1];
+        let mut enc = RefEncoder::new(buf.as_mut());
+        assert_eq!(
+            SshEncode::encode(&MsgRemoveAllIdentities {}, &mut enc),
+            Some(())
+        );
+        assert_eq!([19], buf);
+    }
+
+    #[test]
+    fn test_decode_01() {
+        let buf = [19];
+        let res = Ok(MsgRemoveAllIdentities {});
+        assert_eq!(res, SshCodec::decode(buf.as_ref()));
+    }
+
+    #[test]
+    fn test_decode_02() {
+        let buf = [0];
+        assert!(SshCodec::decode::<MsgRemoveAllIdentities>(buf.as_ref()).is_err());
+    }
+}
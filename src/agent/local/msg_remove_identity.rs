@@ -0,0 +1,42 @@
+use super::*;
+use crate::transport::Message;
+use crate::util::codec::*;
+
+#[derive(Debug, PartialEq)]
+pub struct MsgRemoveIdentity {
+    pub identity: Identity,
+}
+
+impl Message for MsgRemoveIdentity {
+    const NUMBER: u8 = 18;
+}
+
+impl SshEncode for MsgRemoveIdentity {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_u8(<Self as Message>::NUMBER)?;
+        e.push(&self.identity)
+    }
+}
+
+impl SshDecode for MsgRemoveIdentity {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_u8(<Self as Message>::NUMBER)?;
+        let identity = d.take()?;
+        Some(Self { identity })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_01() {
+        let msg = MsgRemoveIdentity {
+            identity: Identity::from(vec![1, 2, 3]),
+        };
+        let buf = [18, 0, 0, 0, 3, 1, 2, 3];
+        assert_eq!(SshCodec::encode(&msg).unwrap(), buf);
+        assert_eq!(SshCodec::decode(&buf[..]), Ok(msg));
+    }
+}
@@ -2,37 +2,45 @@ use super::*;
 use crate::transport::Message;
 use crate::util::codec::*;
 
-#[derive(Clone, Debug)]
-pub struct MsgSignRequest<'a> {
-    pub id: &'a Identity,
-    pub data: &'a [u8],
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgSignRequest {
+    pub id: Identity,
+    pub data: Vec<u8>,
     pub flags: u32,
 }
 
-impl<'a> Message for MsgSignRequest<'a> {
+impl Message for MsgSignRequest {
     const NUMBER: u8 = 13;
 }
 
-impl<'a> SshEncode for MsgSignRequest<'a> {
+impl SshEncode for MsgSignRequest {
     fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
         e.push_u8(<Self as Message>::NUMBER)?;
-        e.push(self.id)?;
-        e.push_bytes_framed(self.data)?;
+        e.push(&self.id)?;
+        e.push_bytes_framed(&self.data)?;
         e.push_u32be(self.flags)
     }
 }
 
+impl SshDecode for MsgSignRequest {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_u8(<Self as Message>::NUMBER)?;
+        let id = d.take()?;
+        let data = d.take_bytes_framed()?.into();
+        let flags = d.take_u32be()?;
+        Some(Self { id, data, flags })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_encode_01() {
-        let data: &'static str = "data";
-        let id = Identity::from(vec![1, 2, 3]);
-        let msg: MsgSignRequest = MsgSignRequest {
-            id: &id,
-            data: data.as_ref(),
+        let msg = MsgSignRequest {
+            id: Identity::from(vec![1, 2, 3]),
+            data: b"data".to_vec(),
             flags: 123,
         };
         assert_eq!(
@@ -40,4 +48,17 @@ mod tests {
             SshCodec::encode(&msg).unwrap()
         );
     }
+
+    #[test]
+    fn test_decode_01() {
+        let msg = MsgSignRequest {
+            id: Identity::from(vec![1, 2, 3]),
+            data: b"data".to_vec(),
+            flags: 123,
+        };
+        let buf = [
+            13, 0, 0, 0, 3, 1, 2, 3, 0, 0, 0, 4, 100, 97, 116, 97, 0, 0, 0, 123,
+        ];
+        assert_eq!(SshCodec::decode(&buf[..]), Ok(msg));
+    }
 }
@@ -0,0 +1,564 @@
+use super::*;
+use crate::util::secret::constant_time_eq;
+use crate::util::socket::Socket;
+use crate::util::BoxFuture;
+use std::io::ErrorKind;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::UnixListener;
+use zeroize::Zeroizing;
+
+/// Asks the user to confirm each use of a key added with [KeyConstraint::Confirm].
+pub trait AgentConfirm: std::fmt::Debug + Send + Sync + 'static {
+    /// Returns `true` if the user allows signing with the identity.
+    fn confirm(&self, identity: &Identity, comment: &str) -> BoxFuture<bool>;
+}
+
+/// The unit never confirms (keys with confirm constraint cannot be used).
+impl AgentConfirm for () {
+    fn confirm(&self, _: &Identity, _: &str) -> BoxFuture<bool> {
+        Box::pin(async { false })
+    }
+}
+
+/// The key management side of an agent served by [AgentServer].
+///
+/// All methods refuse by default, so any [AuthAgent] can be served read-only with an empty
+/// implementation.
+pub trait AgentBackend: AuthAgent {
+    /// Add a key (offered with the certificate if given).
+    ///
+    /// The constraints are enforced by the server, but backends that are agents themselves
+    /// should pass them on (so that keys expire even if the server doesn't run anymore).
+    /// Returns `false` if the backend refused the key.
+    fn add_identity(
+        &self,
+        _: PrivateKey,
+        _: Option<Identity>,
+        _: String,
+        _: Vec<KeyConstraint>,
+    ) -> AuthAgentFuture<bool> {
+        Box::pin(async { Ok(false) })
+    }
+
+    /// Whether the backend asks for confirmation of keys with confirm constraint itself.
+    ///
+    /// The server doesn't ask its [AgentConfirm] for such backends.
+    fn confirms(&self) -> bool {
+        false
+    }
+
+    /// Remove an identity (returns `false` if there was none or it cannot be removed).
+    fn remove_identity(&self, _: &Identity) -> AuthAgentFuture<bool> {
+        Box::pin(async { Ok(false) })
+    }
+
+    /// Remove all identities (returns `false` if refused).
+    fn remove_all_identities(&self) -> AuthAgentFuture<bool> {
+        Box::pin(async { Ok(false) })
+    }
+}
+
+/// The unit backend has no identities and refuses to add any.
+impl AgentBackend for () {}
+
+/// A shared agent is served read-only.
+impl AgentBackend for Arc<dyn AuthAgent> {}
+
+/// The local agent backend forwards all requests (and constraints) to another agent.
+impl AgentBackend for LocalAgent {
+    fn add_identity(
+        &self,
+        key: PrivateKey,
+        cert: Option<Identity>,
+        comment: String,
+        constraints: Vec<KeyConstraint>,
+    ) -> AuthAgentFuture<bool> {
+        let self_ = self.clone();
+        Box::pin(async move {
+            let c = &constraints;
+            match cert {
+                Some(cert) => self_.add_certificate(&key, &cert, &comment, c).await,
+                None => LocalAgent::add_identity(&self_, &key, &comment, c).await,
+            }
+        })
+    }
+
+    fn confirms(&self) -> bool {
+        true
+    }
+
+    fn remove_identity(&self, id: &Identity) -> AuthAgentFuture<bool> {
+        let self_ = self.clone();
+        let id = id.clone();
+        Box::pin(async move { LocalAgent::remove_identity(&self_, &id).await })
+    }
+
+    fn remove_all_identities(&self) -> AuthAgentFuture<bool> {
+        let self_ = self.clone();
+        Box::pin(async move { LocalAgent::remove_all_identities(&self_).await })
+    }
+}
+
+/// Serves an [AgentBackend] on unix domain sockets (like `ssh-agent` itself).
+///
+/// Locking and key constraints are enforced by the server: A locked server neither lists nor
+/// uses any identities, keys with lifetime are removed from the backend once expired and keys
+/// with confirm constraint are only used after [AgentConfirm] allowed it (unless the backend
+/// [confirms](AgentBackend::confirms) itself). Extension constraints are refused and extension
+/// requests are passed to the backend. Certificates are
+/// refused unless they match the private key. Clones share the same state.
+#[derive(Clone, Debug)]
+pub struct AgentServer {
+    backend: Arc<dyn AgentBackend>,
+    confirm: Arc<dyn AgentConfirm>,
+    state: Arc<Mutex<State>>,
+}
+
+impl AgentServer {
+    /// The delay per failed attempt to unlock (like `ssh-agent`).
+    const UNLOCK_DELAY: Duration = Duration::from_millis(100);
+    /// The number of failed attempts after which the delay stops growing (10s).
+    const UNLOCK_FAILURES_MAX: u32 = 100;
+
+    pub fn new(backend: Arc<dyn AgentBackend>, confirm: Arc<dyn AgentConfirm>) -> Self {
+        Self {
+            backend,
+            confirm,
+            state: Default::default(),
+        }
+    }
+
+    /// Accept connections and serve each of them in a separate task.
+    pub async fn listen(&self, listener: UnixListener) -> Result<(), std::io::Error> {
+        loop {
+            let (socket, _) = listener.accept().await?;
+            let self_ = self.clone();
+            drop(tokio::spawn(async move {
+                if let Err(e) = self_.serve(socket).await {
+                    log::warn!("Agent client: {}", e);
+                }
+            }));
+        }
+    }
+
+    /// Serve requests on a single connection until the client closes it.
+    pub async fn serve<S: Socket>(&self, socket: S) -> AuthAgentResult<()> {
+        let mut t: Transmitter<S> = socket.into();
+        loop {
+            let frame = match t.receive_frame().await {
+                Ok(frame) => frame,
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e.into()),
+            };
+            let response = match self.handle(Request::decode(&frame)).await {
+                Ok(response) => response,
+                Err(e) => {
+                    log::debug!("Agent backend: {}", e);
+                    Response::Failure
+                }
+            };
+            t.send(&response).await?;
+        }
+    }
+
+    async fn handle(&self, request: Request) -> AuthAgentResult<Response> {
+        self.expire().await;
+        let locked = self.state.lock().unwrap().lock.is_some();
+        match request {
+            Request::Lock(msg) => Ok(self.lock(&msg).await),
+            Request::Identities if locked => Ok(Response::Identities(MsgIdentitiesAnswer {
+                identities: vec![],
+            })),
+            _ if locked => Ok(Response::Failure),
+            Request::Identities => {
+                let identities = self.backend.identities().await?;
+                Ok(Response::Identities(MsgIdentitiesAnswer { identities }))
+            }
+            Request::Sign(msg) => self.sign(msg).await,
+            Request::Add(msg) => self.add(msg).await,
+            Request::Remove(msg) => {
+                let removed = self.backend.remove_identity(&msg.identity).await?;
+                let mut state = self.state.lock().unwrap();
+                state.constrained.retain(|c| c.identity != msg.identity);
+                Ok(Response::from(removed))
+            }
+            Request::RemoveAll => {
+                let removed = self.backend.remove_all_identities().await?;
+                self.state.lock().unwrap().constrained.clear();
+                Ok(Response::from(removed))
+            }
+            Request::Extension(msg) => {
+                let data = self.backend.extension(&msg.name, &msg.data).await?;
+                Ok(data.map_or(Response::Failure, |data| {
+                    Response::Extension(MsgExtensionResponse { data })
+                }))
+            }
+            Request::Other => Ok(Response::Failure),
+        }
+    }
+
+    async fn sign(&self, msg: MsgSignRequest) -> AuthAgentResult<Response> {
+        let confirm = {
+            let state = self.state.lock().unwrap();
+            let c = state.constrained.iter().find(|c| c.identity == msg.id);
+            c.filter(|c| c.confirm).map(|c| c.comment.clone())
+        };
+        if let Some(comment) = confirm {
+            if !self.confirm.confirm(&msg.id, &comment).await {
+                return Ok(Response::Failure);
+            }
+        }
+        let sig = self
+            .backend
+            .signature(&msg.id, &msg.data, msg.flags)
+            .await?;
+        Ok(sig.map_or(Response::Failure, |signature| {
+            Response::Signature(MsgSignResponse { signature })
+        }))
+    }
+
+    async fn add(&self, msg: MsgAddIdentity) -> AuthAgentResult<Response> {
+        let mut confirm = false;
+        let mut lifetime = None;
+        for constraint in &msg.constraints {
+            match constraint {
+                KeyConstraint::Lifetime(secs) => lifetime = Some(Duration::from_secs(*secs as u64)),
+                KeyConstraint::Confirm => confirm = !self.backend.confirms(),
+                KeyConstraint::Extension(..) => return Ok(Response::Failure),
+            }
+        }
+        let identity = match &msg.cert {
+            Some(cert) if !cert.is_same_key(&msg.key.identity()) => return Ok(Response::Failure),
+            Some(cert) => cert.clone(),
+            None => msg.key.identity(),
+        };
+        let comment = msg.comment.clone();
+        if !self
+            .backend
+            .add_identity(msg.key, msg.cert, msg.comment, msg.constraints)
+            .await?
+        {
+            return Ok(Response::Failure);
+        }
+        let mut state = self.state.lock().unwrap();
+        state.constrained.retain(|c| c.identity != identity);
+        if confirm || lifetime.is_some() {
+            state.constrained.push(Constrained {
+                identity,
+                comment,
+                confirm,
+                expires: lifetime.map(|x| Instant::now() + x),
+            });
+        }
+        // Remove the key once expired even if there are no more requests
+        if let Some(lifetime) = lifetime {
+            let self_ = self.clone();
+            drop(tokio::spawn(async move {
+                tokio::time::sleep(lifetime).await;
+                self_.expire().await;
+            }));
+        }
+        Ok(Response::Success)
+    }
+
+    /// Lock or unlock the agent.
+    ///
+    /// Failed attempts to unlock are answered with an increasing delay (like `ssh-agent`) in
+    /// order to slow down guessing the passphrase.
+    async fn lock(&self, msg: &MsgLock) -> Response {
+        let delay = {
+            let mut state = self.state.lock().unwrap();
+            match (&state.lock, msg.lock) {
+                (None, true) => {
+                    state.lock = Some(Zeroizing::new(msg.passphrase.clone()));
+                    return Response::Success;
+                }
+                (Some(passphrase), false)
+                    if constant_time_eq(passphrase.as_bytes(), msg.passphrase.as_bytes()) =>
+                {
+                    state.lock = None;
+                    state.unlock_failures = 0;
+                    return Response::Success;
+                }
+                (Some(_), false) => {
+                    state.unlock_failures =
+                        (state.unlock_failures + 1).min(Self::UNLOCK_FAILURES_MAX);
+                    Self::UNLOCK_DELAY * state.unlock_failures
+                }
+                _ => return Response::Failure,
+            }
+        };
+        log::debug!("Agent: Failed to unlock");
+        tokio::time::sleep(delay).await;
+        Response::Failure
+    }
+
+    /// Remove all keys whose lifetime has expired from the backend.
+    async fn expire(&self) {
+        let now = Instant::now();
+        let expired: Vec<Identity> = {
+            let mut state = self.state.lock().unwrap();
+            let (expired, constrained) = std::mem::take(&mut state.constrained)
+                .into_iter()
+                .partition(|c| c.expires.is_some_and(|t| t <= now));
+            state.constrained = constrained;
+            expired
+                .into_iter()
+                .map(|c: Constrained| c.identity)
+                .collect()
+        };
+        for identity in expired {
+            if let Err(e) = self.backend.remove_identity(&identity).await {
+                log::debug!("Agent backend: {}", e);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The passphrase if locked
+    lock: Option<Zeroizing<String>>,
+    /// Identities added with constraints
+    constrained: Vec<Constrained>,
+    /// The number of failed attempts to unlock (since the last success)
+    unlock_failures: u32,
+}
+
+#[derive(Debug)]
+struct Constrained {
+    identity: Identity,
+    comment: String,
+    confirm: bool,
+    expires: Option<Instant>,
+}
+
+enum Request {
+    Identities,
+    Sign(MsgSignRequest),
+    Add(MsgAddIdentity),
+    Remove(MsgRemoveIdentity),
+    RemoveAll,
+    Lock(MsgLock),
+    Extension(MsgExtension),
+    Other,
+}
+
+impl Request {
+    fn decode(frame: &[u8]) -> Self {
+        if SshCodec::decode::<MsgIdentitiesRequest>(frame).is_ok() {
+            Self::Identities
+        } else if let Ok(msg) = SshCodec::decode(frame) {
+            Self::Sign(msg)
+        } else if let Ok(msg) = SshCodec::decode(frame) {
+            Self::Add(msg)
+        } else if let Ok(msg) = SshCodec::decode(frame) {
+            Self::Remove(msg)
+        } else if SshCodec::decode::<MsgRemoveAllIdentities>(frame).is_ok() {
+            Self::RemoveAll
+        } else if let Ok(msg) = SshCodec::decode(frame) {
+            Self::Lock(msg)
+        } else if let Ok(msg) = SshCodec::decode(frame) {
+            Self::Extension(msg)
+        } else {
+            Self::Other
+        }
+    }
+}
+
+enum Response {
+    Success,
+    Failure,
+    Identities(MsgIdentitiesAnswer),
+    Signature(MsgSignResponse),
+    Extension(MsgExtensionResponse),
+}
+
+impl From<bool> for Response {
+    fn from(success: bool) -> Self {
+        if success {
+            Self::Success
+        } else {
+            Self::Failure
+        }
+    }
+}
+
+impl SshEncode for Response {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        match self {
+            Self::Success => e.push(&MsgSuccess),
+            Self::Failure => e.push(&MsgFailure),
+            Self::Identities(msg) => e.push(msg),
+            Self::Signature(msg) => e.push(msg),
+            Self::Extension(msg) => e.push(msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::testing::*;
+    use crate::agent::KeyStore;
+
+    #[derive(Debug)]
+    struct Confirm(bool);
+
+    impl AgentConfirm for Confirm {
+        fn confirm(&self, _: &Identity, _: &str) -> BoxFuture<bool> {
+            let x = self.0;
+            Box::pin(async move { x })
+        }
+    }
+
+    /// Start a server with the given backend and return a client connected to it.
+    fn agent(backend: Arc<dyn AgentBackend>, confirm: bool) -> LocalAgent {
+        let (listener, path) = listen();
+        let server = AgentServer::new(backend, Arc::new(Confirm(confirm)));
+        drop(tokio::spawn(async move { server.listen(listener).await }));
+        LocalAgent::new(&path)
+    }
+
+    #[tokio::test]
+    async fn test_identities_signature_01() {
+        let store = KeyStore::new();
+        store.add(key(1), "one");
+        let agent = agent(Arc::new(store), false);
+        let id = key(1).identity();
+        let ids = agent.identities().await.unwrap();
+        assert_eq!(ids, vec![(id.clone(), "one".into())]);
+        let sig = agent.signature(&id, b"data", 0).await.unwrap().unwrap();
+        assert!(sig.verify(&id, b"data").is_ok());
+        let sig = agent.signature(&key(2).identity(), b"data", 0).await;
+        assert!(sig.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_add_remove_01() {
+        let agent = agent(Arc::new(KeyStore::new()), false);
+        assert!(agent.add_identity(&key(1), "one", &[]).await.unwrap());
+        assert!(agent.add_identity(&key(2), "two", &[]).await.unwrap());
+        assert_eq!(agent.identities().await.unwrap().len(), 2);
+        assert!(agent.remove_identity(&key(1).identity()).await.unwrap());
+        assert!(!agent.remove_identity(&key(1).identity()).await.unwrap());
+        assert_eq!(agent.identities().await.unwrap().len(), 1);
+        assert!(agent.remove_all_identities().await.unwrap());
+        assert!(agent.identities().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_add_read_only_01() {
+        let agent = agent(Arc::new(()), false);
+        assert!(!agent.add_identity(&key(1), "one", &[]).await.unwrap());
+        assert!(!agent.remove_all_identities().await.unwrap());
+        assert!(agent.extension("query", &[]).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lock_01() {
+        let agent = agent(Arc::new(KeyStore::new()), false);
+        assert!(agent.add_identity(&key(1), "one", &[]).await.unwrap());
+        assert!(agent.lock("secret").await.unwrap());
+        assert!(!agent.lock("secret").await.unwrap());
+        assert!(agent.identities().await.unwrap().is_empty());
+        let sig = agent.signature(&key(1).identity(), b"data", 0).await;
+        assert!(sig.unwrap().is_none());
+        assert!(!agent.add_identity(&key(2), "two", &[]).await.unwrap());
+        let start = Instant::now();
+        assert!(!agent.unlock("wrong").await.unwrap());
+        assert!(start.elapsed() >= AgentServer::UNLOCK_DELAY);
+        assert!(!agent.unlock("secreT").await.unwrap());
+        assert!(start.elapsed() >= AgentServer::UNLOCK_DELAY * 3);
+        assert!(agent.unlock("secret").await.unwrap());
+        assert!(!agent.unlock("secret").await.unwrap());
+        assert_eq!(agent.identities().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_constraints_01() {
+        let agent = agent(Arc::new(KeyStore::new()), false);
+        let c = [KeyConstraint::Lifetime(0)];
+        assert!(agent.add_identity(&key(1), "one", &c).await.unwrap());
+        assert!(agent.identities().await.unwrap().is_empty());
+        let c = [KeyConstraint::Extension("x@example.com".into(), vec![])];
+        assert!(!agent.add_identity(&key(1), "one", &c).await.unwrap());
+        let c = [KeyConstraint::Lifetime(60), KeyConstraint::Confirm];
+        assert!(agent.add_identity(&key(1), "one", &c).await.unwrap());
+        assert_eq!(agent.identities().await.unwrap().len(), 1);
+        let sig = agent.signature(&key(1).identity(), b"data", 0).await;
+        assert!(sig.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_constraints_02() {
+        let agent = agent(Arc::new(KeyStore::new()), true);
+        let c = [KeyConstraint::Confirm];
+        assert!(agent.add_identity(&key(1), "one", &c).await.unwrap());
+        let sig = agent.signature(&key(1).identity(), b"data", 0).await;
+        assert!(sig.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_constraints_03() {
+        let store = KeyStore::new();
+        let agent = agent(Arc::new(store.clone()), false);
+        let c = [KeyConstraint::Lifetime(1)];
+        assert!(agent.add_identity(&key(1), "one", &c).await.unwrap());
+        assert_eq!(store.identities().await.unwrap().len(), 1);
+        // The key expires without further requests
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(store.identities().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_constraints_04() {
+        let inner = AgentServer::new(Arc::new(KeyStore::new()), Arc::new(Confirm(true)));
+        let (listener, path) = listen();
+        let inner_ = inner.clone();
+        drop(tokio::spawn(async move { inner_.listen(listener).await }));
+        let agent = agent(Arc::new(LocalAgent::new(&path)), false);
+        let c = [KeyConstraint::Lifetime(60), KeyConstraint::Confirm];
+        assert!(agent.add_identity(&key(1), "one", &c).await.unwrap());
+        {
+            // The constraints are passed on to the backend agent
+            let state = inner.state.lock().unwrap();
+            assert_eq!(state.constrained.len(), 1);
+            assert!(state.constrained[0].confirm);
+            assert!(state.constrained[0].expires.is_some());
+        }
+        // The backend agent asks for confirmation instead of the server
+        let sig = agent.signature(&key(1).identity(), b"data", 0).await;
+        assert!(sig.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_certificate_01() {
+        let cert = include_str!("../../../resources/ed25519-user-cert.pub");
+        let cert: Identity = cert.parse().unwrap();
+        let key = include_str!("../../../resources/ed25519-user");
+        let key = PrivateKey::from_openssh(key, None).unwrap().remove(0).0;
+        let agent = agent(Arc::new(KeyStore::new()), false);
+        assert!(agent
+            .add_certificate(&key, &cert, "cert", &[])
+            .await
+            .unwrap());
+        let ids = agent.identities().await.unwrap();
+        assert_eq!(ids, vec![(cert.clone(), "cert".into())]);
+        let sig = agent.signature(&cert, b"data", 0).await.unwrap();
+        assert!(sig.unwrap().verify(&key.identity(), b"data").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_certificate_02() {
+        let cert = include_str!("../../../resources/ed25519-user-cert.pub");
+        let cert: Identity = cert.parse().unwrap();
+        let agent = agent(Arc::new(KeyStore::new()), false);
+        assert!(!agent
+            .add_certificate(&key(1), &cert, "cert", &[])
+            .await
+            .unwrap());
+        assert!(agent.identities().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,86 @@
+use crate::identity::{Identity, Signature};
+use crate::util::codec::*;
+
+/// The data of the `session-bind@openssh.com` extension request.
+///
+/// A client binds its agent connection to a session by presenting the server's host key and
+/// the host key's signature of the session id (taken from the initial key exchange). This
+/// allows the agent to restrict keys to certain destinations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionBind {
+    pub host_key: Identity,
+    pub session_id: Vec<u8>,
+    pub signature: Signature,
+    /// Whether the agent is going to be forwarded over this session
+    pub forwarding: bool,
+}
+
+impl SessionBind {
+    /// The extension name.
+    pub const NAME: &'static str = "session-bind@openssh.com";
+
+    /// Check that the signature of the session id has been created with the host key.
+    pub fn verify(&self) -> bool {
+        self.signature
+            .verify(&self.host_key, &self.session_id)
+            .is_ok()
+    }
+}
+
+impl SshEncode for SessionBind {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push(&self.host_key)?;
+        e.push_bytes_framed(&self.session_id)?;
+        e.push(&self.signature)?;
+        e.push_bool(self.forwarding)
+    }
+}
+
+impl SshDecode for SessionBind {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        let host_key = d.take()?;
+        let session_id = d.take_bytes_framed()?.into();
+        let signature = d.take()?;
+        let forwarding = d.take_bool()?;
+        Some(Self {
+            host_key,
+            session_id,
+            signature,
+            forwarding,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::ssh_ed25519::SshEd25519PrivateKey;
+    use crate::identity::PrivateKey;
+
+    #[test]
+    fn test_encode_decode_01() {
+        let key: PrivateKey = SshEd25519PrivateKey::new([1; 32]).into();
+        let msg = SessionBind {
+            host_key: key.identity(),
+            session_id: vec![1, 2, 3],
+            signature: key.sign(&[1, 2, 3]),
+            forwarding: true,
+        };
+        let buf = SshCodec::encode(&msg).unwrap();
+        assert_eq!(buf.last(), Some(&1));
+        assert_eq!(SshCodec::decode(&buf), Ok(msg.clone()));
+        assert!(msg.verify());
+    }
+
+    #[test]
+    fn test_verify_01() {
+        let key: PrivateKey = SshEd25519PrivateKey::new([1; 32]).into();
+        let msg = SessionBind {
+            host_key: SshEd25519PrivateKey::new([2; 32]).identity(),
+            session_id: vec![1, 2, 3],
+            signature: key.sign(&[1, 2, 3]),
+            forwarding: false,
+        };
+        assert!(!msg.verify());
+    }
+}
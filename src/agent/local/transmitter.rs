@@ -2,34 +2,49 @@ use super::AuthAgentError;
 use super::Frame;
 use crate::util::check;
 use crate::util::codec::*;
-use crate::util::socket::{read_exact, write_all, Socket};
+use crate::util::socket::{write_all, Socket};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::UnixStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::timeout;
+
+const MAX_FRAME_LEN: usize = 35000;
 
 pub struct Transmitter<S: Socket = UnixStream> {
     socket: S,
 }
 
 impl<S: Socket> Transmitter<S> {
-    const MAX_FRAME_LEN: usize = 35000;
-
     pub async fn send<Msg: SshEncode>(&mut self, msg: &Msg) -> Result<(), AuthAgentError> {
         let vec = SshCodec::encode(&Frame(msg)).map_err(AuthAgentError::new)?;
         write_all(&mut self.socket, &vec).await?;
         Ok(())
     }
 
+    /// Send a request and receive the response.
+    pub async fn request<Req: SshEncode, Res: SshDecode>(
+        &mut self,
+        msg: &Req,
+    ) -> Result<Res, AuthAgentError> {
+        self.send(msg).await?;
+        self.receive().await
+    }
+
     pub async fn receive<Msg: SshDecode>(&mut self) -> Result<Msg, AuthAgentError> {
-        let mut len: [u8; 4] = [0; 4];
-        read_exact(&mut self.socket, &mut len[..]).await?;
-        let len = u32::from_be_bytes(len) as usize;
-        check(len <= Self::MAX_FRAME_LEN)
-            .ok_or(SshCodecError::DecodingFailed)
-            .map_err(AuthAgentError::new)?;
-        let mut vec = Vec::with_capacity(len);
-        vec.resize(len, 0);
-        read_exact(&mut self.socket, &mut vec[..]).await?;
+        let vec = self.receive_frame().await?;
         SshCodec::decode(&vec).map_err(AuthAgentError::new)
     }
+
+    /// Receive the payload of the next frame without decoding it.
+    pub async fn receive_frame(&mut self) -> Result<Vec<u8>, std::io::Error> {
+        read_frame(&mut self.socket).await
+    }
 }
 
 impl<S: Socket> From<S> for Transmitter<S> {
@@ -37,3 +52,226 @@ impl<S: Socket> From<S> for Transmitter<S> {
         Self { socket }
     }
 }
+
+/// A persistent connection to an agent socket with a queue of pending requests.
+///
+/// The connection is established on first use and shared by all requests. Requests are
+/// pipelined: They are written as soon as they get queued and the responses are assigned in
+/// order (the agent answers strictly in order). The connection is dropped on any error or if
+/// the agent does not respond within the timeout and re-established by the next request. A
+/// request failing because the agent closed the idle connection (e.g. after a restart) is
+/// retried once on a new connection.
+#[derive(Debug)]
+pub struct TransmitterQueue {
+    path: PathBuf,
+    timeout: Duration,
+    connection: Mutex<Option<QueueConnection>>,
+}
+
+#[derive(Debug)]
+struct QueueConnection {
+    sender: mpsc::UnboundedSender<Job>,
+    /// Set by the connection tasks before reporting an error (so that the next request will
+    /// not be queued for the broken connection)
+    broken: Arc<AtomicBool>,
+}
+
+type Reply = oneshot::Sender<Result<Vec<u8>, JobError>>;
+
+#[derive(Debug)]
+struct Job {
+    frame: Vec<u8>,
+    reply: Reply,
+}
+
+#[derive(Debug)]
+struct JobError {
+    error: AuthAgentError,
+    /// The agent closed the connection while idle (the job may be retried on a new one)
+    stale: bool,
+}
+
+impl From<AuthAgentError> for JobError {
+    fn from(error: AuthAgentError) -> Self {
+        Self {
+            error,
+            stale: false,
+        }
+    }
+}
+
+/// The context shared by the tasks of a connection.
+#[derive(Clone)]
+struct Context {
+    broken: Arc<AtomicBool>,
+    timeout: Duration,
+    /// The number of requests written but not yet answered
+    outstanding: Arc<AtomicUsize>,
+}
+
+impl Context {
+    fn fail(&self, rx: &mut mpsc::UnboundedReceiver<Job>, e: AuthAgentError) {
+        self.broken.store(true, Ordering::SeqCst);
+        rx.close();
+        while let Ok(job) = rx.try_recv() {
+            let _ = job.reply.send(Err(e.clone().into()));
+        }
+    }
+}
+
+impl TransmitterQueue {
+    pub fn new(path: PathBuf, timeout: Duration) -> Self {
+        Self {
+            path,
+            timeout,
+            connection: Mutex::new(None),
+        }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Queue a request and wait for the response.
+    pub async fn request<Req: SshEncode, Res: SshDecode>(
+        &self,
+        msg: &Req,
+    ) -> Result<Res, AuthAgentError> {
+        let frame = SshCodec::encode(&Frame(msg)).map_err(AuthAgentError::new)?;
+        let result = match self.submit(frame.clone()).await {
+            Err(JobError { stale: true, .. }) => {
+                log::debug!("Agent closed idle connection: Reconnecting");
+                self.submit(frame).await
+            }
+            result => result,
+        };
+        let vec = result.map_err(|e| e.error)?;
+        SshCodec::decode(&vec).map_err(AuthAgentError::new)
+    }
+
+    /// Queue a frame and wait for the response frame.
+    async fn submit(&self, frame: Vec<u8>) -> Result<Vec<u8>, JobError> {
+        let (reply, response) = oneshot::channel();
+        self.enqueue(Job { frame, reply });
+        let closed = || AuthAgentError::from(std::io::Error::from(ErrorKind::ConnectionAborted));
+        response.await.map_err(|_| JobError::from(closed()))?
+    }
+
+    /// Pass the job to the current connection (or start a new one).
+    fn enqueue(&self, mut job: Job) {
+        let mut connection = self.connection.lock().unwrap();
+        if let Some(c) = connection.as_ref() {
+            if !c.broken.load(Ordering::SeqCst) {
+                match c.sender.send(job) {
+                    Ok(()) => return,
+                    Err(e) => job = e.0,
+                }
+            }
+        }
+        let (sender, rx) = mpsc::unbounded_channel();
+        let _ = sender.send(job);
+        let broken = Arc::new(AtomicBool::new(false));
+        let cx = Context {
+            broken: broken.clone(),
+            timeout: self.timeout,
+            outstanding: Default::default(),
+        };
+        *connection = Some(QueueConnection { sender, broken });
+        drop(tokio::spawn(Self::connect(cx, self.path.clone(), rx)));
+    }
+
+    async fn connect(cx: Context, path: PathBuf, mut rx: mpsc::UnboundedReceiver<Job>) {
+        let socket = match timeout(cx.timeout, UnixStream::connect(&path)).await {
+            Ok(Ok(socket)) => socket,
+            Ok(Err(e)) => return cx.fail(&mut rx, e.into()),
+            Err(_) => return cx.fail(&mut rx, Self::timed_out()),
+        };
+        let (r, w) = socket.into_split();
+        let (replies_tx, replies_rx) = mpsc::unbounded_channel();
+        drop(tokio::spawn(Self::receive(cx.clone(), r, replies_rx)));
+        Self::send(cx, w, rx, replies_tx).await
+    }
+
+    /// Write the queued requests and pass their reply channels to the receiver.
+    ///
+    /// Each reply channel comes with whether the connection was idle when the request was
+    /// written (after other requests had been answered). Terminates as soon as the receiver
+    /// terminates.
+    async fn send(
+        cx: Context,
+        mut w: OwnedWriteHalf,
+        mut rx: mpsc::UnboundedReceiver<Job>,
+        replies: mpsc::UnboundedSender<(Reply, bool)>,
+    ) {
+        let mut used = false;
+        loop {
+            let job = tokio::select! {
+                job = rx.recv() => job,
+                _ = replies.closed() => None,
+            };
+            let Some(job) = job else { return };
+            let outstanding = cx.outstanding.fetch_add(1, Ordering::SeqCst);
+            let idle = used && outstanding == 0;
+            used = true;
+            let e = match timeout(cx.timeout, w.write_all(&job.frame)).await {
+                Ok(Ok(())) => match replies.send((job.reply, idle)) {
+                    Ok(()) => continue,
+                    Err(_) => return,
+                },
+                Ok(Err(e)) => Self::job_error(e, idle),
+                Err(_) => Self::timed_out().into(),
+            };
+            cx.fail(&mut rx, e.error.clone());
+            let _ = job.reply.send(Err(e));
+            return;
+        }
+    }
+
+    /// Read the responses and dispatch them in order.
+    ///
+    /// Terminates on the first error (dropping the remaining replies aborts their requests).
+    async fn receive(
+        cx: Context,
+        mut r: OwnedReadHalf,
+        mut replies: mpsc::UnboundedReceiver<(Reply, bool)>,
+    ) {
+        while let Some((reply, idle)) = replies.recv().await {
+            let result = match timeout(cx.timeout, read_frame(&mut r)).await {
+                Ok(Ok(frame)) => Ok(frame),
+                Ok(Err(e)) => Err(Self::job_error(e, idle)),
+                Err(_) => Err(Self::timed_out().into()),
+            };
+            cx.outstanding.fetch_sub(1, Ordering::SeqCst);
+            if result.is_err() {
+                cx.broken.store(true, Ordering::SeqCst);
+                let _ = reply.send(result);
+                return;
+            }
+            let _ = reply.send(result);
+        }
+    }
+
+    /// An I/O error is stale if the agent closed the connection while it was idle.
+    fn job_error(e: std::io::Error, idle: bool) -> JobError {
+        let closed = matches!(
+            e.kind(),
+            ErrorKind::UnexpectedEof | ErrorKind::BrokenPipe | ErrorKind::ConnectionReset
+        );
+        JobError {
+            error: e.into(),
+            stale: idle && closed,
+        }
+    }
+
+    fn timed_out() -> AuthAgentError {
+        std::io::Error::new(ErrorKind::TimedOut, "agent did not respond").into()
+    }
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(r: &mut R) -> Result<Vec<u8>, std::io::Error> {
+    let len = r.read_u32().await? as usize;
+    check(len <= MAX_FRAME_LEN).ok_or(ErrorKind::InvalidData)?;
+    let mut vec = vec![0; len];
+    r.read_exact(&mut vec[..]).await?;
+    Ok(vec)
+}
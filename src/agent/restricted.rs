@@ -0,0 +1,409 @@
+use super::*;
+use crate::user_auth::UserAuth;
+use crate::util::codec::*;
+use std::sync::Mutex;
+
+/// Allows identities to be used for a hop to a destination host (like `ssh-add -h`).
+#[derive(Clone, Debug)]
+pub struct DestinationRule {
+    /// The host name (for diagnostics only)
+    pub host: String,
+    /// The keys of the host the agent has been forwarded to (empty for the first hop, i.e.
+    /// sessions of the local client)
+    pub from_host_keys: Vec<Identity>,
+    /// The keys of the host (the session must be bound to one of them)
+    pub host_keys: Vec<Identity>,
+    /// The user to authenticate as (any user if `None`)
+    pub user: Option<String>,
+    /// The identities that may be used at this destination
+    pub identities: Vec<Identity>,
+}
+
+impl DestinationRule {
+    /// Whether the identity may be used for the hop (to any host if `to` is `None`).
+    fn allows(&self, from: Option<&Identity>, to: Option<&Identity>, identity: &Identity) -> bool {
+        let from = match from {
+            None => self.from_host_keys.is_empty(),
+            Some(key) => self.from_host_keys.contains(key),
+        };
+        from && to.is_none_or(|key| self.host_keys.contains(key))
+            && self.identities.contains(identity)
+    }
+}
+
+/// An [AuthAgent] decorator that restricts the use of keys to certain destinations.
+///
+/// The destination is learned from the `session-bind@openssh.com` extension: Clients bind
+/// their agent connection to a session before authentication by presenting the server's host
+/// key and its signature of the session id. Forwarded agent connections carry the bindings of
+/// all previous hops. Like `ssh-agent`, each hop (from the host of the previous binding to the
+/// host of the next one) must be allowed by a rule for an identity to be listed. Identities
+/// only sign `publickey` authentication requests for exactly the last bound session (and user,
+/// if the rule of the last hop names one). Requests of forwarded connections must be
+/// `publickey-hostbound-v00@openssh.com` requests naming the last bound host key. Nothing is
+/// offered before a session has been bound.
+///
+/// Bindings belong to the agent connection they were received on, so each connection (e.g.
+/// each accepted [AuthAgentRequest](crate::connection::AuthAgentRequest)) shall be served by
+/// its own instance (see [unbound](Self::unbound)).
+#[derive(Debug)]
+pub struct RestrictedAgent {
+    agent: Arc<dyn AuthAgent>,
+    rules: Arc<Vec<DestinationRule>>,
+    bindings: Mutex<Vec<SessionBind>>,
+}
+
+impl RestrictedAgent {
+    /// The maximum number of sessions bound to a connection (like `ssh-agent`).
+    const MAX_BINDINGS: usize = 16;
+    /// The `publickey` method variant that includes the server's host key (OpenSSH 8.9).
+    const PUBLICKEY_HOSTBOUND: &'static str = "publickey-hostbound-v00@openssh.com";
+
+    pub fn new(agent: Arc<dyn AuthAgent>) -> Self {
+        Self {
+            agent,
+            rules: Default::default(),
+            bindings: Default::default(),
+        }
+    }
+
+    /// Add a rule (identities without any rule are never offered).
+    pub fn allow(mut self, rule: DestinationRule) -> Self {
+        Arc::make_mut(&mut self.rules).push(rule);
+        self
+    }
+
+    /// Create an instance with the same agent and rules, but without any bound sessions.
+    pub fn unbound(&self) -> Self {
+        Self {
+            agent: self.agent.clone(),
+            rules: self.rules.clone(),
+            bindings: Default::default(),
+        }
+    }
+
+    /// Bind a session (returns `false` if the binding is refused).
+    ///
+    /// The binding is refused if the signature is invalid, the session id is already bound to
+    /// another host key or the last session has not been bound for forwarding.
+    pub fn bind(&self, bind: SessionBind) -> bool {
+        if !bind.verify() {
+            log::debug!("Session bind: Invalid signature");
+            return false;
+        }
+        let mut bindings = self.bindings.lock().unwrap();
+        if let Some(b) = bindings.iter().find(|b| b.session_id == bind.session_id) {
+            return b.host_key == bind.host_key;
+        }
+        if bindings.last().is_some_and(|b| !b.forwarding) {
+            log::debug!("Session bind: Connection already bound for authentication");
+            return false;
+        }
+        if bindings.len() >= Self::MAX_BINDINGS {
+            return false;
+        }
+        bindings.push(bind);
+        true
+    }
+
+    /// Check whether the data is an authentication request the identity may sign.
+    fn permits(&self, id: &Identity, data: &[u8]) -> bool {
+        let bindings = self.bindings.lock().unwrap();
+        let Some(last) = bindings.last() else {
+            return false;
+        };
+        let Some((sid, user, identity, host_key)) = Self::parse_request(data) else {
+            return false;
+        };
+        // Only the first hop may omit the host key (plain `publickey` request)
+        let host_key = match host_key {
+            Some(host_key) => host_key == last.host_key,
+            None => bindings.len() == 1,
+        };
+        sid == last.session_id
+            && identity == *id
+            && host_key
+            && Self::permitted(&self.rules, &bindings, id, Some(&user))
+    }
+
+    /// Check each hop of the bound sessions against the rules (like `ssh-agent`).
+    ///
+    /// The user is only checked for the last hop. Without user (i.e. for listing), identities
+    /// of connections bound for forwarding must also be allowed beyond the last host.
+    fn permitted(
+        rules: &[DestinationRule],
+        bindings: &[SessionBind],
+        id: &Identity,
+        user: Option<&str>,
+    ) -> bool {
+        let Some(last) = bindings.last() else {
+            return false;
+        };
+        if last.forwarding && user.is_some() {
+            return false;
+        }
+        let mut from = None;
+        for (i, bind) in bindings.iter().enumerate() {
+            let user = user.filter(|_| i + 1 == bindings.len());
+            let allowed = rules.iter().any(|r| {
+                r.allows(from, Some(&bind.host_key), id)
+                    && user.is_none_or(|u| r.user.as_ref().is_none_or(|x| x == u))
+            });
+            if !allowed {
+                return false;
+            }
+            from = Some(&bind.host_key);
+        }
+        !last.forwarding || rules.iter().any(|r| r.allows(from, None, id))
+    }
+
+    /// Parse the session id, user name, identity and host key (if hostbound) from signature
+    /// data of a `publickey` authentication request.
+    fn parse_request(data: &[u8]) -> Option<(Vec<u8>, String, Identity, Option<Identity>)> {
+        const SSH_MSG_USERAUTH_REQUEST: u8 = 50;
+        let mut d = RefDecoder::new(data);
+        let session_id = d.take_bytes_framed()?.into();
+        d.expect_u8(SSH_MSG_USERAUTH_REQUEST)?;
+        let user = d.take_str_framed()?.into();
+        let _service = d.take_str_framed()?;
+        let hostbound = match d.take_str_framed()? {
+            UserAuth::PUBLICKEY => false,
+            Self::PUBLICKEY_HOSTBOUND => true,
+            _ => return None,
+        };
+        d.expect_true()?;
+        let _algorithm = d.take_str_framed()?;
+        let identity = d.take()?;
+        let host_key = if hostbound { Some(d.take()?) } else { None };
+        d.expect_eoi()?;
+        Some((session_id, user, identity, host_key))
+    }
+}
+
+impl AuthAgent for RestrictedAgent {
+    fn identities(&self) -> AuthAgentFuture<Vec<(Identity, String)>> {
+        let agent = self.agent.clone();
+        let rules = self.rules.clone();
+        let bindings = self.bindings.lock().unwrap().clone();
+        Box::pin(async move {
+            if bindings.is_empty() {
+                return Ok(vec![]);
+            }
+            let ids = agent.identities().await?;
+            Ok(ids
+                .into_iter()
+                .filter(|(id, _)| Self::permitted(&rules, &bindings, id, None))
+                .collect())
+        })
+    }
+
+    fn signature(
+        &self,
+        id: &Identity,
+        data: &[u8],
+        flags: u32,
+    ) -> AuthAgentFuture<Option<Signature>> {
+        if !self.permits(id, data) {
+            log::debug!("Refused to sign for unbound or disallowed destination");
+            return Box::pin(async { Ok(None) });
+        }
+        self.agent.signature(id, data, flags)
+    }
+
+    /// Only `session-bind@openssh.com` is supported (other extensions are not passed on).
+    fn extension(&self, name: &str, data: &[u8]) -> AuthAgentFuture<Option<Vec<u8>>> {
+        let bound =
+            name == SessionBind::NAME && SshCodec::decode(data).is_ok_and(|bind| self.bind(bind));
+        Box::pin(async move { Ok(bound.then(Vec::new)) })
+    }
+}
+
+/// A restricted agent is served read-only.
+impl AgentBackend for RestrictedAgent {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::testing::*;
+    use crate::user_auth::UserAuth;
+
+    fn bind(host: u8, session_id: &[u8], forwarding: bool) -> Vec<u8> {
+        let bind = SessionBind {
+            host_key: key(host).identity(),
+            session_id: session_id.into(),
+            signature: key(host).sign(session_id),
+            forwarding,
+        };
+        SshCodec::encode(&bind).unwrap()
+    }
+
+    /// The signature data of a `publickey` request (see `user_auth::signature`).
+    ///
+    /// It is a `publickey-hostbound-v00@openssh.com` request if the host key is given.
+    struct Request<'a>(&'a [u8], &'a str, &'a Identity, Option<&'a Identity>);
+
+    impl SshEncode for Request<'_> {
+        fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+            e.push_bytes_framed(self.0)?;
+            e.push_u8(50)?;
+            e.push_str_framed(self.1)?;
+            e.push_str_framed(UserAuth::SSH_CONNECTION)?;
+            match self.3 {
+                None => e.push_str_framed(UserAuth::PUBLICKEY)?,
+                Some(_) => e.push_str_framed(RestrictedAgent::PUBLICKEY_HOSTBOUND)?,
+            }
+            e.push_bool(true)?;
+            e.push_str_framed(self.2.algorithm())?;
+            e.push(self.2)?;
+            match self.3 {
+                None => Some(()),
+                Some(host_key) => e.push(host_key),
+            }
+        }
+    }
+
+    fn request(session_id: &[u8], user: &str, id: &Identity) -> Vec<u8> {
+        SshCodec::encode(&Request(session_id, user, id, None)).unwrap()
+    }
+
+    fn request_hostbound(session_id: &[u8], user: &str, id: &Identity, host: u8) -> Vec<u8> {
+        let host_key = key(host).identity();
+        SshCodec::encode(&Request(session_id, user, id, Some(&host_key))).unwrap()
+    }
+
+    /// A rule for keys `ids` from host `from` (or the local client) to host `to`.
+    fn rule(from: Option<u8>, to: u8, user: Option<&str>, ids: &[u8]) -> DestinationRule {
+        DestinationRule {
+            host: to.to_string(),
+            from_host_keys: from.map(|from| key(from).identity()).into_iter().collect(),
+            host_keys: vec![key(to).identity()],
+            user: user.map(String::from),
+            identities: ids.iter().map(|id| key(*id).identity()).collect(),
+        }
+    }
+
+    /// Keys 1 and 2 in the store, key 1 allowed at host 10, key 2 allowed as `bob` at host 11.
+    fn agent() -> RestrictedAgent {
+        let store = KeyStore::new();
+        store.add(key(1), "one");
+        store.add(key(2), "two");
+        RestrictedAgent::new(Arc::new(store))
+            .allow(rule(None, 10, None, &[1]))
+            .allow(rule(None, 11, Some("bob"), &[2]))
+    }
+
+    async fn extension(agent: &RestrictedAgent, data: &[u8]) -> bool {
+        let ext = agent.extension(SessionBind::NAME, data).await;
+        ext.unwrap().is_some()
+    }
+
+    #[tokio::test]
+    async fn test_unbound_01() {
+        let agent = agent();
+        assert!(agent.identities().await.unwrap().is_empty());
+        let id = key(1).identity();
+        let data = request(b"sid", "alice", &id);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bound_01() {
+        let agent = agent();
+        assert!(extension(&agent, &bind(10, b"sid", false)).await);
+        let ids = agent.identities().await.unwrap();
+        assert_eq!(ids, vec![(key(1).identity(), "one".into())]);
+        let id = key(1).identity();
+        let data = request(b"sid", "alice", &id);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_some());
+        // Other session, no authentication request, other key
+        let data = request(b"other", "alice", &id);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_none());
+        assert!(agent.signature(&id, b"data", 0).await.unwrap().is_none());
+        let id = key(2).identity();
+        let data = request(b"sid", "bob", &id);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bound_user_01() {
+        let agent = agent();
+        assert!(extension(&agent, &bind(11, b"sid", false)).await);
+        let id = key(2).identity();
+        let data = request(b"sid", "bob", &id);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_some());
+        let data = request(b"sid", "alice", &id);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bind_01() {
+        let agent = agent();
+        // Invalid signature
+        let mut data = bind(10, b"sid", true);
+        data[60] ^= 1;
+        assert!(!extension(&agent, &data).await);
+        assert!(extension(&agent, &bind(10, b"sid", true)).await);
+        assert!(extension(&agent, &bind(10, b"sid", true)).await);
+        // Same session id, other host key
+        assert!(!extension(&agent, &bind(11, b"sid", true)).await);
+        // Forwarded to host 11
+        assert!(extension(&agent, &bind(11, b"sid2", false)).await);
+        // No further hop after a binding for authentication
+        assert!(!extension(&agent, &bind(10, b"sid3", false)).await);
+        assert!(agent.unbound().identities().await.unwrap().is_empty());
+        let ext = agent.extension("query", &[]).await.unwrap();
+        assert!(ext.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_hostbound_01() {
+        let agent = agent();
+        assert!(extension(&agent, &bind(10, b"sid", false)).await);
+        let id = key(1).identity();
+        let data = request_hostbound(b"sid", "alice", &id, 10);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_some());
+        // Other host key than the bound one
+        let data = request_hostbound(b"sid", "alice", &id, 11);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_01() {
+        // Key 2 may be forwarded to host 10 and used from there at host 11
+        let agent =
+            agent()
+                .allow(rule(None, 10, None, &[2]))
+                .allow(rule(Some(10), 11, Some("bob"), &[2]));
+        assert!(extension(&agent, &bind(10, b"sid", true)).await);
+        // Key 1 may not be used beyond host 10
+        let ids = agent.identities().await.unwrap();
+        assert_eq!(ids, vec![(key(2).identity(), "two".into())]);
+        let id = key(2).identity();
+        let data = request_hostbound(b"sid", "bob", &id, 10);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_none());
+        assert!(extension(&agent, &bind(11, b"sid2", false)).await);
+        let ids = agent.identities().await.unwrap();
+        assert_eq!(ids, vec![(key(2).identity(), "two".into())]);
+        let data = request_hostbound(b"sid2", "bob", &id, 11);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_some());
+        // No host key, other host key, other user
+        let data = request(b"sid2", "bob", &id);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_none());
+        let data = request_hostbound(b"sid2", "bob", &id, 10);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_none());
+        let data = request_hostbound(b"sid2", "alice", &id, 11);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_02() {
+        // Key 2 may be used from host 10 at host 11, but not forwarded to host 10
+        let agent = agent().allow(rule(Some(10), 11, None, &[2]));
+        assert!(extension(&agent, &bind(10, b"sid", true)).await);
+        assert!(extension(&agent, &bind(11, b"sid2", false)).await);
+        assert!(agent.identities().await.unwrap().is_empty());
+        let id = key(2).identity();
+        let data = request_hostbound(b"sid2", "bob", &id, 11);
+        assert!(agent.signature(&id, &data, 0).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,22 @@
+//! Fixtures shared by the agent tests.
+
+use crate::identity::ssh_ed25519::SshEd25519PrivateKey;
+use crate::identity::PrivateKey;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::net::UnixListener;
+
+/// The Ed25519 key derived from `seed`.
+pub fn key(seed: u8) -> PrivateKey {
+    SshEd25519PrivateKey::new([seed; 32]).into()
+}
+
+/// Listen on a unix socket with a fresh temporary path.
+pub fn listen() -> (UnixListener, PathBuf) {
+    static N: AtomicUsize = AtomicUsize::new(0);
+    let n = N.fetch_add(1, Ordering::SeqCst);
+    let name = format!("shellfish-agent-{}-{}.sock", std::process::id(), n);
+    let path = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_file(&path);
+    (UnixListener::bind(&path).unwrap(), path)
+}
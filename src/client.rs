@@ -1,8 +1,10 @@
 mod config;
 mod error;
+mod ssh_config;
 
 pub use self::config::*;
 pub use self::error::*;
+pub use self::ssh_config::*;
 
 use crate::connection::*;
 use crate::transport::*;
@@ -35,8 +37,10 @@ impl Client {
 
     /// Create a new connection to the given host.
     ///
-    /// A running `ssh-agent` is expected for key or certificate authentication (`SSH_AUTH_SOCK`
-    /// environment variable).
+    /// Key or certificate authentication uses the in-process keys of the user auth config and a
+    /// running `ssh-agent` (`SSH_AUTH_SOCK` environment variable). Password authentication
+    /// requires a [PasswordPrompt](crate::user_auth::PasswordPrompt) in the user auth config.
+    /// See [UserAuth::request_connection] for the order in which methods are tried.
     pub async fn connect<F: FnOnce(&Connection) -> Box<dyn ConnectionHandler>>(
         &self,
         user: &str,
@@ -46,13 +50,15 @@ impl Client {
     ) -> Result<Connection, ClientError> {
         let e = |e: std::io::Error| TransportError::from(e);
         let socket = TcpStream::connect((host, port)).await.map_err(e)?;
+        let addr = socket.peer_addr().ok().map(|addr| addr.ip());
         let tc = &self.config.transport;
         let cc = &self.config.connection;
         let hv = &self.config.host_verifier;
+        let ua = &self.config.user_auth;
         let aa = &self.config.auth_agent;
         let sv = UserAuth::SSH_USERAUTH;
-        let t = Transport::connect(socket, tc, hv, host, port, sv).await?;
-        Ok(UserAuth::request_connection(t, cc, handle, user, aa).await?)
+        let t = Transport::connect(socket, tc, hv, host, port, addr, sv).await?;
+        Ok(UserAuth::request_connection(t, cc, handle, ua, user, host, aa).await?)
     }
 }
 
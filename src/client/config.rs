@@ -2,6 +2,7 @@ use crate::agent::*;
 use crate::connection::ConnectionConfig;
 use crate::host::*;
 use crate::transport::*;
+use crate::user_auth::UserAuthConfig;
 use crate::util::socket::*;
 
 use std::sync::Arc;
@@ -12,6 +13,7 @@ pub struct ClientConfig {
     pub(crate) socket: Arc<SocketConfig>,
     pub(crate) transport: Arc<TransportConfig>,
     pub(crate) connection: Arc<ConnectionConfig>,
+    pub(crate) user_auth: Arc<UserAuthConfig>,
     pub(crate) host_verifier: Arc<dyn HostVerifier>,
     pub(crate) auth_agent: Arc<dyn AuthAgent>,
 }
@@ -41,6 +43,14 @@ impl ClientConfig {
         Arc::make_mut(&mut self.connection)
     }
 
+    pub fn user_auth(&self) -> &UserAuthConfig {
+        &self.user_auth
+    }
+
+    pub fn user_auth_mut(&mut self) -> &mut UserAuthConfig {
+        Arc::make_mut(&mut self.user_auth)
+    }
+
     pub fn host_verifier_mut(&mut self) -> &mut Arc<dyn HostVerifier> {
         &mut self.host_verifier
     }
@@ -56,6 +66,7 @@ impl Default for ClientConfig {
             socket: Arc::new(SocketConfig::default()),
             transport: Arc::new(TransportConfig::default()),
             connection: Arc::new(ConnectionConfig::default()),
+            user_auth: Arc::new(UserAuthConfig::default()),
             host_verifier: Arc::new(KnownHosts::default()),
             auth_agent: match LocalAgent::new_env() {
                 Some(agent) => Arc::new(agent),
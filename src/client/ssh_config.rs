@@ -0,0 +1,767 @@
+use super::*;
+use crate::agent::*;
+use crate::host::*;
+use crate::identity::Krl;
+use crate::util::config::*;
+use crate::util::glob::*;
+use std::path::{Path, PathBuf};
+
+/// An OpenSSH client configuration (`~/.ssh/config` and `/etc/ssh/ssh_config`).
+///
+/// The files are parsed once and may then be [resolved](Self::resolve) for any number of host
+/// aliases. Like in OpenSSH, the first value obtained for each option wins: Options are read
+/// from top to bottom and only those in matching `Host` and `Match` blocks apply.
+///
+/// Supported are `Host` and `Match` blocks (criteria `all`, `host`, `originalhost`, `user`,
+/// `localuser`, `canonical` and `final`), `Include` (with wildcards) and the options
+/// evaluated by [resolve](Self::resolve). Unknown options are ignored, while files with other
+/// `Match` criteria (like `exec`) are rejected.
+///
+/// `StrictHostKeyChecking no` (and `off`) is treated like `accept-new`: Unknown hosts are added
+/// to `known_hosts`, but changed host keys are still rejected.
+#[derive(Clone, Debug)]
+pub struct SshConfig {
+    blocks: Vec<Block>,
+    host_key_prompt: Arc<dyn HostKeyPrompt>,
+    passphrase_prompt: Arc<dyn PassphrasePrompt>,
+}
+
+/// The options of a `Host` or `Match` block (or the file's beginning).
+///
+/// Blocks of included files also carry the condition of the including block.
+#[derive(Clone, Debug, Default)]
+struct Block {
+    conditions: Vec<Condition>,
+    lines: Vec<ConfigLine>,
+}
+
+#[derive(Clone, Debug)]
+enum Condition {
+    /// A `Host` line (list of patterns)
+    Host(Vec<String>),
+    /// A `Match` line (criteria and their arguments)
+    Match(Vec<(String, Option<String>)>),
+}
+
+/// The connection target of a resolved host alias.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Destination {
+    /// The user name (`User`, defaults to the local user)
+    pub user: String,
+    /// The host name or address to connect to (`HostName`, defaults to the alias)
+    pub host: String,
+    /// The port (`Port`, defaults to 22)
+    pub port: u16,
+    /// The hosts to connect through in given order (`ProxyJump`)
+    pub proxy_jump: Vec<JumpHost>,
+    /// The command whose standard input and output shall be used as socket (`ProxyCommand`)
+    pub proxy_command: Option<String>,
+}
+
+/// A hop of `ProxyJump` (`[user@]host[:port]` or `ssh://[user@]host[:port]`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JumpHost {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl JumpHost {
+    fn parse(s: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid jump host: {}", s);
+        let s = s.strip_prefix("ssh://").unwrap_or(s);
+        let (user, host) = match s.rsplit_once('@') {
+            Some((user, host)) => (Some(user.to_string()), host),
+            None => (None, s),
+        };
+        let (host, port) = if let Some(rest) = host.strip_prefix('[') {
+            let (host, rest) = rest.split_once(']').ok_or_else(invalid)?;
+            match rest.strip_prefix(':') {
+                Some(port) => (host, Some(port)),
+                None if rest.is_empty() => (host, None),
+                None => return Err(invalid()),
+            }
+        } else {
+            match host.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (host, None),
+            }
+        };
+        let port = port.map(|p| p.parse().map_err(|_| invalid())).transpose()?;
+        if host.is_empty() || user.as_ref().is_some_and(|u| u.is_empty()) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            user,
+            host: host.into(),
+            port,
+        })
+    }
+}
+
+impl SshConfig {
+    /// The maximum nesting depth of `Include` directives.
+    const MAX_INCLUDE_DEPTH: usize = 16;
+
+    /// Parse a configuration (relative `Include` paths are relative to `~/.ssh`).
+    pub async fn parse(text: &str) -> Result<Self, ConfigError> {
+        let mut self_ = Self::empty();
+        let dir = expand_tilde("~/.ssh/");
+        let path = dir.join("config");
+        self_.parse_file(&path, text, &dir, &[], 0).await?;
+        Ok(self_)
+    }
+
+    /// Load and parse a configuration file (relative `Include` paths are relative to the
+    /// file's directory).
+    pub async fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let mut self_ = Self::empty();
+        self_.load_file(path.as_ref(), &[], 0).await?;
+        Ok(self_)
+    }
+
+    /// Load the user's `~/.ssh/config` followed by the system wide `/etc/ssh/ssh_config`.
+    ///
+    /// Missing files are skipped.
+    pub async fn load_default() -> Result<Self, ConfigError> {
+        let mut self_ = Self::empty();
+        for path in [expand_tilde("~/.ssh/config"), "/etc/ssh/ssh_config".into()] {
+            match self_.load_file(&path, &[], 0).await {
+                Err(ConfigError::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound => (),
+                r => r?,
+            }
+        }
+        Ok(self_)
+    }
+
+    /// Ask this prompt about unknown hosts (`StrictHostKeyChecking ask`, rejects by default).
+    pub fn host_key_prompt(mut self, prompt: Arc<dyn HostKeyPrompt>) -> Self {
+        self.host_key_prompt = prompt;
+        self
+    }
+
+    /// Ask this prompt for the passphrases of encrypted identity files (skipped by default).
+    pub fn passphrase_prompt(mut self, prompt: Arc<dyn PassphrasePrompt>) -> Self {
+        self.passphrase_prompt = prompt;
+        self
+    }
+
+    fn empty() -> Self {
+        Self {
+            blocks: vec![],
+            host_key_prompt: Arc::new(()),
+            passphrase_prompt: Arc::new(()),
+        }
+    }
+
+    async fn load_file(
+        &mut self,
+        path: &Path,
+        outer: &[Condition],
+        depth: usize,
+    ) -> Result<(), ConfigError> {
+        let text = tokio::fs::read_to_string(path).await?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        self.parse_file(path, &text, dir, outer, depth).await
+    }
+
+    /// Parse a file into blocks (the `outer` conditions are those of the including block).
+    async fn parse_file(
+        &mut self,
+        path: &Path,
+        text: &str,
+        dir: &Path,
+        outer: &[Condition],
+        depth: usize,
+    ) -> Result<(), ConfigError> {
+        let mut conditions = outer.to_vec();
+        self.blocks.push(Block {
+            conditions: conditions.clone(),
+            lines: vec![],
+        });
+        for (i, line) in text.lines().enumerate() {
+            let err = |e| ConfigError::line(path, i + 1, e);
+            let Some(line) = ConfigLine::parse(line).map_err(err)? else {
+                continue;
+            };
+            let condition = match line.keyword.as_str() {
+                "host" if line.args.is_empty() => return Err(err("Missing host pattern".into())),
+                "host" => Condition::Host(line.args),
+                "match" => Condition::Match(Self::parse_match(&line.args).map_err(err)?),
+                "include" => {
+                    if depth >= Self::MAX_INCLUDE_DEPTH {
+                        return Err(err("Include nested too deeply".into()));
+                    }
+                    let current = conditions.clone();
+                    for arg in &line.args {
+                        for file in Self::include_files(dir, arg).await? {
+                            let load = self.load_file(&file, &current, depth + 1);
+                            Box::pin(load).await?;
+                        }
+                    }
+                    // Continue with the including block
+                    self.blocks.push(Block {
+                        conditions: current,
+                        lines: vec![],
+                    });
+                    continue;
+                }
+                _ => {
+                    let block = self.blocks.last_mut().expect("block");
+                    block.lines.push(line);
+                    continue;
+                }
+            };
+            conditions = outer.to_vec();
+            conditions.push(condition);
+            self.blocks.push(Block {
+                conditions: conditions.clone(),
+                lines: vec![],
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_match(args: &[String]) -> Result<Vec<(String, Option<String>)>, String> {
+        let mut criteria = vec![];
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let criterion = arg.to_ascii_lowercase();
+            let name = criterion.strip_prefix('!').unwrap_or(&criterion);
+            let value = match name {
+                "all" | "canonical" | "final" => None,
+                "host" | "originalhost" | "user" | "localuser" => {
+                    let value = args
+                        .next()
+                        .ok_or(format!("Match {}: Missing argument", name))?;
+                    Some(value.clone())
+                }
+                _ => return Err(format!("Unsupported Match criterion: {}", arg)),
+            };
+            criteria.push((criterion, value));
+        }
+        if criteria.is_empty() {
+            return Err("Missing Match criteria".into());
+        }
+        Ok(criteria)
+    }
+
+    /// The files to include (sorted if the file name contains wildcards).
+    async fn include_files(dir: &Path, arg: &str) -> Result<Vec<PathBuf>, ConfigError> {
+        let path = dir.join(expand_tilde(arg));
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => return Ok(vec![path]),
+        };
+        if !name.contains(['*', '?']) {
+            return Ok(vec![path]);
+        }
+        let mut files = vec![];
+        let parent = path.parent().unwrap_or(Path::new("."));
+        let mut entries = match tokio::fs::read_dir(parent).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(files),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            if Glob(name.as_str()).test(&entry.file_name().to_string_lossy()) {
+                files.push(entry.path());
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Collect the options for a host alias (first value wins, `IdentityFile` accumulates).
+    fn options(&self, alias: &str) -> Options {
+        let mut options = Options {
+            alias: alias.into(),
+            values: ConfigOptions::default(),
+            identity_files: vec![],
+        };
+        for block in &self.blocks {
+            if !block.conditions.iter().all(|c| options.test(c)) {
+                continue;
+            }
+            for line in &block.lines {
+                if line.keyword == "identityfile" {
+                    options.identity_files.extend(line.args.iter().cloned());
+                } else if !options.values.0.contains_key(&line.keyword) {
+                    let args = line.args.clone();
+                    options.values.0.insert(line.keyword.clone(), args);
+                }
+            }
+        }
+        options
+    }
+
+    /// Resolve a host alias into a client configuration and the connection target.
+    ///
+    /// This loads the identity files and revoked host keys. Missing identity files are skipped,
+    /// other options with invalid values fail.
+    pub async fn resolve(&self, alias: &str) -> Result<(ClientConfig, Destination), ConfigError> {
+        let options = self.options(alias);
+        let err = |e| ConfigError::InvalidValue(e);
+        let local_user = local_user().unwrap_or_default();
+        let host = match options.values.arg("hostname").map_err(err)? {
+            Some(host) => Tokens::expand_host(host, alias),
+            None => alias.to_string(),
+        };
+        let port = match options.values.arg("port").map_err(err)? {
+            Some(port) => port
+                .parse()
+                .map_err(|_| err(format!("Invalid port: {}", port)))?,
+            None => 22,
+        };
+        let user = match options.values.arg("user").map_err(err)? {
+            Some(user) => user.to_string(),
+            None => local_user.clone(),
+        };
+        let tokens = Tokens {
+            alias,
+            host: &host,
+            port,
+            user: &user,
+            local_user: &local_user,
+        };
+        let mut destination = Destination {
+            proxy_jump: vec![],
+            proxy_command: None,
+            user: user.clone(),
+            host: host.clone(),
+            port,
+        };
+        if let Some(jumps) = options.values.arg("proxyjump").map_err(err)? {
+            if !jumps.eq_ignore_ascii_case("none") {
+                for jump in jumps.split(',') {
+                    destination
+                        .proxy_jump
+                        .push(JumpHost::parse(jump).map_err(err)?);
+                }
+            }
+        }
+        if let Some(args) = options.values.get("proxycommand") {
+            let command = args.join(" ");
+            if !command.eq_ignore_ascii_case("none") && destination.proxy_jump.is_empty() {
+                destination.proxy_command = Some(tokens.expand(&command));
+            }
+        }
+
+        let mut config = ClientConfig::default();
+        options
+            .values
+            .transport(config.transport_mut())
+            .map_err(err)?;
+        Self::resolve_connection(&options, config.connection_mut()).map_err(err)?;
+        let keys = self.resolve_keys(&options, &tokens).await;
+        let ua = config.user_auth_mut();
+        ua.keys = Some(Arc::new(keys));
+        ua.local_user_name = Some(local_user.clone());
+        if let Some(flag) = options.values.flag("identitiesonly").map_err(err)? {
+            ua.identities_only = flag;
+        }
+        ua.methods = Self::resolve_methods(&options, &ua.methods).map_err(err)?;
+        config.host_verifier = self.resolve_verifier(&options, &tokens).await?;
+        if let Some(agent) = options.values.arg("identityagent").map_err(err)? {
+            config.auth_agent = match agent {
+                "none" => Arc::new(()),
+                "SSH_AUTH_SOCK" => match LocalAgent::new_env() {
+                    Some(agent) => Arc::new(agent),
+                    None => Arc::new(()),
+                },
+                agent => match agent.strip_prefix('$') {
+                    Some(var) => match std::env::var_os(var) {
+                        Some(path) => Arc::new(LocalAgent::new(Path::new(&path))),
+                        None => Arc::new(()),
+                    },
+                    None => Arc::new(LocalAgent::new(&tokens.path(agent))),
+                },
+            };
+        }
+        Ok((config, destination))
+    }
+
+    fn resolve_connection(options: &Options, cc: &mut ConnectionConfig) -> Result<(), String> {
+        if let Some(interval) = options.values.arg("serveraliveinterval")? {
+            let interval = parse_time(interval)?;
+            cc.keepalive_interval = (!interval.is_zero()).then_some(interval);
+        }
+        if let Some(count) = options.values.arg("serveralivecountmax")? {
+            cc.keepalive_count_max = count
+                .parse()
+                .map_err(|_| format!("Invalid count: {}", count))?;
+        }
+        if let Some(update) = options.values.arg("updatehostkeys")? {
+            cc.host_keys_update = update == "ask" || parse_flag(update)?;
+        }
+        Ok(())
+    }
+
+    /// The preferred methods without those that have been disabled.
+    fn resolve_methods(
+        options: &Options,
+        defaults: &[&'static str],
+    ) -> Result<Vec<&'static str>, String> {
+        let mut methods = defaults.to_vec();
+        if let Some(preferred) = options.values.arg("preferredauthentications")? {
+            methods = preferred
+                .split(',')
+                .filter_map(|m| defaults.iter().find(|d| **d == m).copied())
+                .collect();
+        }
+        let disabled = [
+            ("hostbasedauthentication", UserAuth::HOSTBASED),
+            ("pubkeyauthentication", UserAuth::PUBLICKEY),
+            (
+                "kbdinteractiveauthentication",
+                UserAuth::KEYBOARD_INTERACTIVE,
+            ),
+            ("passwordauthentication", UserAuth::PASSWORD),
+        ];
+        for (keyword, method) in disabled {
+            if options.values.flag(keyword)? == Some(false) {
+                methods.retain(|m| *m != method);
+            }
+        }
+        Ok(methods)
+    }
+
+    /// Load the identity files into a key store.
+    async fn resolve_keys(&self, options: &Options, tokens: &Tokens<'_>) -> KeyStore {
+        let keys = KeyStore::new();
+        let files = if options.identity_files.is_empty() {
+            vec!["~/.ssh/id_ed25519".into(), "~/.ssh/id_rsa".into()]
+        } else {
+            options.identity_files.clone()
+        };
+        for file in files {
+            let path = tokens.path(&file);
+            match keys.load(&path, self.passphrase_prompt.as_ref()).await {
+                Ok(_) => (),
+                Err(KeyStoreError::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound => (),
+                Err(e) => log::warn!("Identity file {}: {}", path.display(), e),
+            }
+        }
+        keys
+    }
+
+    async fn resolve_verifier(
+        &self,
+        options: &Options,
+        tokens: &Tokens<'_>,
+    ) -> Result<Arc<dyn HostVerifier>, ConfigError> {
+        let err = |e| ConfigError::InvalidValue(e);
+        let files = |keyword, defaults: &[&str]| match options.values.get(keyword) {
+            Some(args) if args.iter().any(|a| a == "none") => vec![],
+            Some(args) => args.iter().map(|a| tokens.path(a)).collect(),
+            None => defaults.iter().map(|a| tokens.path(a)).collect::<Vec<_>>(),
+        };
+        let global = ["/etc/ssh/ssh_known_hosts", "/etc/ssh/ssh_known_hosts2"];
+        let user = ["~/.ssh/known_hosts", "~/.ssh/known_hosts2"];
+        // The first user file is the one written to and needs to be last
+        let mut paths = files("globalknownhostsfile", &global);
+        paths.extend(files("userknownhostsfile", &user).into_iter().rev());
+        let mut known_hosts = KnownHosts::new(paths);
+        if let Some(flag) = options.values.flag("hashknownhosts").map_err(err)? {
+            known_hosts = known_hosts.hash_names(flag);
+        }
+        if let Some(flag) = options.values.flag("checkhostip").map_err(err)? {
+            known_hosts = known_hosts.check_host_ip(flag);
+        }
+        let strict = options.values.arg("stricthostkeychecking").map_err(err)?;
+        let mut verifier: Arc<dyn HostVerifier> = match strict.unwrap_or("ask") {
+            "yes" | "true" => Arc::new(known_hosts),
+            // Never accept changed keys (OpenSSH would with `no`)
+            "accept-new" | "no" | "false" | "off" => {
+                Arc::new(PromptVerifier::accept_new(known_hosts))
+            }
+            "ask" => Arc::new(PromptVerifier::new(
+                known_hosts,
+                self.host_key_prompt.clone(),
+            )),
+            s => return Err(err(format!("StrictHostKeyChecking: Invalid value {}", s))),
+        };
+        if let Some(path) = options.values.arg("revokedhostkeys").map_err(err)? {
+            if path != "none" {
+                let path = tokens.path(path);
+                let krl = Krl::load(&path)
+                    .await
+                    .map_err(|e| err(format!("RevokedHostKeys {}: {}", path.display(), e)))?;
+                verifier = Arc::new(KrlVerifier::new(Arc::new(krl), verifier));
+            }
+        }
+        Ok(verifier)
+    }
+}
+
+impl Default for SshConfig {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/// The options collected for a host alias.
+struct Options {
+    alias: String,
+    values: ConfigOptions,
+    identity_files: Vec<String>,
+}
+
+impl Options {
+    fn test(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::Host(patterns) => Self::test_patterns(&patterns.join(","), &self.alias),
+            Condition::Match(criteria) => criteria.iter().all(|(criterion, value)| {
+                let (negated, name) = match criterion.strip_prefix('!') {
+                    Some(name) => (true, name),
+                    None => (false, criterion.as_str()),
+                };
+                let value = value.as_deref().unwrap_or_default();
+                let first = |k| self.values.get(k).and_then(|a| a.first());
+                let result = match name {
+                    "all" | "final" => true,
+                    "canonical" => false,
+                    "host" => {
+                        let host = first("hostname").map(|h| Tokens::expand_host(h, &self.alias));
+                        Self::test_patterns(value, host.as_deref().unwrap_or(&self.alias))
+                    }
+                    "originalhost" => Self::test_patterns(value, &self.alias),
+                    "user" => match first("user").cloned().or_else(local_user) {
+                        Some(user) => GlobList(value).test(&user),
+                        None => false,
+                    },
+                    "localuser" => local_user().is_some_and(|user| GlobList(value).test(&user)),
+                    // Other criteria (like `exec`) are rejected when parsing
+                    _ => false,
+                };
+                result != negated
+            }),
+        }
+    }
+
+    fn test_patterns(patterns: &str, host: &str) -> bool {
+        GlobList(patterns.to_ascii_lowercase()).test(&host.to_ascii_lowercase())
+    }
+}
+
+/// Expansion of `%` tokens and `~` in option values.
+struct Tokens<'a> {
+    alias: &'a str,
+    host: &'a str,
+    port: u16,
+    user: &'a str,
+    local_user: &'a str,
+}
+
+impl Tokens<'_> {
+    /// Expand `%h` (alias) and `%%` in `HostName`.
+    fn expand_host(host: &str, alias: &str) -> String {
+        host.replace("%%", "\0")
+            .replace("%h", alias)
+            .replace('\0', "%")
+    }
+
+    fn expand(&self, s: &str) -> String {
+        let mut result = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                result.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('%') => result.push('%'),
+                Some('h') => result.push_str(self.host),
+                Some('n') => result.push_str(self.alias),
+                Some('p') => result.push_str(&self.port.to_string()),
+                Some('r') => result.push_str(self.user),
+                Some('u') => result.push_str(self.local_user),
+                Some('d') => result.push_str(&expand_tilde("~/").to_string_lossy()),
+                Some(c) => {
+                    result.push('%');
+                    result.push(c);
+                }
+                None => result.push('%'),
+            }
+        }
+        result
+    }
+
+    fn path(&self, s: &str) -> PathBuf {
+        expand_tilde(&self.expand(s))
+    }
+}
+
+/// The local user name (from `USER` or `LOGNAME`).
+fn local_user() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("LOGNAME"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn resolve(text: &str, alias: &str) -> (ClientConfig, Destination) {
+        SshConfig::parse(text)
+            .await
+            .unwrap()
+            .resolve(alias)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_resolve_01() {
+        let text = "
+            Host web *.example.com !db.example.com
+                HostName %h.internal
+                Port 2222
+                User alice
+            Host db*
+                User bob
+                ProxyJump carol@jump.example.com:2200,[::1]
+            Host *
+                User nobody
+                Port 22
+        ";
+        let (_, dest) = resolve(text, "web").await;
+        assert_eq!(dest.host, "web.internal");
+        assert_eq!(dest.port, 2222);
+        assert_eq!(dest.user, "alice");
+        assert!(dest.proxy_jump.is_empty());
+        let (_, dest) = resolve(text, "DB.example.com").await;
+        assert_eq!(dest.host, "DB.example.com");
+        assert_eq!(dest.port, 22);
+        assert_eq!(dest.user, "bob");
+        let jump = JumpHost {
+            user: Some("carol".into()),
+            host: "jump.example.com".into(),
+            port: Some(2200),
+        };
+        let jump2 = JumpHost {
+            user: None,
+            host: "::1".into(),
+            port: None,
+        };
+        assert_eq!(dest.proxy_jump, vec![jump, jump2]);
+        let (_, dest) = resolve(text, "other").await;
+        assert_eq!(dest.user, "nobody");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_match_01() {
+        let text = "
+            Host alias
+                HostName real.example.com
+            Match host real.example.com !originalhost real.example.com
+                Port 2022
+            Match originalhost real.example.com
+                Port 2023
+            Match user alice
+                ProxyCommand nc %h %p
+            Match all
+                User alice
+                ServerAliveInterval 1m
+                ServerAliveCountMax 5
+        ";
+        let (config, dest) = resolve(text, "alias").await;
+        assert_eq!(dest.port, 2022);
+        assert_eq!(dest.user, "alice");
+        assert_eq!(dest.proxy_command, None);
+        let cc = config.connection();
+        assert_eq!(cc.keepalive_interval, Some(Duration::from_secs(60)));
+        assert_eq!(cc.keepalive_count_max, 5);
+        let (_, dest) = resolve(text, "real.example.com").await;
+        assert_eq!(dest.port, 2023);
+        assert!(SshConfig::parse("Match foo bar").await.is_err());
+        assert!(SshConfig::parse("Match host").await.is_err());
+        assert!(SshConfig::parse("Match !exec true").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_proxy_command_01() {
+        let text = "Host a\n  User bob\nHost *\n  ProxyCommand ssh -W %h:%p %r@gw\n";
+        let (_, dest) = resolve(text, "a").await;
+        assert_eq!(dest.proxy_command.as_deref(), Some("ssh -W a:22 bob@gw"));
+        let text = "Host a\n  ProxyCommand none\nHost *\n  ProxyCommand nc %h %p\n";
+        let (_, dest) = resolve(text, "a").await;
+        assert_eq!(dest.proxy_command, None);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_transport_01() {
+        let text = "
+            Host a
+                Ciphers -chacha*
+            Host b
+                KexAlgorithms ^curve25519-sha256@libssh.org
+                HostKeyAlgorithms +ssh-rsa
+                RekeyLimit 1G 10m
+            Host c
+                KexAlgorithms curve25519-sha256,foo
+        ";
+        assert!(SshConfig::parse(text)
+            .await
+            .unwrap()
+            .resolve("a")
+            .await
+            .is_err());
+        let (config, _) = resolve(text, "b").await;
+        let tc = config.transport();
+        assert_eq!(tc.kex_algorithms, vec!["curve25519-sha256"]);
+        assert_eq!(tc.host_key_algorithms, vec!["ssh-ed25519"]);
+        assert_eq!(tc.kex_interval_bytes, 1 << 30);
+        assert_eq!(tc.kex_interval_duration, Duration::from_secs(600));
+        let (config, _) = resolve(text, "c").await;
+        assert_eq!(config.transport().kex_algorithms, vec!["curve25519-sha256"]);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_user_auth_01() {
+        let text = "
+            IdentitiesOnly yes
+            PreferredAuthentications password,publickey,gssapi-with-mic
+            PasswordAuthentication no
+            IdentityFile /nonexistent/id_ed25519
+        ";
+        let (config, _) = resolve(text, "a").await;
+        let ua = config.user_auth();
+        assert!(ua.identities_only);
+        assert_eq!(ua.methods, vec![UserAuth::PUBLICKEY]);
+    }
+
+    #[tokio::test]
+    async fn test_include_01() {
+        let dir = std::env::temp_dir().join(format!("shellfish-ssh-config-{}", std::process::id()));
+        tokio::fs::create_dir_all(dir.join("conf.d")).await.unwrap();
+        let write = |name: &str, text: &str| std::fs::write(dir.join(name), text).unwrap();
+        write(
+            "config",
+            "Host a\n  Include conf.d/*.conf\n  User alice\nHost *\n  Port 2000\n",
+        );
+        write("conf.d/1.conf", "Port 1001\nHost *\n  User carol\n");
+        write("conf.d/2.conf", "Port 1002\n");
+        write("conf.d/3.txt", "Port 1003\n");
+        let config = SshConfig::load(dir.join("config")).await.unwrap();
+        let (_, dest) = config.resolve("a").await.unwrap();
+        assert_eq!((dest.user.as_str(), dest.port), ("carol", 1001));
+        let (_, dest) = config.resolve("b").await.unwrap();
+        assert_eq!(dest.port, 2000);
+        write("config", "Include config\n");
+        assert!(SshConfig::load(dir.join("config")).await.is_err());
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[test]
+    fn test_jump_host_01() {
+        let jump = JumpHost::parse("ssh://bob@[fe80::1]:22").unwrap();
+        assert_eq!(jump.user.as_deref(), Some("bob"));
+        assert_eq!(jump.host, "fe80::1");
+        assert_eq!(jump.port, Some(22));
+        assert!(JumpHost::parse("host:port").is_err());
+        assert!(JumpHost::parse("@host").is_err());
+    }
+}
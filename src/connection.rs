@@ -7,12 +7,14 @@ mod msg;
 mod request;
 mod state;
 
+pub use self::channel::auth_agent::{AuthAgentChannel, AuthAgentRequest};
 pub use self::channel::direct_tcpip::{DirectTcpIp, DirectTcpIpParams, DirectTcpIpRequest};
 pub use self::channel::session::{Process, SessionClient};
 pub use self::channel::{OpenFailure, RequestFailure};
 pub use self::config::ConnectionConfig;
 pub use self::error::ConnectionError;
 pub use self::global::{Global, GlobalRequest, GlobalRequestWantReply, GlobalWantReply};
+pub use self::global::{HostKeyList, HostKeys, HostKeysProve, SignatureList};
 pub use self::handler::ConnectionHandler;
 
 use self::error::ConnectionErrorWatch;
@@ -96,6 +98,20 @@ impl Connection {
         res.await.map_err(e2)
     }
 
+    /// Open a new `auth-agent@openssh.com` channel (to the agent forwarded by the peer).
+    ///
+    /// See [ForwardedAgent](crate::agent::ForwardedAgent) for an [AuthAgent](crate::agent::AuthAgent)
+    /// using these channels.
+    pub async fn open_auth_agent(
+        &self,
+    ) -> Result<Result<AuthAgentChannel, OpenFailure>, ConnectionError> {
+        let (req, res) = Request::open_auth_agent();
+        let e1 = |_| self.error_or_dropped();
+        let e2 = |_| self.error_or_dropped();
+        self.creqs_tx.send(req).await.map_err(e1)?;
+        res.await.map_err(e2)
+    }
+
     /// Perform a global request (without reply).
     pub async fn request<T: Global>(&self, data: &T::RequestData) -> Result<(), ConnectionError> {
         let request = Request::Global {
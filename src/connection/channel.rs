@@ -1,8 +1,10 @@
+pub(crate) mod auth_agent;
 pub(crate) mod direct_tcpip;
 pub(crate) mod open_failure;
 pub(crate) mod request_failure;
 pub(crate) mod session;
 
+pub use self::auth_agent::AuthAgentChannel;
 pub use self::direct_tcpip::{DirectTcpIp, DirectTcpIpParams, DirectTcpIpRequest};
 pub use self::open_failure::OpenFailure;
 pub use self::request_failure::RequestFailure;
@@ -0,0 +1,95 @@
+mod request;
+mod state_opening_out;
+
+pub use self::request::AuthAgentRequest;
+
+use self::state_opening_out::StateOpeningOutbound;
+use super::direct_tcpip::state::State;
+use super::direct_tcpip::state_opening_in::StateOpeningInbound;
+use super::{Channel, ChannelState, OpenFailure};
+use crate::connection::msg::MsgChannelOpen;
+use crate::connection::{ConnectionConfig, ConnectionError};
+use crate::util::socket::Socket;
+use std::io::Error;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::oneshot::{channel, Sender};
+
+/// An `auth-agent@openssh.com` channel (agent forwarding).
+///
+/// The channel transports the `ssh-agent` protocol just like the unix domain socket of a local
+/// agent and may be used as [Socket] for it.
+#[derive(Debug)]
+pub struct AuthAgentChannel(State);
+
+impl AuthAgentChannel {
+    pub(crate) fn open_in(
+        config: &ConnectionConfig,
+        msg: &MsgChannelOpen,
+        lid: u32,
+    ) -> Result<(Box<dyn ChannelState>, AuthAgentRequest), ConnectionError> {
+        let (tx, rx) = channel();
+        let lbs = config.channel_max_buffer_size;
+        let lps = config.channel_max_packet_size;
+        let rid = msg.sender_channel;
+        let rws = msg.initial_window_size;
+        let rps = msg.maximum_packet_size;
+        let st1 = State::new(lid, lbs, lps, rid, rws, rps);
+        let req = AuthAgentRequest::new(Self(st1.clone()), tx);
+        let bch = Box::new(StateOpeningInbound::new(st1, rx));
+        Ok((bch, req))
+    }
+
+    pub(crate) fn open_out(
+        config: &ConnectionConfig,
+        lid: u32,
+        reply_tx: Sender<Result<AuthAgentChannel, OpenFailure>>,
+    ) -> Box<dyn ChannelState> {
+        let lbs = config.channel_max_buffer_size;
+        let lps = config.channel_max_packet_size;
+        let st1 = State::new(lid, lbs, lps, 0, 0, 0);
+        Box::new(StateOpeningOutbound::new(st1, reply_tx))
+    }
+}
+
+impl Channel for AuthAgentChannel {
+    const NAME: &'static str = "auth-agent@openssh.com";
+}
+
+/// Dropping initiates the channel close procedure (see [DirectTcpIp](super::DirectTcpIp)).
+impl Drop for AuthAgentChannel {
+    fn drop(&mut self) {
+        self.0.close()
+    }
+}
+
+impl AsyncRead for AuthAgentChannel {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<Result<(), Error>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for AuthAgentChannel {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Error>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+impl Socket for AuthAgentChannel {}
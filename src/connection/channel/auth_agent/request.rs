@@ -0,0 +1,46 @@
+use super::AuthAgentChannel;
+use super::OpenFailure;
+use crate::agent::{AgentServer, AuthAgent};
+use std::sync::Arc;
+use tokio::sync::oneshot::Sender;
+
+/// An inbound `auth-agent@openssh.com` channel request.
+///
+/// A client shall only accept it if it requested agent forwarding for one of its sessions.
+/// Dropping the request rejects it.
+#[derive(Debug)]
+pub struct AuthAgentRequest {
+    channel: AuthAgentChannel,
+    reply_tx: Sender<Result<(), OpenFailure>>,
+}
+
+impl AuthAgentRequest {
+    pub(crate) fn new(
+        channel: AuthAgentChannel,
+        reply_tx: Sender<Result<(), OpenFailure>>,
+    ) -> Self {
+        Self { channel, reply_tx }
+    }
+
+    pub fn accept(self) -> AuthAgentChannel {
+        let _ = self.reply_tx.send(Ok(()));
+        self.channel
+    }
+
+    pub fn reject(self, e: OpenFailure) {
+        let _ = self.reply_tx.send(Err(e));
+    }
+
+    /// Accept the channel and serve the agent on it (in a separate task).
+    ///
+    /// The peer may list identities and request signatures, but neither add nor remove keys.
+    pub fn bridge(self, agent: Arc<dyn AuthAgent>) {
+        let server = AgentServer::new(Arc::new(agent), Arc::new(()));
+        let channel = self.accept();
+        drop(tokio::spawn(async move {
+            if let Err(e) = server.serve(channel).await {
+                log::debug!("Agent forwarding: {}", e);
+            }
+        }));
+    }
+}
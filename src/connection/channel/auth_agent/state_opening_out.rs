@@ -0,0 +1,57 @@
+use super::super::super::error::ConnectionError;
+use super::super::direct_tcpip::state::State;
+use super::super::open_failure::OpenFailure;
+use super::super::{Channel, ChannelState, PollResult};
+use super::AuthAgentChannel;
+use crate::transport::Transport;
+use std::task::{ready, Context, Poll};
+use tokio::sync::oneshot::Sender;
+
+#[derive(Debug)]
+pub(crate) struct StateOpeningOutbound {
+    state: State,
+    sent: bool,
+    reply_tx: Sender<Result<AuthAgentChannel, OpenFailure>>,
+}
+
+impl StateOpeningOutbound {
+    pub fn new(state: State, reply_tx: Sender<Result<AuthAgentChannel, OpenFailure>>) -> Self {
+        Self {
+            state,
+            sent: false,
+            reply_tx,
+        }
+    }
+}
+
+impl ChannelState for StateOpeningOutbound {
+    fn on_open_confirmation(
+        self: Box<Self>,
+        rid: u32,
+        rws: u32,
+        rps: u32,
+    ) -> Result<Box<dyn ChannelState>, ConnectionError> {
+        self.state.set_open_confirmation(rid, rws, rps);
+        let c = AuthAgentChannel(self.state.clone());
+        let _ = self.reply_tx.send(Ok(c));
+        Ok(Box::new(self.state))
+    }
+
+    fn on_open_failure(self: Box<Self>, e: OpenFailure) -> Result<(), ConnectionError> {
+        let _ = self.reply_tx.send(Err(e));
+        Ok(())
+    }
+
+    fn poll_with_transport(
+        &mut self,
+        cx: &mut Context,
+        t: &mut Transport,
+    ) -> Poll<Result<PollResult, ConnectionError>> {
+        if !self.sent {
+            let msg = self.state.msg_open(AuthAgentChannel::NAME, vec![]);
+            ready!(t.poll_send(cx, &msg))?;
+            self.sent = true;
+        }
+        Poll::Ready(Ok(PollResult::Noop))
+    }
+}
@@ -1,7 +1,7 @@
 mod params;
 mod request;
-mod state;
-mod state_opening_in;
+pub(crate) mod state;
+pub(crate) mod state_opening_in;
 mod state_opening_out;
 
 use crate::connection::ConnectionConfig;
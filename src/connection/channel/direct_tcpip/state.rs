@@ -1,7 +1,6 @@
 use super::super::super::super::connection::msg::*;
-use super::super::super::channel::{Channel, ChannelState, OpenFailure, PollResult};
+use super::super::super::channel::{ChannelState, OpenFailure, PollResult};
 use super::super::super::error::ConnectionError;
-use super::DirectTcpIp;
 use crate::transport::Transport;
 use crate::util::buffer::Buffer;
 use crate::util::check;
@@ -96,10 +95,14 @@ impl State {
         x.rps = rps;
     }
 
-    pub(crate) fn msg_open(&self, data: Vec<u8>) -> MsgChannelOpen<&'static str> {
+    pub(crate) fn msg_open(
+        &self,
+        name: &'static str,
+        data: Vec<u8>,
+    ) -> MsgChannelOpen<&'static str> {
         let x = self.0.lock().unwrap();
         MsgChannelOpen {
-            name: DirectTcpIp::NAME,
+            name,
             sender_channel: x.lid,
             initial_window_size: x.lws,
             maximum_packet_size: x.lps,
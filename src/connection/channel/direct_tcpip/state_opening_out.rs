@@ -1,6 +1,6 @@
 use super::super::super::error::ConnectionError;
 use super::super::open_failure::OpenFailure;
-use super::super::ChannelState;
+use super::super::{Channel, ChannelState};
 use super::state::State;
 use super::{DirectTcpIp, DirectTcpIpParams};
 use crate::connection::channel::PollResult;
@@ -56,7 +56,7 @@ impl ChannelState for StateOpeningOutbound {
     ) -> Poll<Result<PollResult, ConnectionError>> {
         if let Some(params) = &self.params {
             let prm = SshCodec::encode(params)?;
-            let msg = self.state.msg_open(prm);
+            let msg = self.state.msg_open(DirectTcpIp::NAME, prm);
             ready!(t.poll_send(cx, &msg))?;
             self.params = None;
         }
@@ -71,6 +71,19 @@ impl SessionClient {
         map_err!(self, response.await)
     }
 
+    /// Request agent forwarding (`auth-agent-req@openssh.com`).
+    ///
+    /// The peer will then open `auth-agent@openssh.com` channels which are passed to
+    /// [ConnectionHandler::on_auth_agent_request](crate::connection::ConnectionHandler::on_auth_agent_request).
+    pub async fn agent_forwarding(
+        &mut self,
+        want_reply: bool,
+    ) -> Result<Result<(), RequestFailure>, ConnectionError> {
+        let param = SessionReq2::AgentForwarding;
+        let response = self.req_tx.req_unit(param, want_reply);
+        map_err!(self, response.await)
+    }
+
     /// Execute a remote shell.
     pub async fn shell(self) -> Result<Result<Process, RequestFailure<Self>>, ConnectionError> {
         let mut s = self;
@@ -303,6 +303,15 @@ impl ChannelState for ClientState2 {
                         };
                         ready!(t.poll_send(cx, &msg))?;
                     }
+                    SessionReq2::AgentForwarding => {
+                        let msg = MsgChannelRequest {
+                            recipient_channel: self.rid,
+                            request: "auth-agent-req@openssh.com",
+                            want_reply,
+                            specific: (),
+                        };
+                        ready!(t.poll_send(cx, &msg))?;
+                    }
                     SessionReq2::Shell => {
                         let msg = MsgChannelRequest {
                             recipient_channel: self.rid,
@@ -403,6 +412,7 @@ pub enum SessionRes2 {
 pub enum SessionReq2 {
     Env((String, String)),
     Pty(PtySpecification),
+    AgentForwarding,
     Shell,
     Exec(String),
     Subsystem(String),
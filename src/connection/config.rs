@@ -1,3 +1,7 @@
+#[cfg(doc)]
+use super::ConnectionError;
+use std::time::Duration;
+
 #[derive(Clone, Debug)]
 pub struct ConnectionConfig {
     /// The maximum number of queued requests/replies per connection.
@@ -21,6 +25,26 @@ pub struct ConnectionConfig {
     ///
     /// Defaults to 32kB.
     pub channel_max_packet_size: u32,
+    /// Whether to keep the known host keys up to date (`UpdateHostKeys`).
+    ///
+    /// A server announces all its host keys after authentication (`hostkeys-00@openssh.com`).
+    /// A client requests proof of the new keys and passes them to its host verifier.
+    ///
+    /// Defaults to true.
+    pub host_keys_update: bool,
+    /// The interval of keep-alive requests sent to the peer (like `ServerAliveInterval`).
+    ///
+    /// Requests are only sent after the peer has been silent for this long: Any inbound message
+    /// restarts the interval. The connection is terminated with
+    /// [ConnectionError::KeepAliveTimeout] when [keepalive_count_max](Self::keepalive_count_max)
+    /// requests in a row remained unanswered.
+    ///
+    /// Defaults to `None` (disabled).
+    pub keepalive_interval: Option<Duration>,
+    /// The number of unanswered keep-alive requests after which the connection is terminated.
+    ///
+    /// Defaults to 3.
+    pub keepalive_count_max: u32,
 }
 
 impl Default for ConnectionConfig {
@@ -30,6 +54,9 @@ impl Default for ConnectionConfig {
             channel_max_count: 256,
             channel_max_buffer_size: 1024 * 1024,
             channel_max_packet_size: 32768,
+            host_keys_update: true,
+            keepalive_interval: None,
+            keepalive_count_max: 3,
         }
     }
 }
@@ -45,5 +72,8 @@ mod tests {
         assert_eq!(c.channel_max_count, 256);
         assert_eq!(c.channel_max_buffer_size, 1024 * 1024);
         assert_eq!(c.channel_max_packet_size, 32768);
+        assert!(c.host_keys_update);
+        assert_eq!(c.keepalive_interval, None);
+        assert_eq!(c.keepalive_count_max, 3);
     }
 }
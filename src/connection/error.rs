@@ -33,6 +33,7 @@ pub enum ConnectionError {
     ChannelWindowSizeExceeded,
     ChannelWindowSizeOverflow,
     GlobalReplyUnexpected,
+    KeepAliveTimeout,
     ResourceExhaustion,
     Dropped,
 }
@@ -61,7 +62,7 @@ impl From<OpenFailure> for ConnectionError {
     }
 }
 
-impl <T> From<RequestFailure<T>> for ConnectionError {
+impl<T> From<RequestFailure<T>> for ConnectionError {
     fn from(_: RequestFailure<T>) -> Self {
         Self::ChannelRequestFailure
     }
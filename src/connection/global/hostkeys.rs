@@ -1,9 +1,162 @@
 use super::*;
+use crate::agent::AuthAgent;
+use crate::identity::{Identity, Signature};
+use crate::util::secret::Secret;
 
+/// The announcement of all host keys of a server after authentication (`UpdateHostKeys`).
 #[derive(Debug)]
 pub struct HostKeys;
 
 impl Global for HostKeys {
     const NAME: &'static str = "hostkeys-00@openssh.com";
-    type RequestData = Vec<u8>;
+    type RequestData = HostKeyList;
+}
+
+/// The request to prove the possession of announced host keys.
+///
+/// The server answers with one signature per key in the same order.
+#[derive(Debug)]
+pub struct HostKeysProve;
+
+impl HostKeysProve {
+    /// The data signed for each host key.
+    pub fn signed_data(session_id: &Secret, host_key: &Identity) -> Option<Vec<u8>> {
+        struct Data<'a>(&'a Secret, &'a Identity);
+        impl SshEncode for Data<'_> {
+            fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+                e.push_str_framed(HostKeysProve::NAME)?;
+                e.push(self.0)?;
+                e.push(self.1)
+            }
+        }
+        SshCodec::encode(&Data(session_id, host_key)).ok()
+    }
+
+    /// Check that there is a valid signature for each host key (in the same order).
+    pub fn verify(session_id: &Secret, host_keys: &[Identity], signatures: &SignatureList) -> bool {
+        host_keys.len() == signatures.0.len()
+            && host_keys.iter().zip(&signatures.0).all(|(key, sig)| {
+                Self::signed_data(session_id, key)
+                    .is_some_and(|data| sig.verify(key, &data).is_ok())
+            })
+    }
+
+    /// Sign the data for each host key with the agent.
+    ///
+    /// Returns `None` if any of the keys is not held by the agent.
+    pub(crate) async fn prove(
+        agent: &dyn AuthAgent,
+        session_id: &Secret,
+        host_keys: &[Identity],
+    ) -> Option<SignatureList> {
+        let ids = agent.identities().await.ok()?;
+        let mut sigs = Vec::with_capacity(host_keys.len());
+        for key in host_keys {
+            if !ids.iter().any(|(id, _)| id == key) {
+                log::debug!(
+                    "Host keys: Refused to prove unknown key {}",
+                    key.fingerprint()
+                );
+                return None;
+            }
+            let data = Self::signed_data(session_id, key)?;
+            sigs.push(agent.signature(key, &data, 0).await.ok()??);
+        }
+        Some(SignatureList(sigs))
+    }
+}
+
+impl Global for HostKeysProve {
+    const NAME: &'static str = "hostkeys-prove-00@openssh.com";
+    type RequestData = HostKeyList;
+}
+
+impl GlobalWantReply for HostKeysProve {
+    type ResponseData = SignatureList;
+}
+
+/// A list of host keys (framed and concatenated until the end of data).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HostKeyList(pub Vec<Identity>);
+
+impl SshEncode for HostKeyList {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        self.0.iter().try_for_each(|id| e.push(id))
+    }
+}
+
+impl SshDecode for HostKeyList {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        let mut ids = vec![];
+        while d.expect_eoi().is_none() {
+            ids.push(d.take()?);
+        }
+        Some(Self(ids))
+    }
+}
+
+/// A list of signatures (framed and concatenated until the end of data).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SignatureList(pub Vec<Signature>);
+
+impl SshEncode for SignatureList {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        self.0.iter().try_for_each(|sig| e.push(sig))
+    }
+}
+
+impl SshDecode for SignatureList {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        let mut sigs = vec![];
+        while d.expect_eoi().is_none() {
+            sigs.push(d.take()?);
+        }
+        Some(Self(sigs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::KeyStore;
+    use crate::identity::ssh_ed25519::SshEd25519PrivateKey;
+
+    #[test]
+    fn test_host_key_list_01() {
+        let list = HostKeyList(vec![Identity::from(vec![1]), Identity::from(vec![2, 3])]);
+        let buf = [0, 0, 0, 1, 1, 0, 0, 0, 2, 2, 3];
+        assert_eq!(SshCodec::encode(&list).unwrap(), buf);
+        assert_eq!(SshCodec::decode(&buf[..]), Ok(list));
+        assert_eq!(SshCodec::decode(&[][..]), Ok(HostKeyList::default()));
+        assert!(SshCodec::decode::<HostKeyList>(&[0, 0, 0, 2, 1][..]).is_err());
+    }
+
+    #[test]
+    fn test_signature_list_01() {
+        let key = SshEd25519PrivateKey::new([1; 32]);
+        let sid = Secret::new(b"session");
+        let data = HostKeysProve::signed_data(&sid, &key.identity()).unwrap();
+        let list = SignatureList(vec![key.sign(&data)]);
+        let buf = SshCodec::encode(&list).unwrap();
+        let list: SignatureList = SshCodec::decode(&buf).unwrap();
+        assert!(list.0[0].verify(&key.identity(), &data).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_prove_verify_01() {
+        let key1 = SshEd25519PrivateKey::new([1; 32]);
+        let key2 = SshEd25519PrivateKey::new([2; 32]);
+        let agent = KeyStore::new();
+        agent.add(key1.clone().into(), "key1");
+        let sid = Secret::new(b"session");
+        let keys = [key1.identity()];
+        let sigs = HostKeysProve::prove(&agent, &sid, &keys).await.unwrap();
+        assert!(HostKeysProve::verify(&sid, &keys, &sigs));
+        assert!(!HostKeysProve::verify(&Secret::new(b"other"), &keys, &sigs));
+        assert!(!HostKeysProve::verify(&sid, &[key2.identity()], &sigs));
+        assert!(!HostKeysProve::verify(&sid, &[], &sigs));
+        // Keys not held by the agent
+        let keys = [key1.identity(), key2.identity()];
+        assert!(HostKeysProve::prove(&agent, &sid, &keys).await.is_none());
+    }
 }
@@ -1,3 +1,4 @@
+use super::channel::auth_agent::AuthAgentRequest;
 use super::channel::direct_tcpip::DirectTcpIpRequest;
 use super::channel::session::SessionRequest;
 use super::global::{GlobalRequest, GlobalRequestWantReply};
@@ -17,6 +18,10 @@ pub trait ConnectionHandler: Send + Sync + 'static {
         log::error!("HANDLER ON_DIRECT_TCPIP_REQUEST {:?}", request)
     }
 
+    fn on_auth_agent_request(&mut self, request: AuthAgentRequest) {
+        log::error!("HANDLER ON_AUTH_AGENT_REQUEST {:?}", request)
+    }
+
     fn on_session_request(&mut self, request: SessionRequest) {
         log::error!("HANDLER ON_SESSION_REQUEST {:?}", request)
     }
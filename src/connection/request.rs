@@ -1,3 +1,4 @@
+use super::channel::AuthAgentChannel;
 use super::channel::DirectTcpIp;
 use super::channel::DirectTcpIpParams;
 use super::channel::OpenFailure;
@@ -18,6 +19,9 @@ pub enum Request {
         params: DirectTcpIpParams,
         reply: Sender<Result<DirectTcpIp, OpenFailure>>,
     },
+    OpenAuthAgent {
+        reply: Sender<Result<AuthAgentChannel, OpenFailure>>,
+    },
 }
 
 impl Request {
@@ -34,4 +38,10 @@ impl Request {
         let self_ = Self::OpenDirectTcpIp { params, reply: tx };
         (self_, rx)
     }
+
+    pub fn open_auth_agent() -> (Self, Receiver<Result<AuthAgentChannel, OpenFailure>>) {
+        let (tx, rx) = channel();
+        let self_ = Self::OpenAuthAgent { reply: tx };
+        (self_, rx)
+    }
 }
@@ -1,3 +1,4 @@
+use super::channel::auth_agent::AuthAgentChannel;
 use super::channel::direct_tcpip::DirectTcpIp;
 use super::channel::session;
 use super::channel::session::*;
@@ -10,6 +11,7 @@ use super::handler::ConnectionHandler;
 use super::msg::*;
 use super::request::*;
 use crate::connection::channel::PollResult;
+use crate::identity::Identity;
 use crate::transport::Message;
 use crate::transport::{DisconnectReason, Transport, TransportError};
 use crate::util::codec::*;
@@ -21,6 +23,7 @@ use std::task::{ready, Context, Poll};
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 use tokio::sync::watch;
+use tokio::time::{sleep, Instant, Sleep};
 
 pub struct ConnectionState {
     /// Config
@@ -33,6 +36,15 @@ pub struct ConnectionState {
     requests_head: Option<Request>,
     /// Async bounded queue of requests to process
     requests_queue: mpsc::Receiver<Request>,
+    /// Queue of requests issued by the connection itself (like host key announcements)
+    requests_internal_tx: mpsc::UnboundedSender<Request>,
+    requests_internal_rx: mpsc::UnboundedReceiver<Request>,
+    /// Whether the peer already announced its host keys (only the first announcement counts)
+    host_keys_seen: bool,
+    /// Timer for the next keep-alive request (if enabled)
+    keepalive: Option<Pin<Box<Sleep>>>,
+    /// Number of keep-alive requests sent since the peer was last heard from
+    keepalive_missed: u32,
     /// Ordered list of transmitted global requests awaiting reply
     requests_replies: VecDeque<oneshot::Sender<Result<Vec<u8>, ()>>>,
     /// Next global request reply ready for transmission
@@ -61,12 +73,18 @@ impl ConnectionState {
         error_tx: watch::Sender<Option<Arc<ConnectionError>>>,
         error_rx: watch::Receiver<Option<Arc<ConnectionError>>>,
     ) -> Self {
-        Self {
+        let (requests_internal_tx, requests_internal_rx) = mpsc::unbounded_channel();
+        let self_ = Self {
             config: config.clone(),
             handler,
             transport,
             requests_head: None,
             requests_queue: requests,
+            requests_internal_tx,
+            requests_internal_rx,
+            host_keys_seen: false,
+            keepalive: config.keepalive_interval.map(|i| Box::pin(sleep(i))),
+            keepalive_missed: 0,
             requests_replies: VecDeque::new(),
             replies_head: None,
             replies_queue: VecDeque::new(),
@@ -75,7 +93,11 @@ impl ConnectionState {
             close_tx,
             error_tx,
             error_rx,
+        };
+        if self_.config.host_keys_update {
+            self_.announce_host_keys();
         }
+        self_
     }
 
     /// Poll the connection and make progress.
@@ -101,6 +123,7 @@ impl ConnectionState {
         ready!(self.poll_close(cx))?;
         ready!(self.poll_transport(cx))?;
         ready!(self.poll_replies(cx))?;
+        ready!(self.poll_keepalive(cx))?;
         ready!(self.poll_requests(cx))?;
         ready!(self.poll_channels(cx))?;
         ready!(self.poll_flush(cx))?;
@@ -169,6 +192,11 @@ impl ConnectionState {
 
         // Poll the transport for the next message available.
         while let Some(buf) = ready!(self.transport.poll_receive_buf(cx))? {
+            // The peer is alive: Restart the keep-alive interval (if enabled)
+            if let (Some(timer), Some(i)) = (&mut self.keepalive, self.config.keepalive_interval) {
+                self.keepalive_missed = 0;
+                timer.as_mut().reset(Instant::now() + i);
+            }
             // Dispatch message (must not block; message MUST be dispatched)
             match *buf.get(0).unwrap_or(&0) {
                 <MsgChannelOpen as Message>::NUMBER => {
@@ -186,6 +214,12 @@ impl ConnectionState {
                                 self.handler.on_direct_tcpip_request(req);
                                 self.channels[lid as usize] = Some(cst);
                             }
+                            AuthAgentChannel::NAME => {
+                                let (cst, req) =
+                                    AuthAgentChannel::open_in(&self.config, &msg, lid)?;
+                                self.handler.on_auth_agent_request(req);
+                                self.channels[lid as usize] = Some(cst);
+                            }
                             _ => {
                                 let e = OpenFailure::UNKNOWN_CHANNEL_TYPE;
                                 self.channels_reject.push_back((msg.sender_channel, e));
@@ -264,14 +298,22 @@ impl ConnectionState {
                 <MsgGlobalRequest as Message>::NUMBER => {
                     let msg: MsgGlobalRequest = SshCodec::decode(buf)?;
                     log::debug!("<< {:?}", msg);
+                    let name: String = msg.name.into();
+                    let data: Vec<u8> = msg.data.into();
                     if msg.want_reply {
                         let (s, r) = oneshot::channel();
                         self.replies_queue.push_back(r);
-                        let request =
-                            GlobalRequestWantReply::new(msg.name.into(), msg.data.into(), s);
-                        self.handler.on_request_want_reply(request);
+                        if name == HostKeysProve::NAME && self.transport.host_key_agent().is_some()
+                        {
+                            self.prove_host_keys(&data, s);
+                        } else {
+                            let request = GlobalRequestWantReply::new(name, data, s);
+                            self.handler.on_request_want_reply(request);
+                        }
+                    } else if name == HostKeys::NAME && self.config.host_keys_update {
+                        self.update_host_keys(&data);
                     } else {
-                        let request = GlobalRequest::new(msg.name.into(), msg.data.into());
+                        let request = GlobalRequest::new(name, data);
                         self.handler.on_request(request);
                     }
                 }
@@ -363,6 +405,34 @@ impl ConnectionState {
         Poll::Ready(Ok(()))
     }
 
+    /// Queue a keep-alive request whenever the interval has elapsed (if enabled).
+    ///
+    /// The timer is reset by any inbound message (see [Self::poll_transport]), so requests are
+    /// only sent while the peer is idle. Returns `Ready(Err(_))` if too many requests in a row
+    /// remained unanswered. The [Context] is registered for wakeup when the timer elapses next.
+    fn poll_keepalive(&mut self, cx: &mut Context) -> Poll<Result<(), ConnectionError>> {
+        let (Some(timer), Some(interval)) = (&mut self.keepalive, self.config.keepalive_interval)
+        else {
+            return Poll::Ready(Ok(()));
+        };
+        while timer.as_mut().poll(cx).is_ready() {
+            if self.keepalive_missed >= self.config.keepalive_count_max {
+                return Poll::Ready(Err(ConnectionError::KeepAliveTimeout));
+            }
+            self.keepalive_missed += 1;
+            // The reply itself is of no interest: Receiving it resets the timer
+            let (tx, _) = oneshot::channel();
+            let name = KeepAlive::NAME;
+            let data = Vec::new();
+            let reply = Some(tx);
+            let _ = self
+                .requests_internal_tx
+                .send(Request::Global { name, data, reply });
+            timer.as_mut().reset(Instant::now() + interval);
+        }
+        Poll::Ready(Ok(()))
+    }
+
     /// Try processing local requests (global and channel open, if any).
     ///
     /// This function returns `Ready(Ok(_))` if all requests have been processed and `Pending` on
@@ -372,6 +442,8 @@ impl ConnectionState {
         loop {
             let cr = if let Some(cr) = self.requests_head.take() {
                 cr
+            } else if let Poll::Ready(Some(cr)) = self.requests_internal_rx.poll_recv(cx) {
+                cr
             } else {
                 match self.requests_queue.poll_recv(cx) {
                     Poll::Pending => return Poll::Ready(Ok(())),
@@ -407,6 +479,15 @@ impl ConnectionState {
                         reply.send(Err(e)).unwrap_or(());
                     }
                 }
+                Request::OpenAuthAgent { reply } => {
+                    if let Some(lid) = self.alloc_channel_id() {
+                        let channel = AuthAgentChannel::open_out(&self.config, lid, reply);
+                        self.channels[lid as usize] = Some(channel);
+                    } else {
+                        let e = OpenFailure::RESOURCE_SHORTAGE;
+                        reply.send(Err(e)).unwrap_or(());
+                    }
+                }
                 Request::OpenDirectTcpIp { reply, params } => {
                     if let Some(lid) = self.alloc_channel_id() {
                         let channel = DirectTcpIp::open_out(&self.config, lid, reply, params)?;
@@ -470,6 +551,110 @@ impl ConnectionState {
         self.transport.poll_flush(cx).map_err(Into::into)
     }
 
+    /// Announce all host keys to the client (server side only).
+    ///
+    /// The keys are requested from the host key agent and the announcement is queued as soon as
+    /// they are available.
+    fn announce_host_keys(&self) {
+        let Some(agent) = self.transport.host_key_agent() else {
+            return;
+        };
+        let agent = agent.clone();
+        let requests = self.requests_internal_tx.clone();
+        drop(tokio::spawn(async move {
+            let ids = match agent.identities().await {
+                Ok(ids) => ids.into_iter().map(|(id, _)| id),
+                Err(e) => return log::warn!("Host keys: Failed to list keys: {}", e),
+            };
+            let ids = HostKeyList(ids.filter(|id| id.as_cert().is_none()).collect());
+            if let Ok(data) = SshCodec::encode(&ids) {
+                let name = HostKeys::NAME;
+                let _ = requests.send(Request::Global {
+                    name,
+                    data,
+                    reply: None,
+                });
+            }
+        }));
+    }
+
+    /// Handle the host key announcement of the server (client side only).
+    ///
+    /// The server is asked to prove the possession of all keys other than the one it has been
+    /// verified with. The complete set of keys is then passed to the host verifier. Certificates,
+    /// announcements lacking the verified key and all but the first announcement are ignored.
+    fn update_host_keys(&mut self, data: &[u8]) {
+        let (Some((verifier, name, port)), Some((host_key, _))) =
+            (self.transport.host_verifier(), self.transport.host_key())
+        else {
+            return;
+        };
+        if std::mem::replace(&mut self.host_keys_seen, true) {
+            return log::debug!("Host keys: Ignoring repeated announcement");
+        }
+        let Ok(HostKeyList(ids)) = SshCodec::decode(data) else {
+            return log::debug!("Host keys: Invalid announcement");
+        };
+        let mut keys: Vec<Identity> = Vec::with_capacity(ids.len());
+        for id in ids {
+            if id.as_cert().is_none() && !keys.contains(&id) {
+                keys.push(id);
+            }
+        }
+        if !keys.contains(host_key) {
+            return log::debug!("Host keys: Announcement lacks the verified key");
+        }
+        let new: Vec<Identity> = keys.iter().filter(|k| *k != host_key).cloned().collect();
+        let verifier = verifier.clone();
+        let name: String = name.into();
+        let session_id = self.transport.session_id().clone();
+        let proof = if new.is_empty() {
+            None
+        } else {
+            let (tx, rx) = oneshot::channel();
+            let Ok(data) = SshCodec::encode(&HostKeyList(new.clone())) else {
+                return;
+            };
+            let name = HostKeysProve::NAME;
+            let reply = Some(tx);
+            let _ = self
+                .requests_internal_tx
+                .send(Request::Global { name, data, reply });
+            Some(rx)
+        };
+        drop(tokio::spawn(async move {
+            if let Some(proof) = proof {
+                let sigs = proof.await.ok().and_then(|r| r.ok());
+                let sigs = sigs.and_then(|data| SshCodec::decode(&data).ok());
+                if !sigs.is_some_and(|sigs| HostKeysProve::verify(&session_id, &new, &sigs)) {
+                    return log::warn!("Host keys: Server failed to prove the announced keys");
+                }
+            }
+            if let Err(e) = verifier.update_host_keys(&name, port, &keys).await {
+                log::warn!("Host keys: Failed to update: {:?}", e);
+            }
+        }));
+    }
+
+    /// Answer the client's request to prove the possession of host keys (server side only).
+    ///
+    /// The request is rejected (by dropping the reply sender) unless all keys can be signed.
+    fn prove_host_keys(&self, data: &[u8], reply: oneshot::Sender<Vec<u8>>) {
+        let (Some(agent), Ok(HostKeyList(keys))) =
+            (self.transport.host_key_agent(), SshCodec::decode(data))
+        else {
+            return;
+        };
+        let agent = agent.clone();
+        let session_id = self.transport.session_id().clone();
+        drop(tokio::spawn(async move {
+            let sigs = HostKeysProve::prove(agent.as_ref(), &session_id, &keys).await;
+            if let Some(data) = sigs.and_then(|sigs| SshCodec::encode(&sigs).ok()) {
+                let _ = reply.send(data);
+            }
+        }));
+    }
+
     fn alloc_channel_id(&mut self) -> Option<u32> {
         for (id, slot) in self.channels.iter().enumerate() {
             if slot.is_none() {
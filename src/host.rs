@@ -1,9 +1,18 @@
+mod chain;
 mod files;
+mod krl;
+mod pinned;
+mod prompt;
 
+pub use self::chain::*;
 pub use self::files::*;
+pub use self::krl::*;
+pub use self::pinned::*;
+pub use self::prompt::*;
 
 use crate::identity::*;
 use crate::util::BoxFuture;
+use std::net::IpAddr;
 
 /// This trait captures the capability of verifying host identities.
 ///
@@ -16,17 +25,36 @@ pub trait HostVerifier: std::fmt::Debug + Send + Sync + 'static {
     /// and its positive or negative outcome. `Ok(None)` shall be returned in case it is unknown
     /// whether the host shall be accepted or rejected. If no additional/alternative verification
     /// mechanism exists, this shall simply result in rejection as well.
+    ///
+    /// `addr` is the address the host name has been resolved to (if known). Verifiers may check
+    /// it as well in order to detect DNS spoofing.
     fn verify(
         &self,
         name: &str,
         port: u16,
+        addr: Option<IpAddr>,
         identity: &Identity,
     ) -> BoxFuture<Result<(), HostVerificationError>>;
+
+    /// Learn the complete set of keys a host proved to possess (like `UpdateHostKeys`).
+    ///
+    /// The host has been verified with one of the keys before. Verifiers that do not persist
+    /// host keys ignore this (which is the default).
+    fn update_host_keys(
+        &self,
+        _name: &str,
+        _port: u16,
+        _identities: &[Identity],
+    ) -> BoxFuture<Result<(), HostVerificationError>> {
+        Box::pin(async { Ok(()) })
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum HostVerificationError {
     Unverifiable,
+    /// The host is known with another key (the key has changed or the host is spoofed)
+    KeyMismatch,
     KeyRevoked,
     CertError(CertError),
     OtherError(String),
@@ -48,6 +76,7 @@ impl std::fmt::Display for HostVerificationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Unverifiable => write!(f, "Key/identity not found"),
+            Self::KeyMismatch => write!(f, "Key differs from the known key"),
             Self::KeyRevoked => write!(f, "Key has been revoked"),
             Self::CertError(e) => write!(f, "{}", e),
             Self::OtherError(e) => write!(f, "{}", e),
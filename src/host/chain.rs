@@ -0,0 +1,110 @@
+use super::*;
+use std::sync::Arc;
+
+/// Consults several verifiers in order until one of them decides.
+///
+/// A verifier that cannot decide ([HostVerificationError::Unverifiable]) passes on to the
+/// next one. Any other error (like a revoked or changed key) rejects the host immediately. The
+/// host is unverifiable if no verifier accepts it.
+#[derive(Clone, Debug, Default)]
+pub struct HostVerifierChain {
+    verifiers: Vec<Arc<dyn HostVerifier>>,
+}
+
+impl HostVerifierChain {
+    pub fn new(verifiers: Vec<Arc<dyn HostVerifier>>) -> Self {
+        Self { verifiers }
+    }
+
+    /// Append a verifier to the chain.
+    pub fn with(mut self, verifier: Arc<dyn HostVerifier>) -> Self {
+        self.verifiers.push(verifier);
+        self
+    }
+}
+
+impl HostVerifier for HostVerifierChain {
+    fn verify(
+        &self,
+        name: &str,
+        port: u16,
+        addr: Option<IpAddr>,
+        identity: &Identity,
+    ) -> BoxFuture<Result<(), HostVerificationError>> {
+        let verifiers = self.verifiers.clone();
+        let name: String = name.into();
+        let identity = identity.clone();
+        Box::pin(async move {
+            for verifier in verifiers {
+                match verifier.verify(&name, port, addr, &identity).await {
+                    Err(HostVerificationError::Unverifiable) => continue,
+                    result => return result,
+                }
+            }
+            Err(HostVerificationError::Unverifiable)
+        })
+    }
+
+    /// All verifiers learn the keys.
+    fn update_host_keys(
+        &self,
+        name: &str,
+        port: u16,
+        identities: &[Identity],
+    ) -> BoxFuture<Result<(), HostVerificationError>> {
+        let verifiers = self.verifiers.clone();
+        let name: String = name.into();
+        let identities = identities.to_vec();
+        Box::pin(async move {
+            for verifier in verifiers {
+                verifier.update_host_keys(&name, port, &identities).await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Fixed(Result<(), HostVerificationError>);
+
+    impl HostVerifier for Fixed {
+        fn verify(
+            &self,
+            _: &str,
+            _: u16,
+            _: Option<IpAddr>,
+            _: &Identity,
+        ) -> BoxFuture<Result<(), HostVerificationError>> {
+            let result = self.0.clone();
+            Box::pin(async move { result })
+        }
+    }
+
+    async fn verify(
+        results: Vec<Result<(), HostVerificationError>>,
+    ) -> Result<(), HostVerificationError> {
+        let chain = results
+            .into_iter()
+            .fold(HostVerifierChain::default(), |c, r| {
+                c.with(Arc::new(Fixed(r)))
+            });
+        chain
+            .verify("localhost", 22, None, &Identity::from(vec![]))
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_verify_01() {
+        use HostVerificationError::*;
+        assert!(matches!(verify(vec![]).await, Err(Unverifiable)));
+        assert!(verify(vec![Err(Unverifiable), Ok(())]).await.is_ok());
+        let r = verify(vec![Err(Unverifiable), Err(KeyMismatch), Ok(())]).await;
+        assert!(matches!(r, Err(KeyMismatch)));
+        let r = verify(vec![Err(Unverifiable), Err(Unverifiable)]).await;
+        assert!(matches!(r, Err(Unverifiable)));
+    }
+}
@@ -1,78 +1,268 @@
+mod cache;
 mod line;
 mod pattern;
 
+use self::cache::*;
 use self::line::*;
+use self::pattern::*;
 use super::*;
-use tokio::io::AsyncRead;
-use tokio::fs::File;
-use std::path::PathBuf;
+use rand_core::{OsRng, RngCore};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// A `known_hosts` file processor and verifier.
 ///
-/// The default instance contains the common `known_hosts` file locations.
+/// The default instance contains the common `known_hosts` file locations. New entries are
+/// written to the last file (which is the user's file by convention).
+///
+/// The files are parsed once and kept in memory as long as they do not change (clones share
+/// this cache). Writes to the user file are serialized between clones.
 #[derive(Clone, Debug)]
 pub struct KnownHosts {
     paths: Vec<PathBuf>,
+    hash_names: bool,
+    check_host_ip: bool,
+    cache: Arc<KnownHostsCache>,
+    writing: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// The result of looking up a host and key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Status {
+    /// No entry for this host and key algorithm
+    Unknown,
+    /// The host is known with another key of the same algorithm
+    Mismatch,
+    /// The key has been found for this host
+    Found,
 }
 
 impl KnownHosts {
-    async fn query(
+    /// Create a processor for the given files (system wide files first, user file last).
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        Self {
+            paths,
+            hash_names: false,
+            check_host_ip: false,
+            cache: Default::default(),
+            writing: Default::default(),
+        }
+    }
+
+    /// Write host names of new entries as hashes (like `HashKnownHosts yes`).
+    pub fn hash_names(mut self, hash_names: bool) -> Self {
+        self.hash_names = hash_names;
+        self
+    }
+
+    /// Check the host's address as well as its name (like `CheckHostIP yes`).
+    ///
+    /// A key that is known for the name but differs from the key known for the address is
+    /// rejected (the name might have been resolved to a spoofed address). A warning is logged if
+    /// the address is not known at all.
+    pub fn check_host_ip(mut self, check_host_ip: bool) -> Self {
+        self.check_host_ip = check_host_ip;
+        self
+    }
+
+    /// Append an entry for the host and key to the user file.
+    pub async fn add(&self, name: &str, port: u16, id: &Identity) -> Result<(), std::io::Error> {
+        use tokio::io::AsyncWriteExt;
+        let path = self.user_file()?;
+        Self::create_parent(path).await?;
+        let line = self.line(name, port, id);
+        let _writing = self.writing.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        self.cache.invalidate(path);
+        Ok(())
+    }
+
+    /// Replace the host's keys of the same algorithm as `id` in the user file by `id`.
+    ///
+    /// Only entries for exactly this host are replaced. Entries with several hosts or wildcard
+    /// patterns as well as marked entries (like `@cert-authority`) are kept unchanged.
+    pub async fn update(&self, name: &str, port: u16, id: &Identity) -> Result<(), std::io::Error> {
+        let host = Self::host(name, port);
+        let line = self.line(name, port, id);
+        self.rewrite(|text| {
+            let mut out = Self::retain(text, |line| {
+                let key = KnownHostsLine(line).exact_entry(&host);
+                key.is_none_or(|key| key.algorithm() != id.algorithm())
+            });
+            out.push_str(&line);
+            out
+        })
+        .await
+    }
+
+    /// Replace the host's keys in the user file by the given set of keys.
+    ///
+    /// New keys are added and keys no longer in the set are removed (like `UpdateHostKeys`).
+    /// As with [update](Self::update), only entries for exactly this host are touched. Nothing
+    /// is changed unless the user file has such an entry (the host might be known otherwise).
+    pub async fn update_all(
         &self,
         name: &str,
         port: u16,
-        id: &Identity,
-    ) -> Result<bool, HostVerificationError> {
-        let host;
-        let host = match port {
-            22 => name,
-            _ => {
-                host = format!("[{}]:{}", name, port);
-                &host
+        ids: &[Identity],
+    ) -> Result<(), std::io::Error> {
+        let host = Self::host(name, port);
+        self.rewrite(|text| {
+            let exact = |line: &str| KnownHostsLine(line).exact_entry(&host);
+            if !text.lines().any(|line| exact(line).is_some()) {
+                log::debug!("Host keys of {} not updated: No entry in user file", host);
+                return text.into();
+            }
+            let mut out = Self::retain(text, |line| exact(line).is_none_or(|k| ids.contains(&k)));
+            for id in ids {
+                // Revoked keys are not added either
+                let known = |line: &str| KnownHostsLine(line).test(&host, id, None).unwrap_or(true);
+                if !out.lines().any(known) {
+                    log::info!("Adding {} key for {} to known hosts", id.algorithm(), host);
+                    out.push_str(&self.line(name, port, id));
+                }
             }
+            out
+        })
+        .await
+    }
+
+    /// Rewrite the user file with the result of `edit` applied to its contents.
+    ///
+    /// The file is replaced atomically (by renaming a temporary file) and only if changed.
+    /// The temporary file name is unique so that other processes rewriting the same file do not
+    /// clobber it. Symbolic links are followed (the link target is replaced) and the file keeps
+    /// its permissions.
+    async fn rewrite<F: FnOnce(&str) -> String>(&self, edit: F) -> Result<(), std::io::Error> {
+        let user_file = self.user_file()?;
+        let _writing = self.writing.lock().await;
+        let path = &Self::resolve(user_file).await?;
+        Self::create_parent(path).await?;
+        let (text, permissions) = match tokio::fs::read_to_string(path).await {
+            Ok(text) => (text, Some(tokio::fs::metadata(path).await?.permissions())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (String::new(), None),
+            Err(e) => return Err(e),
         };
-        if let Some(cert) = id.as_cert() {
-            cert.verify_for_host(host)?;
-            self.query_files(host, id, Some(cert.authority())).await
+        let out = edit(&text);
+        if out == text {
+            return Ok(());
+        }
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(format!(
+            ".{}.{:08x}.tmp",
+            std::process::id(),
+            OsRng.next_u32()
+        ));
+        let result = async {
+            tokio::fs::write(&tmp, out).await?;
+            if let Some(permissions) = permissions {
+                tokio::fs::set_permissions(&tmp, permissions).await?;
+            }
+            tokio::fs::rename(&tmp, path).await
+        };
+        if let Err(e) = result.await {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e);
+        }
+        self.cache.invalidate(user_file);
+        Ok(())
+    }
+
+    /// Follow symbolic links to the file they point to (which need not exist).
+    async fn resolve(path: &Path) -> Result<PathBuf, std::io::Error> {
+        const MAX_LINKS: usize = 40;
+        let mut path = path.to_path_buf();
+        for _ in 0..MAX_LINKS {
+            match tokio::fs::read_link(&path).await {
+                // Relative targets are relative to the link's directory
+                Ok(target) => path = path.parent().unwrap_or(Path::new("")).join(target),
+                Err(e) if e.kind() == std::io::ErrorKind::InvalidInput => return Ok(path),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(path),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(std::io::Error::other("Too many levels of symbolic links"))
+    }
+
+    /// The lines of the text for which `keep` is true.
+    fn retain<F: Fn(&str) -> bool>(text: &str, keep: F) -> String {
+        let mut out = String::with_capacity(text.len());
+        for line in text.lines().filter(|line| keep(line)) {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Format an entry (including line break).
+    fn line(&self, name: &str, port: u16, id: &Identity) -> String {
+        let host = Self::host(name, port);
+        if self.hash_names {
+            format!("{} {}\n", KnownHostsPattern::hash(&host), id)
         } else {
-            self.query_files(host, id, None).await
+            format!("{} {}\n", host, id)
         }
     }
 
-    /// Loop through all files and lines until either a match or a revocation has been found.
-    async fn query_files(
+    fn user_file(&self) -> Result<&Path, std::io::Error> {
+        let path = self.paths.last().ok_or(std::io::ErrorKind::NotFound)?;
+        Ok(path)
+    }
+
+    async fn create_parent(path: &Path) -> Result<(), std::io::Error> {
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => {
+                let mut builder = tokio::fs::DirBuilder::new();
+                builder.recursive(true).mode(0o700).create(dir).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The host as written in `known_hosts` (with port unless it is the default port).
+    fn host(name: &str, port: u16) -> String {
+        match port {
+            22 => name.into(),
+            _ => format!("[{}]:{}", name, port),
+        }
+    }
+
+    async fn query(
         &self,
-        host: &str,
+        name: &str,
+        port: u16,
         id: &Identity,
-        ca: Option<&Identity>,
-    ) -> Result<bool, HostVerificationError> {
-        let mut found = false;
-        for path in &self.paths {
-            match File::open(path).await {
-                Ok(file) => {
-                    found |= Self::query_file(host, id, ca, file).await?;
-                }
-                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                    continue;
-                }
-                Err(e) => Err(e)?,
-            }
+    ) -> Result<Status, HostVerificationError> {
+        let host = Self::host(name, port);
+        if let Some(cert) = id.as_cert() {
+            cert.verify_for_host(&host)?;
+            self.query_files(&host, id, Some(cert.authority())).await
+        } else {
+            self.query_files(&host, id, None).await
         }
-        Ok(found)
     }
 
-    async fn query_file<T: AsyncRead + Unpin>(
+    /// Look up the host in all files until a revocation has been found.
+    async fn query_files(
+        &self,
         host: &str,
         id: &Identity,
         ca: Option<&Identity>,
-        file: T,
-    ) -> Result<bool, HostVerificationError> {
-        let mut found = false;
-        use tokio::io::AsyncBufReadExt;
-        let mut lines = tokio::io::BufReader::new(file).lines();
-        while let Some(line) = lines.next_line().await? {
-            found |= KnownHostsLine(&line).test(host, id, ca)?;
+    ) -> Result<Status, HostVerificationError> {
+        let mut status = Status::Unknown;
+        for path in &self.paths {
+            if let Some(index) = self.cache.index(path).await? {
+                status = status.max(index.query(host, id, ca)?);
+            }
         }
-        Ok(found)
+        Ok(status)
     }
 }
 
@@ -90,7 +280,7 @@ impl Default for KnownHosts {
             path.push("known_hosts");
             paths.push(path)
         }
-        Self { paths }
+        Self::new(paths)
     }
 }
 
@@ -99,17 +289,262 @@ impl HostVerifier for KnownHosts {
         &self,
         name: &str,
         port: u16,
+        addr: Option<IpAddr>,
         id: &Identity,
     ) -> BoxFuture<Result<(), HostVerificationError>> {
         let self_ = self.clone();
         let name: String = name.into();
         let id = id.clone();
         Box::pin(async move {
-            if self_.query(&name, port, &id).await? {
-                Ok(())
-            } else {
-                Err(HostVerificationError::Unverifiable)
+            let status = self_.query(&name, port, &id).await?;
+            // Certificates are bound to names (and the address is the name if given as such)
+            let addr = addr
+                .map(|addr| addr.to_string())
+                .filter(|addr| self_.check_host_ip && id.as_cert().is_none() && *addr != name);
+            if let Some(addr) = addr {
+                match (status, self_.query(&addr, port, &id).await?) {
+                    (Status::Found, Status::Mismatch) => {
+                        log::error!(
+                            "Host key for {} differs from the key for address {}",
+                            name,
+                            addr
+                        );
+                        return Err(HostVerificationError::KeyMismatch);
+                    }
+                    (Status::Found, Status::Unknown) => {
+                        log::warn!("Host key for {} is not known for address {}", name, addr);
+                    }
+                    (Status::Unknown, Status::Found) => {
+                        log::warn!("Host key for {} is only known for address {}", name, addr);
+                    }
+                    _ => (),
+                }
+            }
+            match status {
+                Status::Found => Ok(()),
+                Status::Mismatch => Err(HostVerificationError::KeyMismatch),
+                Status::Unknown => Err(HostVerificationError::Unverifiable),
             }
         })
     }
+
+    fn update_host_keys(
+        &self,
+        name: &str,
+        port: u16,
+        identities: &[Identity],
+    ) -> BoxFuture<Result<(), HostVerificationError>> {
+        let self_ = self.clone();
+        let name: String = name.into();
+        let identities = identities.to_vec();
+        Box::pin(async move { Ok(self_.update_all(&name, port, &identities).await?) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(name: &str) -> PathBuf {
+        let name = format!("shellfish-known-hosts-{}-{}", std::process::id(), name);
+        let path = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn public_key(line: &str) -> Identity {
+        Identity::from_authorized_key(line).unwrap().0
+    }
+
+    #[tokio::test]
+    async fn test_add_hashed_01() {
+        let path = path("hashed");
+        let known_hosts = KnownHosts::new(vec![path.clone()]).hash_names(true);
+        let host = public_key(include_str!("../../resources/ed25519-host.pub"));
+        let r = known_hosts.verify("example.com", 22, None, &host).await;
+        assert!(matches!(r, Err(HostVerificationError::Unverifiable)));
+        known_hosts.add("example.com", 22, &host).await.unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("|1|"));
+        assert!(!text.contains("example.com"));
+        assert!(known_hosts
+            .verify("example.com", 22, None, &host)
+            .await
+            .is_ok());
+        let r = known_hosts.verify("example.org", 22, None, &host).await;
+        assert!(matches!(r, Err(HostVerificationError::Unverifiable)));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_update_01() {
+        let path = path("update");
+        let old = public_key(include_str!("../../resources/ed25519-user.pub"));
+        let new = public_key(include_str!("../../resources/ed25519-host.pub"));
+        let text =
+            format!("example.com {old}\n# comment\nexample.com,other.com {old}\nother.com {old}\n");
+        std::fs::write(&path, text).unwrap();
+        let known_hosts = KnownHosts::new(vec![path.clone()]);
+        let r = known_hosts.verify("example.com", 22, None, &new).await;
+        assert!(matches!(r, Err(HostVerificationError::KeyMismatch)));
+        known_hosts.update("example.com", 22, &new).await.unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let expected =
+            format!("# comment\nexample.com,other.com {old}\nother.com {old}\nexample.com {new}\n");
+        assert_eq!(text, expected);
+        assert!(known_hosts
+            .verify("example.com", 22, None, &new)
+            .await
+            .is_ok());
+        assert!(known_hosts
+            .verify("other.com", 22, None, &old)
+            .await
+            .is_ok());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_update_all_01() {
+        let path = path("update-all");
+        let k1 = public_key(include_str!("../../resources/ed25519-user.pub"));
+        let k2 = public_key(include_str!("../../resources/ed25519-host.pub"));
+        let k3 = public_key(include_str!("../../resources/ed25519-ca.pub"));
+        let text = format!("[example.com]:2222 {k1}\n*.com {k1}\n[example.com]:2222 {k2}\n");
+        std::fs::write(&path, &text).unwrap();
+        let known_hosts = KnownHosts::new(vec![path.clone()]);
+        // Unknown host: nothing changes
+        known_hosts
+            .update_all("other.com", 22, std::slice::from_ref(&k3))
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+        // Remove k1 (but keep the wildcard entry), keep k2, add k3
+        let ids = [k2.clone(), k3.clone()];
+        known_hosts
+            .update_all("example.com", 2222, &ids)
+            .await
+            .unwrap();
+        let expected = format!("*.com {k1}\n[example.com]:2222 {k2}\n[example.com]:2222 {k3}\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_update_concurrent_01() {
+        let path = path("update-concurrent");
+        let old = public_key(include_str!("../../resources/ed25519-user.pub"));
+        let new = public_key(include_str!("../../resources/ed25519-host.pub"));
+        let hosts: Vec<String> = (0..16).map(|i| format!("host{}.com", i)).collect();
+        let text: String = hosts.iter().map(|h| format!("{h} {old}\n")).collect();
+        std::fs::write(&path, text).unwrap();
+        let known_hosts = KnownHosts::new(vec![path.clone()]);
+        let mut tasks = vec![];
+        for host in hosts.clone() {
+            let known_hosts = known_hosts.clone();
+            let new = new.clone();
+            tasks.push(tokio::spawn(async move {
+                known_hosts.update(&host, 22, &new).await.unwrap()
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        // No update got lost
+        for host in &hosts {
+            assert!(known_hosts.verify(host, 22, None, &new).await.is_ok());
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_update_symlink_01() {
+        use std::os::unix::fs::PermissionsExt;
+        let path = path("symlink");
+        let target = path.with_extension("target");
+        let old = public_key(include_str!("../../resources/ed25519-user.pub"));
+        let new = public_key(include_str!("../../resources/ed25519-host.pub"));
+        std::fs::write(&target, format!("example.com {old}\n")).unwrap();
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o600)).unwrap();
+        std::os::unix::fs::symlink(target.file_name().unwrap(), &path).unwrap();
+        let known_hosts = KnownHosts::new(vec![path.clone()]);
+        known_hosts.update("example.com", 22, &new).await.unwrap();
+        // The link is kept and the target replaced with the same permissions
+        assert!(std::fs::symlink_metadata(&path).unwrap().is_symlink());
+        let text = std::fs::read_to_string(&target).unwrap();
+        assert_eq!(text, format!("example.com {new}\n"));
+        let mode = std::fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&target);
+    }
+
+    #[tokio::test]
+    async fn test_check_host_ip_01() {
+        let path = path("check-host-ip");
+        let k1 = public_key(include_str!("../../resources/ed25519-user.pub"));
+        let k2 = public_key(include_str!("../../resources/ed25519-host.pub"));
+        let text = format!(
+            "example.com,192.0.2.1 {k1}
+example.org {k1}
+192.0.2.2 {k2}
+"
+        );
+        std::fs::write(&path, text).unwrap();
+        let ip1 = Some("192.0.2.1".parse().unwrap());
+        let ip2 = Some("192.0.2.2".parse().unwrap());
+        let ip3 = Some("192.0.2.3".parse().unwrap());
+        let known_hosts = KnownHosts::new(vec![path.clone()]);
+        assert!(known_hosts
+            .verify("example.com", 22, ip2, &k1)
+            .await
+            .is_ok());
+        let known_hosts = known_hosts.check_host_ip(true);
+        assert!(known_hosts
+            .verify("example.com", 22, ip1, &k1)
+            .await
+            .is_ok());
+        // Address not known: warning only
+        assert!(known_hosts
+            .verify("example.org", 22, ip3, &k1)
+            .await
+            .is_ok());
+        // Address known with another key
+        let r = known_hosts.verify("example.com", 22, ip2, &k1).await;
+        assert!(matches!(r, Err(HostVerificationError::KeyMismatch)));
+        let r = known_hosts.verify("example.net", 22, ip1, &k1).await;
+        assert!(matches!(r, Err(HostVerificationError::Unverifiable)));
+        assert!(known_hosts.verify("192.0.2.2", 22, ip2, &k2).await.is_ok());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_cache_reload_01() {
+        let path = path("cache-reload");
+        let k1 = public_key(include_str!("../../resources/ed25519-user.pub"));
+        let k2 = public_key(include_str!("../../resources/ed25519-host.pub"));
+        std::fs::write(&path, format!("example.com {k1}\n")).unwrap();
+        let known_hosts = KnownHosts::new(vec![path.clone()]);
+        assert!(known_hosts
+            .verify("example.com", 22, None, &k1)
+            .await
+            .is_ok());
+        // Changed by another process
+        std::fs::write(&path, format!("example.com {k2}\nexample.org {k1}\n")).unwrap();
+        let r = known_hosts.verify("example.com", 22, None, &k1).await;
+        assert!(matches!(r, Err(HostVerificationError::KeyMismatch)));
+        assert!(known_hosts
+            .verify("example.org", 22, None, &k1)
+            .await
+            .is_ok());
+        // Changed again right away without changing the size
+        std::fs::write(&path, format!("example.com {k1}\nexample.org {k2}\n")).unwrap();
+        assert!(known_hosts
+            .verify("example.com", 22, None, &k1)
+            .await
+            .is_ok());
+        std::fs::remove_file(&path).unwrap();
+        let r = known_hosts.verify("example.org", 22, None, &k1).await;
+        assert!(matches!(r, Err(HostVerificationError::Unverifiable)));
+    }
 }
@@ -0,0 +1,292 @@
+use super::line::*;
+use super::pattern::*;
+use super::{HostVerificationError, Status};
+use crate::identity::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// An in-memory index of `known_hosts` files.
+///
+/// Each file is parsed once and its entries are indexed by plain host name. A file is parsed
+/// again as soon as its modification time, size, inode or status change time changes.
+#[derive(Default)]
+pub struct KnownHostsCache {
+    files: Mutex<HashMap<PathBuf, Arc<KnownHostsIndex>>>,
+}
+
+impl KnownHostsCache {
+    /// Get the current index of a file (`None` if the file does not exist).
+    pub async fn index(&self, path: &Path) -> Result<Option<Arc<KnownHostsIndex>>, std::io::Error> {
+        let version = match tokio::fs::metadata(path).await {
+            Ok(meta) => FileVersion::new(&meta),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.invalidate(path);
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        if let Some(index) = self.files.lock().unwrap().get(path) {
+            if index.version == version && !index.version.is_racy() {
+                return Ok(Some(index.clone()));
+            }
+        }
+        let text = match tokio::fs::read_to_string(path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        log::debug!("Indexing known hosts file {}", path.display());
+        let index = Arc::new(KnownHostsIndex::new(version, &text));
+        let mut files = self.files.lock().unwrap();
+        files.insert(path.into(), index.clone());
+        Ok(Some(index))
+    }
+
+    /// Drop the index of a file (after it has been written).
+    pub fn invalidate(&self, path: &Path) {
+        self.files.lock().unwrap().remove(path);
+    }
+}
+
+impl std::fmt::Debug for KnownHostsCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "KnownHostsCache {{ ... }}")
+    }
+}
+
+/// The metadata that tells whether a file has changed.
+///
+/// Besides modification time and size, inode and status change time are compared on Unix (a
+/// file replaced by rename gets another inode, and the change time cannot be set by tools that
+/// preserve modification times). As modification times are coarse on some file systems, a file
+/// written within the second before it was read might change again unnoticed and is therefore
+/// considered [racy](Self::is_racy). On other platforms, a file changed without changing size
+/// while its modification time is preserved or set back is not detected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct FileVersion {
+    modified: Option<SystemTime>,
+    len: u64,
+    inode: u64,
+    changed: (i64, i64),
+}
+
+impl FileVersion {
+    /// Files modified less than this before they are read are parsed again on each use.
+    const RACY: Duration = Duration::from_secs(1);
+
+    fn new(meta: &std::fs::Metadata) -> Self {
+        let mut version = Self {
+            modified: meta.modified().ok(),
+            len: meta.len(),
+            ..Default::default()
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            version.inode = meta.ino();
+            version.changed = (meta.ctime(), meta.ctime_nsec());
+        }
+        version
+    }
+
+    /// Whether the file might change without changing its version.
+    fn is_racy(&self) -> bool {
+        match self.modified {
+            Some(modified) => SystemTime::now()
+                .duration_since(modified)
+                .map_or(true, |age| age < Self::RACY),
+            None => true,
+        }
+    }
+}
+
+/// The parsed entries of a single `known_hosts` file.
+pub struct KnownHostsIndex {
+    /// The version of the file when parsed
+    version: FileVersion,
+    /// All plain and `@cert-authority` entries
+    entries: Vec<Entry>,
+    /// Entries by plain host name
+    names: HashMap<String, Vec<usize>>,
+    /// Entries with hashed names or patterns (need to be tested for each host)
+    patterns: Vec<(String, usize)>,
+    /// Keys marked as `@revoked` (for all hosts)
+    revoked: Vec<PublicKey>,
+    /// Entries with hashed names or patterns matching a host (memorized per host)
+    matches: Mutex<HashMap<String, Arc<[usize]>>>,
+}
+
+struct Entry {
+    cert_authority: bool,
+    key: PublicKey,
+}
+
+impl KnownHostsIndex {
+    /// The maximum number of hosts for which pattern matches are memorized.
+    const MAX_MATCHES: usize = 4096;
+
+    fn new(version: FileVersion, text: &str) -> Self {
+        let mut self_ = Self {
+            version,
+            entries: vec![],
+            names: HashMap::new(),
+            patterns: vec![],
+            revoked: vec![],
+            matches: Default::default(),
+        };
+        for line in text.lines() {
+            let Some((marker, pattern, key)) = KnownHostsLine(line).parts() else {
+                continue;
+            };
+            let cert_authority = match marker {
+                None => false,
+                Some("@cert-authority") => true,
+                Some("@revoked") => {
+                    self_.revoked.push(key);
+                    continue;
+                }
+                Some(_) => continue,
+            };
+            let i = self_.entries.len();
+            self_.entries.push(Entry {
+                cert_authority,
+                key,
+            });
+            match KnownHostsPattern(pattern).names() {
+                Some(names) => {
+                    for name in names {
+                        self_.names.entry(name.into()).or_default().push(i);
+                    }
+                }
+                None => self_.patterns.push((pattern.into(), i)),
+            }
+        }
+        self_
+    }
+
+    /// Look up the host and key (see [KnownHostsLine::test] for the semantics).
+    pub fn query(
+        &self,
+        host: &str,
+        id: &PublicKey,
+        ca: Option<&PublicKey>,
+    ) -> Result<Status, HostVerificationError> {
+        if self
+            .revoked
+            .iter()
+            .any(|key| key.is_same_key(id) || Some(key) == ca)
+        {
+            return Err(HostVerificationError::KeyRevoked);
+        }
+        let names = self.names.get(host).map(Vec::as_slice).unwrap_or_default();
+        let patterns = self.matches(host);
+        let status = names
+            .iter()
+            .chain(patterns.iter())
+            .map(|i| self.entries[*i].status(id, ca))
+            .max();
+        Ok(status.unwrap_or(Status::Unknown))
+    }
+
+    fn matches(&self, host: &str) -> Arc<[usize]> {
+        if let Some(matches) = self.matches.lock().unwrap().get(host) {
+            return matches.clone();
+        }
+        let matches: Arc<[usize]> = self
+            .patterns
+            .iter()
+            .filter(|(pattern, _)| KnownHostsPattern(pattern).test(host))
+            .map(|(_, i)| *i)
+            .collect();
+        let mut memo = self.matches.lock().unwrap();
+        if memo.len() >= Self::MAX_MATCHES {
+            memo.clear();
+        }
+        memo.insert(host.into(), matches.clone());
+        matches
+    }
+}
+
+impl Entry {
+    fn status(&self, id: &PublicKey, ca: Option<&PublicKey>) -> Status {
+        if self.cert_authority {
+            if Some(&self.key) == ca {
+                return Status::Found;
+            }
+        } else if self.key.is_same_key(id) {
+            return Status::Found;
+        } else if ca.is_none() && self.key.algorithm() == id.algorithm() {
+            return Status::Mismatch;
+        }
+        Status::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public_key(line: &str) -> PublicKey {
+        Identity::from_authorized_key(line).unwrap().0
+    }
+
+    fn index(text: &str) -> KnownHostsIndex {
+        KnownHostsIndex::new(FileVersion::default(), text)
+    }
+
+    #[test]
+    fn test_query_01() {
+        let k1 = public_key(include_str!("../../../resources/ed25519-user.pub"));
+        let k2 = public_key(include_str!("../../../resources/ed25519-host.pub"));
+        let hash = KnownHostsPattern::hash("hashed.com");
+        let text = format!(
+            "# comment\na.com,b.com {k1}\n*.org,!c.org {k2}\n{hash} {k2}\n@cert-authority c.com {k1}"
+        );
+        let index = index(&text);
+        let query = |host, id| index.query(host, id, None).unwrap();
+        assert_eq!(query("a.com", &k1), Status::Found);
+        assert_eq!(query("b.com", &k2), Status::Mismatch);
+        assert_eq!(query("a.org", &k2), Status::Found);
+        assert_eq!(query("c.org", &k2), Status::Unknown);
+        assert_eq!(query("hashed.com", &k2), Status::Found);
+        assert_eq!(query("hashed.com", &k1), Status::Mismatch);
+        assert_eq!(query("c.com", &k1), Status::Unknown);
+        assert_eq!(index.query("c.com", &k2, Some(&k1)).unwrap(), Status::Found);
+        assert_eq!(
+            index.query("b.com", &k2, Some(&k2)).unwrap(),
+            Status::Unknown
+        );
+        assert_eq!(index.matches.lock().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_query_revoked_01() {
+        let k1 = public_key(include_str!("../../../resources/ed25519-user.pub"));
+        let k2 = public_key(include_str!("../../../resources/ed25519-host.pub"));
+        let index = index(&format!("a.com {k1}\n@revoked * {k1}\n"));
+        let r = index.query("b.com", &k1, None);
+        assert!(matches!(r, Err(HostVerificationError::KeyRevoked)));
+        let r = index.query("a.com", &k2, Some(&k1));
+        assert!(matches!(r, Err(HostVerificationError::KeyRevoked)));
+        assert_eq!(index.query("a.com", &k2, None).unwrap(), Status::Mismatch);
+    }
+
+    #[test]
+    fn test_query_cert_01() {
+        let key = public_key(include_str!("../../../resources/ed25519-host.pub"));
+        let cert = public_key(include_str!("../../../resources/ed25519-host-cert.pub"));
+        let ca = public_key(include_str!("../../../resources/ed25519-ca.pub"));
+        // A plain entry for the certified key is found
+        let plain = index(&format!("a.com {key}\n"));
+        assert_eq!(
+            plain.query("a.com", &cert, Some(&ca)).unwrap(),
+            Status::Found
+        );
+        // Revoking the certified key revokes the certificate
+        let revoked = index(&format!("@revoked * {key}\n"));
+        let r = revoked.query("a.com", &cert, Some(&ca));
+        assert!(matches!(r, Err(HostVerificationError::KeyRevoked)));
+    }
+}
@@ -19,32 +19,14 @@ impl<'a> KnownHostsLine<'a> {
         host_key: &PublicKey,
         host_ca_key: Option<&PublicKey>,
     ) -> Result<bool, HostVerificationError> {
-        // Split the line by whitespace. Complication is introduced by optional @-marker.
-        let mut ws = self.0.split_whitespace();
-        let w1 = ws.next().unwrap_or("");
-        let marker;
-        let pattern;
-        let algo;
-        let key;
-        if w1.starts_with('@') {
-            marker = Some(w1);
-            pattern = ws.next().unwrap_or("");
-            algo = ws.next().unwrap_or("");
-            key = ws.next().unwrap_or("");
-        } else {
-            marker = None; // internal use only
-            pattern = w1;
-            algo = ws.next().unwrap_or("");
-            key = ws.next().unwrap_or("");
-        }
+        let Some((marker, pattern, key)) = self.parts() else {
+            return Ok(false);
+        };
         // Reject the key if it has been revoked. For a certificate is it sufficient if either the
         // signed key or the signing key has been marked as revoked. The hostname is not checked.
         if marker == Some("@revoked") {
-            if let Ok(key) = base64::decode(key) {
-                let key = PublicKey::from(key);
-                if &key == host_key || Some(&key) == host_ca_key {
-                    return Err(HostVerificationError::KeyRevoked);
-                }
+            if key.is_same_key(host_key) || Some(&key) == host_ca_key {
+                return Err(HostVerificationError::KeyRevoked);
             }
             return Ok(false);
         }
@@ -52,28 +34,43 @@ impl<'a> KnownHostsLine<'a> {
         if !KnownHostsPattern(pattern).test(host_name) {
             return Ok(false);
         }
-        // Test key against `host_key` if no marker is present.
-        if marker == None && algo == host_key.algorithm() {
-            if let Ok(key) = base64::decode(key) {
-                let key = PublicKey::from(key);
-                if &key == host_key {
-                    return Ok(true);
-                }
-            }
-            return Ok(false);
+        match marker {
+            // Test key against `host_key` (or the key of the certificate) if no marker is present.
+            None => Ok(key.is_same_key(host_key)),
+            // Test key against `host_ca_key` if marker is `@cert-authority`.
+            Some("@cert-authority") => Ok(Some(&key) == host_ca_key),
+            Some(_) => Ok(false),
         }
-        // Test key against `host_ca_key` if marker is `@cert-authority`.
-        if marker == Some("@cert-authority") && Some(algo) == host_ca_key.map(|x| x.algorithm()) {
-            if let Ok(key) = base64::decode(key) {
-                let key = PublicKey::from(key);
-                if Some(&key) == host_ca_key {
-                    return Ok(true);
-                }
-            }
-            return Ok(false);
+    }
+
+    /// Return the key if the line is a plain entry for exactly this host and no other.
+    pub fn exact_entry(&self, host_name: &str) -> Option<PublicKey> {
+        match self.parts()? {
+            (None, pattern, key) if KnownHostsPattern(pattern).is_exact(host_name) => Some(key),
+            _ => None,
         }
-        Ok(false)
     }
+
+    /// Split off the optional @-marker and the hostname pattern. The rest is the key.
+    ///
+    /// Returns `None` for comments, empty and malformed lines.
+    pub fn parts(&self) -> Option<(Option<&'a str>, &'a str, PublicKey)> {
+        let (w1, rest) = split_word(self.0);
+        let (marker, pattern, key) = if w1.starts_with('@') {
+            let (pattern, key) = split_word(rest);
+            (Some(w1), pattern, key)
+        } else {
+            (None, w1, rest)
+        };
+        Some((marker, pattern, key.parse().ok()?))
+    }
+}
+
+/// Split the first whitespace separated word from the rest.
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    let i = s.find(char::is_whitespace).unwrap_or(s.len());
+    s.split_at(i)
 }
 
 #[cfg(test)]
@@ -1,6 +1,7 @@
 use crate::util::check;
-use crate::util::glob::Glob;
+use crate::util::glob::{Glob, GlobList};
 use hmac::{Hmac, Mac};
+use rand_core::{OsRng, RngCore};
 use sha1::Sha1;
 
 pub struct KnownHostsPattern<'a>(pub &'a str);
@@ -14,6 +15,42 @@ impl<'a> KnownHostsPattern<'a> {
         }
     }
 
+    /// Test whether the pattern denotes exactly this name (and no other).
+    ///
+    /// This is the case for the plain name itself or a hash of it, but not for lists or globs.
+    pub fn is_exact(&self, name: &str) -> bool {
+        if self.0.starts_with('|') {
+            self.test_hash(name).is_some()
+        } else {
+            self.0 == name
+        }
+    }
+
+    /// The names if the pattern is a plain list of names (without hashes, wildcards or negations).
+    ///
+    /// Names that can never match are omitted.
+    pub fn names(&self) -> Option<Vec<&'a str>> {
+        if self.0.starts_with('|') || self.0.contains(['*', '?', '!']) {
+            return None;
+        }
+        Some(
+            self.0
+                .split(',')
+                .filter(|name| Self::is_valid(name))
+                .collect(),
+        )
+    }
+
+    /// Hash a name with a random salt (like `HashKnownHosts yes`).
+    pub fn hash(name: &str) -> String {
+        let mut salt = [0; 20];
+        OsRng.fill_bytes(&mut salt);
+        let mut hmac = Hmac::<Sha1>::new_from_slice(&salt).expect("any key length");
+        hmac.update(name.as_ref());
+        let mac = hmac.finalize().into_bytes();
+        format!("|1|{}|{}", base64::encode(salt), base64::encode(mac))
+    }
+
     fn test_hash(&self, name: &str) -> Option<()> {
         const ALG_HMAC_SHA1: usize = 1;
         let mut known = self.0.split('|');
@@ -40,25 +77,17 @@ impl<'a> KnownHostsPattern<'a> {
     }
 
     fn test_globs(&self, name: &str) -> bool {
-        // Test all globs. Stop immediately on negated match or try all.
-        let mut result = false;
-        for glob in self.0.split(',') {
-            if let Some(glob) = glob.strip_prefix('!') {
-                if Self::test_glob(glob, name) {
-                    // Return early: This line must not be used for this host
-                    return false;
-                }
-            } else if Self::test_glob(glob, name) {
-                // DO NOT return early: Negated match might follow!
-                result = true
-            }
-        }
-        result
+        GlobList(self.0).test_by(name, Self::test_glob)
     }
 
     fn test_glob(glob: &str, name: &str) -> bool {
-        let valid = |c: char| c.is_ascii_alphanumeric() || ":.-*?".contains(c);
-        !glob.is_empty() && glob.chars().all(valid) && Glob(glob).test(name)
+        Self::is_valid(glob) && Glob(glob).test(name)
+    }
+
+    fn is_valid(glob: &str) -> bool {
+        // Brackets enclose names with non-default port (like `[example.com]:2222`)
+        let valid = |c: char| c.is_ascii_alphanumeric() || ":.-*?[]".contains(c);
+        !glob.is_empty() && glob.chars().all(valid)
     }
 }
 
@@ -118,6 +147,51 @@ mod tests {
         assert_eq!(test_host(pattern, "192.168.1.1"), false);
     }
 
+    #[test]
+    fn host_name_port_01() {
+        let pattern = "[example.com]:2222,[*.example.org]:*";
+
+        assert!(test_host(pattern, "[example.com]:2222"));
+        assert!(test_host(pattern, "[foo.example.org]:22"));
+        assert!(!test_host(pattern, "[example.com]:22"));
+        assert!(!test_host(pattern, "example.com"));
+    }
+
+    #[test]
+    fn host_name_hash_01() {
+        let pattern = KnownHostsPattern::hash("192.168.1.61");
+
+        assert!(test_host(&pattern, "192.168.1.61"));
+        assert!(!test_host(&pattern, "192.168.1.1"));
+        assert_ne!(pattern, KnownHostsPattern::hash("192.168.1.61"));
+    }
+
+    #[test]
+    fn host_name_exact_01() {
+        let hash = KnownHostsPattern::hash("example.com");
+
+        assert!(KnownHostsPattern("example.com").is_exact("example.com"));
+        assert!(KnownHostsPattern(&hash).is_exact("example.com"));
+        assert!(!KnownHostsPattern("*.com").is_exact("example.com"));
+        assert!(!KnownHostsPattern("example.com,other.com").is_exact("example.com"));
+    }
+
+    #[test]
+    fn host_name_names_01() {
+        let names = |pattern| KnownHostsPattern(pattern).names();
+
+        assert_eq!(
+            names("a.com,[b.com]:2222,,c_d"),
+            Some(vec!["a.com", "[b.com]:2222"])
+        );
+        assert_eq!(names("a.com,*.b.com"), None);
+        assert_eq!(names("a.com,!b.com"), None);
+        assert_eq!(
+            names("|1|F1E1KeoE/eEWhi10WpGv4OdiO6Y=|3988QV0VE8wmZL7suNrYQLITLCg="),
+            None
+        );
+    }
+
     #[test]
     fn host_name_test_06() {
         let pattern = "|2|F1E1KeoE/eEWhi10WpGv4OdiO6Y=|3988QV0VE8wmZL7suNrYQLITLCg=";
@@ -0,0 +1,82 @@
+use super::*;
+use std::sync::Arc;
+
+/// A verifier that rejects keys and certificates revoked by a [Krl] before consulting another
+/// verifier (like OpenSSH's `RevokedHostKeys`).
+#[derive(Clone, Debug)]
+pub struct KrlVerifier {
+    krl: Arc<Krl>,
+    verifier: Arc<dyn HostVerifier>,
+}
+
+impl KrlVerifier {
+    pub fn new(krl: Arc<Krl>, verifier: Arc<dyn HostVerifier>) -> Self {
+        Self { krl, verifier }
+    }
+}
+
+impl HostVerifier for KrlVerifier {
+    fn verify(
+        &self,
+        name: &str,
+        port: u16,
+        addr: Option<IpAddr>,
+        identity: &Identity,
+    ) -> BoxFuture<Result<(), HostVerificationError>> {
+        if self.krl.is_revoked(identity) {
+            return Box::pin(async { Err(HostVerificationError::KeyRevoked) });
+        }
+        self.verifier.verify(name, port, addr, identity)
+    }
+
+    /// Revoked keys are not learned.
+    fn update_host_keys(
+        &self,
+        name: &str,
+        port: u16,
+        identities: &[Identity],
+    ) -> BoxFuture<Result<(), HostVerificationError>> {
+        let identities: Vec<Identity> = identities
+            .iter()
+            .filter(|id| !self.krl.is_revoked(id))
+            .cloned()
+            .collect();
+        self.verifier.update_host_keys(name, port, &identities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct AcceptAll;
+
+    impl HostVerifier for AcceptAll {
+        fn verify(
+            &self,
+            _: &str,
+            _: u16,
+            _: Option<IpAddr>,
+            _: &Identity,
+        ) -> BoxFuture<Result<(), HostVerificationError>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    fn public_key(line: &str) -> Identity {
+        Identity::from_authorized_key(line).unwrap().0
+    }
+
+    #[tokio::test]
+    async fn test_verify_01() {
+        let krl = Krl::parse(include_bytes!("../../resources/krl")).unwrap();
+        let verifier = KrlVerifier::new(Arc::new(krl), Arc::new(AcceptAll));
+        let revoked = public_key(include_str!("../../resources/ed25519-host-cert.pub"));
+        let r = verifier.verify("foo.example.com", 22, None, &revoked).await;
+        assert!(matches!(r, Err(HostVerificationError::KeyRevoked)));
+        let valid = public_key(include_str!("../../resources/ed25519-host.pub"));
+        let r = verifier.verify("foo.example.com", 22, None, &valid).await;
+        assert!(r.is_ok());
+    }
+}
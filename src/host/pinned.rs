@@ -0,0 +1,81 @@
+use super::*;
+
+/// Accepts hosts by the fingerprints of their keys (e.g. distributed out of band).
+///
+/// A host with pinned fingerprints is rejected with [HostVerificationError::KeyMismatch] if
+/// its key matches none of them. Hosts without pins are unverifiable (so that another
+/// verifier in a [HostVerifierChain] may decide).
+#[derive(Clone, Debug, Default)]
+pub struct PinnedFingerprints {
+    pins: Vec<(String, u16, Fingerprint)>,
+}
+
+impl PinnedFingerprints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pin a fingerprint for a host (several fingerprints per host are allowed).
+    pub fn pin(mut self, name: &str, port: u16, fingerprint: Fingerprint) -> Self {
+        self.pins.push((name.into(), port, fingerprint));
+        self
+    }
+}
+
+impl HostVerifier for PinnedFingerprints {
+    fn verify(
+        &self,
+        name: &str,
+        port: u16,
+        _addr: Option<IpAddr>,
+        identity: &Identity,
+    ) -> BoxFuture<Result<(), HostVerificationError>> {
+        let mut pins = self.pins.iter().filter(|p| p.0 == name && p.1 == port);
+        let result = match pins.next() {
+            None => Err(HostVerificationError::Unverifiable),
+            Some(pin) => {
+                if std::iter::once(pin)
+                    .chain(pins)
+                    .any(|p| p.2.matches(identity))
+                {
+                    Ok(())
+                } else {
+                    Err(HostVerificationError::KeyMismatch)
+                }
+            }
+        };
+        Box::pin(async move { result })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public_key(line: &str) -> Identity {
+        Identity::from_authorized_key(line).unwrap().0
+    }
+
+    #[tokio::test]
+    async fn test_verify_01() {
+        let host = public_key(include_str!("../../resources/ed25519-host.pub"));
+        let cert = public_key(include_str!("../../resources/ed25519-host-cert.pub"));
+        let other = public_key(include_str!("../../resources/ed25519-user.pub"));
+        let verifier = PinnedFingerprints::new()
+            .pin("example.com", 22, other.fingerprint())
+            .pin("example.com", 22, Fingerprint::md5(&host));
+        assert!(verifier
+            .verify("example.com", 22, None, &host)
+            .await
+            .is_ok());
+        assert!(verifier
+            .verify("example.com", 22, None, &cert)
+            .await
+            .is_ok());
+        let r = verifier.verify("example.com", 2222, None, &host).await;
+        assert!(matches!(r, Err(HostVerificationError::Unverifiable)));
+        let verifier = PinnedFingerprints::new().pin("example.com", 22, other.fingerprint());
+        let r = verifier.verify("example.com", 22, None, &host).await;
+        assert!(matches!(r, Err(HostVerificationError::KeyMismatch)));
+    }
+}
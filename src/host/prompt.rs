@@ -0,0 +1,183 @@
+use super::*;
+use std::sync::Arc;
+
+/// The decision about a host that is not known yet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HostKeyDecision {
+    /// Reject the host
+    Reject,
+    /// Accept the host for this connection only
+    AcceptOnce,
+    /// Accept the host and add its key to `known_hosts`
+    AcceptAndSave,
+}
+
+/// Asks the user whether to trust the key of a host that is not known yet.
+pub trait HostKeyPrompt: std::fmt::Debug + Send + Sync + 'static {
+    fn prompt(&self, name: &str, port: u16, identity: &Identity) -> BoxFuture<HostKeyDecision>;
+}
+
+/// The unit prompt rejects all unknown hosts.
+impl HostKeyPrompt for () {
+    fn prompt(&self, _: &str, _: u16, _: &Identity) -> BoxFuture<HostKeyDecision> {
+        Box::pin(async { HostKeyDecision::Reject })
+    }
+}
+
+/// Trust on first use: Accepts and saves the key of every unknown host without asking (like
+/// `StrictHostKeyChecking accept-new`).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AcceptNew;
+
+impl HostKeyPrompt for AcceptNew {
+    fn prompt(&self, _: &str, _: u16, _: &Identity) -> BoxFuture<HostKeyDecision> {
+        Box::pin(async { HostKeyDecision::AcceptAndSave })
+    }
+}
+
+/// Verifies hosts with [KnownHosts] and asks a [HostKeyPrompt] about unknown hosts.
+///
+/// Hosts with a changed key ([HostVerificationError::KeyMismatch]) or a revoked key are rejected
+/// without asking.
+#[derive(Clone, Debug)]
+pub struct PromptVerifier {
+    known_hosts: KnownHosts,
+    prompt: Arc<dyn HostKeyPrompt>,
+}
+
+impl PromptVerifier {
+    pub fn new(known_hosts: KnownHosts, prompt: Arc<dyn HostKeyPrompt>) -> Self {
+        Self {
+            known_hosts,
+            prompt,
+        }
+    }
+
+    /// Accept and save the keys of all unknown hosts (see [AcceptNew]).
+    pub fn accept_new(known_hosts: KnownHosts) -> Self {
+        Self::new(known_hosts, Arc::new(AcceptNew))
+    }
+}
+
+impl HostVerifier for PromptVerifier {
+    fn verify(
+        &self,
+        name: &str,
+        port: u16,
+        addr: Option<IpAddr>,
+        identity: &Identity,
+    ) -> BoxFuture<Result<(), HostVerificationError>> {
+        let self_ = self.clone();
+        let name: String = name.into();
+        let identity = identity.clone();
+        Box::pin(async move {
+            match self_.known_hosts.verify(&name, port, addr, &identity).await {
+                Err(HostVerificationError::Unverifiable) => (),
+                result => return result,
+            }
+            match self_.prompt.prompt(&name, port, &identity).await {
+                HostKeyDecision::Reject => Err(HostVerificationError::Unverifiable),
+                HostKeyDecision::AcceptOnce => Ok(()),
+                HostKeyDecision::AcceptAndSave => {
+                    log::info!(
+                        "Adding {} key for {} to known hosts",
+                        identity.algorithm(),
+                        name
+                    );
+                    self_.known_hosts.add(&name, port, &identity).await?;
+                    Ok(())
+                }
+            }
+        })
+    }
+
+    fn update_host_keys(
+        &self,
+        name: &str,
+        port: u16,
+        identities: &[Identity],
+    ) -> BoxFuture<Result<(), HostVerificationError>> {
+        self.known_hosts.update_host_keys(name, port, identities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug)]
+    struct Prompt(HostKeyDecision, AtomicUsize);
+
+    impl HostKeyPrompt for Arc<Prompt> {
+        fn prompt(&self, _: &str, _: u16, _: &Identity) -> BoxFuture<HostKeyDecision> {
+            self.1.fetch_add(1, Ordering::SeqCst);
+            let decision = self.0;
+            Box::pin(async move { decision })
+        }
+    }
+
+    fn path(name: &str) -> PathBuf {
+        let name = format!("shellfish-prompt-{}-{}", std::process::id(), name);
+        let path = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn public_key(line: &str) -> Identity {
+        Identity::from_authorized_key(line).unwrap().0
+    }
+
+    #[tokio::test]
+    async fn test_accept_once_01() {
+        let known_hosts = KnownHosts::new(vec![path("once")]);
+        let prompt = Arc::new(Prompt(HostKeyDecision::AcceptOnce, AtomicUsize::new(0)));
+        let verifier = PromptVerifier::new(known_hosts, Arc::new(prompt.clone()));
+        let host = public_key(include_str!("../../resources/ed25519-host.pub"));
+        assert!(verifier
+            .verify("example.com", 22, None, &host)
+            .await
+            .is_ok());
+        assert!(verifier
+            .verify("example.com", 22, None, &host)
+            .await
+            .is_ok());
+        assert_eq!(prompt.1.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_reject_01() {
+        let known_hosts = KnownHosts::new(vec![path("reject")]);
+        let verifier = PromptVerifier::new(known_hosts, Arc::new(()));
+        let host = public_key(include_str!("../../resources/ed25519-host.pub"));
+        let r = verifier.verify("example.com", 22, None, &host).await;
+        assert!(matches!(r, Err(HostVerificationError::Unverifiable)));
+    }
+
+    #[tokio::test]
+    async fn test_accept_new_01() {
+        let path = path("new");
+        let verifier = PromptVerifier::accept_new(KnownHosts::new(vec![path.clone()]));
+        let host = public_key(include_str!("../../resources/ed25519-host.pub"));
+        let other = public_key(include_str!("../../resources/ed25519-user.pub"));
+        assert!(verifier
+            .verify("example.com", 2222, None, &host)
+            .await
+            .is_ok());
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text, format!("[example.com]:2222 {}\n", host));
+        // Known now, so the other key is a mismatch
+        let r = verifier.verify("example.com", 2222, None, &other).await;
+        assert!(matches!(r, Err(HostVerificationError::KeyMismatch)));
+        assert!(verifier
+            .verify("example.com", 2222, None, &host)
+            .await
+            .is_ok());
+        assert!(verifier
+            .verify("example.com", 22, None, &other)
+            .await
+            .is_ok());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,13 +1,21 @@
 mod cert;
+mod fingerprint;
 mod identity;
+mod krl;
+mod private_key;
 mod signature;
+mod sshsig;
 
 pub mod ssh_ed25519;
 pub mod ssh_ed25519_cert;
 pub mod ssh_rsa;
 
 pub use self::cert::*;
+pub use self::fingerprint::*;
 pub use self::identity::*;
+pub use self::krl::*;
+pub use self::private_key::*;
 pub use self::signature::*;
+pub use self::sshsig::*;
 
 pub(crate) const HOST_KEY_ALGORITHMS: [&'static str; 1] = [ssh_ed25519::SshEd25519::NAME];
@@ -15,7 +15,15 @@ use std::net::IpAddr;
 /// (either ssh-cert or X.509 certificates).
 pub trait Cert: Sync + Send + 'static {
     fn authority(&self) -> &Identity;
+    /// The certified public key (without certificate).
+    fn key(&self) -> Identity;
+    fn serial(&self) -> u64;
+    fn key_id(&self) -> &str;
 
     fn verify_for_host(&self, hostname: &str) -> Result<(), CertError>;
     fn verify_for_client(&self, username: &str, source: &IpAddr) -> Result<(), CertError>;
+    /// Verify a user certificate for signing data (like `ssh-keygen -Y verify`).
+    ///
+    /// Unlike for clients, the principal must be listed explicitly and there is no source.
+    fn verify_for_signer(&self, principal: &str) -> Result<(), CertError>;
 }
@@ -18,6 +18,20 @@ pub enum CertExtension {
     Other(String, Vec<u8>),
 }
 
+impl CertExtension {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::NoPresenceRequired => NO_PRESENCE_REQUIRED,
+            Self::PermitX11Forwarding => PERMIT_X11_FORWARDING,
+            Self::PermitAgentForwarding => PERMIT_AGENT_FORWARDING,
+            Self::PermitPortForwarding => PERMIT_PORT_FORWARDING,
+            Self::PermitPty => PERMIT_PTY,
+            Self::PermitUserRc => PERMIT_USER_RC,
+            Self::Other(name, _) => name,
+        }
+    }
+}
+
 impl SshEncode for CertExtension {
     fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
         match self {
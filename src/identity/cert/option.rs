@@ -10,6 +10,16 @@ pub enum CertOption {
     Other(String, Vec<u8>),
 }
 
+impl CertOption {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::ForceCommand(_) => FORCE_COMMAND,
+            Self::SourceAddress(_) => SOURCE_ADDRESS,
+            Self::Other(name, _) => name,
+        }
+    }
+}
+
 impl SshEncode for CertOption {
     fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
         match self {
@@ -0,0 +1,300 @@
+use super::*;
+use crate::util::codec::*;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+/// The hash algorithm of a [Fingerprint].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FingerprintHash {
+    /// The legacy fingerprint (`MD5:d8:0d:0e:...`).
+    Md5,
+    /// The default fingerprint (`SHA256:XPYY7ucQ...`).
+    Sha256,
+}
+
+/// A public key fingerprint as displayed by `ssh-keygen -l`.
+///
+/// Certificates have the fingerprint of their certified key (like in OpenSSH).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fingerprint {
+    hash: FingerprintHash,
+    digest: Vec<u8>,
+}
+
+impl Fingerprint {
+    const MD5: &'static str = "MD5:";
+    const SHA256: &'static str = "SHA256:";
+
+    /// The SHA-256 fingerprint of an identity.
+    pub fn sha256(identity: &Identity) -> Self {
+        Self::new(FingerprintHash::Sha256, identity)
+    }
+
+    /// The legacy MD5 fingerprint of an identity.
+    pub fn md5(identity: &Identity) -> Self {
+        Self::new(FingerprintHash::Md5, identity)
+    }
+
+    pub fn new(hash: FingerprintHash, identity: &Identity) -> Self {
+        let blob = SshCodec::encode(&identity.key()).unwrap_or_default();
+        let blob = blob.get(4..).unwrap_or_default();
+        let digest = match hash {
+            FingerprintHash::Md5 => Md5::digest(blob).to_vec(),
+            FingerprintHash::Sha256 => Sha256::digest(blob).to_vec(),
+        };
+        Self { hash, digest }
+    }
+
+    /// Parse a fingerprint like `SHA256:XPYY7ucQ...` or `MD5:d8:0d:0e:...`.
+    ///
+    /// MD5 fingerprints are also accepted without prefix (like displayed by old OpenSSH).
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some(b64) = text.strip_prefix(Self::SHA256) {
+            let digest = base64::decode_config(b64, base64::STANDARD_NO_PAD).ok()?;
+            crate::util::check(digest.len() == 32)?;
+            return Some(Self {
+                hash: FingerprintHash::Sha256,
+                digest,
+            });
+        }
+        let hex = text.strip_prefix(Self::MD5).unwrap_or(text);
+        let digest = hex
+            .split(':')
+            .map(|x| match x.len() {
+                2 => u8::from_str_radix(x, 16).ok(),
+                _ => None,
+            })
+            .collect::<Option<Vec<u8>>>()?;
+        crate::util::check(digest.len() == 16)?;
+        Some(Self {
+            hash: FingerprintHash::Md5,
+            digest,
+        })
+    }
+
+    pub fn hash(&self) -> FingerprintHash {
+        self.hash
+    }
+
+    pub fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+
+    /// Whether this is the fingerprint of the given identity (e.g. to pin a host key).
+    pub fn matches(&self, identity: &Identity) -> bool {
+        *self == Self::new(self.hash, identity)
+    }
+
+    /// Render the fingerprint as "visual host key" (like `ssh-keygen -lv`).
+    ///
+    /// This is the drunken bishop algorithm of OpenSSH: A bishop starts in the middle of a
+    /// 17x9 field and moves diagonally according to each 2 bits of the digest. The characters
+    /// indicate how often a cell has been visited. `S` and `E` mark start and end.
+    pub fn randomart(&self, identity: &Identity) -> String {
+        const WIDTH: usize = 17;
+        const HEIGHT: usize = 9;
+        const SYMBOLS: &[u8] = b" .o+=*BOX@%&#/^SE";
+        let max = SYMBOLS.len() - 1;
+        let mut field = [[0usize; WIDTH]; HEIGHT];
+        let (mut x, mut y) = (WIDTH / 2, HEIGHT / 2);
+        for byte in &self.digest {
+            let mut input = *byte;
+            for _ in 0..4 {
+                x = if input & 0x1 != 0 {
+                    (x + 1).min(WIDTH - 1)
+                } else {
+                    x.saturating_sub(1)
+                };
+                y = if input & 0x2 != 0 {
+                    (y + 1).min(HEIGHT - 1)
+                } else {
+                    y.saturating_sub(1)
+                };
+                if field[y][x] < max - 2 {
+                    field[y][x] += 1;
+                }
+                input >>= 2;
+            }
+        }
+        field[HEIGHT / 2][WIDTH / 2] = max - 1;
+        field[y][x] = max;
+
+        let (name, bits) = key_type(identity);
+        let mut title = format!("[{} {}]", name, bits.unwrap_or_default());
+        if bits.is_none() || title.len() > WIDTH {
+            title = format!("[{}]", name);
+        }
+        let hash = match self.hash {
+            FingerprintHash::Md5 => "[MD5]",
+            FingerprintHash::Sha256 => "[SHA256]",
+        };
+        let border = |label: &str| {
+            let left = WIDTH.saturating_sub(label.len()) / 2;
+            let right = WIDTH.saturating_sub(left + label.len());
+            format!("+{}{}{}+", "-".repeat(left), label, "-".repeat(right))
+        };
+        let mut art = border(&title);
+        art.push('\n');
+        for row in &field {
+            art.push('|');
+            art.extend(row.iter().map(|n| SYMBOLS[*n.min(&max)] as char));
+            art.push_str("|\n");
+        }
+        art.push_str(&border(hash));
+        art
+    }
+}
+
+impl std::fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.hash {
+            FingerprintHash::Md5 => {
+                write!(f, "{}", Self::MD5)?;
+                for (i, x) in self.digest.iter().enumerate() {
+                    write!(f, "{}{:02x}", if i == 0 { "" } else { ":" }, x)?;
+                }
+                Ok(())
+            }
+            FingerprintHash::Sha256 => {
+                let b64 = base64::encode_config(&self.digest, base64::STANDARD_NO_PAD);
+                write!(f, "{}{}", Self::SHA256, b64)
+            }
+        }
+    }
+}
+
+/// The key type name and size as displayed by OpenSSH (e.g. `ED25519` and `256`).
+fn key_type(identity: &Identity) -> (String, Option<usize>) {
+    let blob = SshCodec::encode(identity).unwrap_or_default();
+    let d = &mut RefDecoder::new(blob.get(4..).unwrap_or_default());
+    match d.take_str_framed().unwrap_or("") {
+        ssh_ed25519::SshEd25519::NAME => ("ED25519".into(), Some(256)),
+        ssh_ed25519_cert::SshEd25519Cert::NAME => ("ED25519-CERT".into(), Some(256)),
+        ssh_rsa::SshRsa::NAME => {
+            let modulus = d.take_bytes_framed().and_then(|_| d.take_bytes_framed());
+            let bits = modulus.map(|n| {
+                let n = &n[n.iter().take_while(|x| **x == 0).count()..];
+                n.first().map(|x| n.len() * 8 - x.leading_zeros() as usize)
+            });
+            ("RSA".into(), bits.flatten())
+        }
+        algorithm => (algorithm.to_uppercase(), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: &str = include_str!("../../resources/ed25519-user.pub");
+    const USER_CERT: &str = include_str!("../../resources/ed25519-user-cert.pub");
+    const RSA: &str = "ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAABgQDB/VweDkYyDpV3OtZatiLvST3re2xUuNGw+xUhpGCTPHkMROjemd2D/MOCcF1ft8K6JcdwRYRvIs6IcuGMLaJiX8xCDYjrPFyly6a7JKYIuSI4UzaWA4gZDpnnC6abNCVcCbDaC3g4dIkcUirgTMt2wVmOvC1ybiuPGn07/I5jEvsCZZ3Fpb3jAJjU3Q98fQn3SVZd0uO1FOfUFffJORO8OjEnrStgY+KbgJ5M+p1WBmK33KYxZGSGCzds/I194FQHnouHAXNoSwwJy8kyVAi9MOEEhku67Cf8faisAyi0HiLV9my+Ao77gmk5SupiWFD/xkcL69wNkf9F8vfueZmS7vBEKtju3U666bSGgKCBrXx9EjHEo2Vlr3KDeQVdRhH9B8COr29i+pNyhtz8vl8lfgc9d9KicHGAW035Dx4D7NUgvIqpeWQq2PqV5pdQNnGQwL6HbjsDn08GDK7Vmj2Zu8BZelTtAaCrQ0ZJcXUKQ9NK4MV8ZPfvzQqTsHj1+S0= root@vm";
+
+    fn public_key(line: &str) -> Identity {
+        Identity::from_authorized_key(line).unwrap().0
+    }
+
+    #[test]
+    fn test_sha256_01() {
+        let fp = Fingerprint::sha256(&public_key(USER));
+        assert_eq!(
+            fp.to_string(),
+            "SHA256:XPYY7ucQ/I9w9bO4tTI6n0/ULg+7EnimYNARGfhJt2M"
+        );
+        let fp = Fingerprint::sha256(&public_key(USER_CERT));
+        assert_eq!(
+            fp.to_string(),
+            "SHA256:XPYY7ucQ/I9w9bO4tTI6n0/ULg+7EnimYNARGfhJt2M"
+        );
+    }
+
+    #[test]
+    fn test_sha256_02() {
+        let cert = public_key(USER_CERT);
+        let ca = cert.as_cert().unwrap().authority().clone();
+        let fp = Fingerprint::sha256(&ca);
+        assert_eq!(
+            fp.to_string(),
+            "SHA256:cX60gqTIkBpilJxoQOXI30G4AORz7PCN0j6yg1uAQvc"
+        );
+        let fp = Fingerprint::sha256(&public_key(RSA));
+        assert_eq!(
+            fp.to_string(),
+            "SHA256:zWSFQX/2/kGqj/AEVD9RAhL6nIl0NrfSHUMXEN9XxQk"
+        );
+    }
+
+    #[test]
+    fn test_md5_01() {
+        let fp = Fingerprint::md5(&public_key(USER));
+        assert_eq!(
+            fp.to_string(),
+            "MD5:d8:0d:0e:40:05:e5:4c:f7:5a:9c:de:6c:65:9e:66:b6"
+        );
+    }
+
+    #[test]
+    fn test_parse_01() {
+        let user = public_key(USER);
+        let fp = Fingerprint::parse("SHA256:XPYY7ucQ/I9w9bO4tTI6n0/ULg+7EnimYNARGfhJt2M").unwrap();
+        assert_eq!(fp.hash(), FingerprintHash::Sha256);
+        assert!(fp.matches(&user));
+        assert!(fp.matches(&public_key(USER_CERT)));
+        assert!(!fp.matches(&public_key(RSA)));
+        let fp = Fingerprint::parse("MD5:d8:0d:0e:40:05:e5:4c:f7:5a:9c:de:6c:65:9e:66:b6").unwrap();
+        assert!(fp.matches(&user));
+        let fp = Fingerprint::parse("d8:0d:0e:40:05:e5:4c:f7:5a:9c:de:6c:65:9e:66:b6").unwrap();
+        assert_eq!(fp.hash(), FingerprintHash::Md5);
+        assert!(fp.matches(&user));
+    }
+
+    #[test]
+    fn test_parse_02() {
+        assert_eq!(Fingerprint::parse("SHA256:XPYY7ucQ"), None);
+        assert_eq!(Fingerprint::parse("SHA256:!!!"), None);
+        assert_eq!(Fingerprint::parse("MD5:d8:0d"), None);
+        assert_eq!(
+            Fingerprint::parse("d8:0d:0e:40:05:e5:4c:f7:5a:9c:de:6c:65:9e:66:b"),
+            None
+        );
+        assert_eq!(Fingerprint::parse(""), None);
+    }
+
+    #[test]
+    fn test_randomart_01() {
+        let user = public_key(USER);
+        let art = "+--[ED25519 256]--+
+|       .o+       |
+|      . + .      |
+|       + ++.     |
+|      ..+=E+    .|
+|       .S.=o. ...|
+|        o..o+..o |
+|       . .+++.o+o|
+|          .B.===+|
+|           .**O=.|
++----[SHA256]-----+";
+        assert_eq!(Fingerprint::sha256(&user).randomart(&user), art);
+        let cert = public_key(USER_CERT);
+        let art = art.replace("+--[ED25519 256]--+", "+-[ED25519-CERT]--+");
+        assert_eq!(Fingerprint::sha256(&cert).randomart(&cert), art);
+    }
+
+    #[test]
+    fn test_randomart_02() {
+        let rsa = public_key(RSA);
+        let art = "+---[RSA 3072]----+
+|         ++=oE==*|
+|        . +oo =.+|
+|       o =o..=o.o|
+|      . B=* oo+..|
+|       .SBoo . ..|
+|          o   o. |
+|         . . . ..|
+|          + o   o|
+|           +..  .|
++----[SHA256]-----+";
+        assert_eq!(Fingerprint::sha256(&rsa).randomart(&rsa), art);
+    }
+}
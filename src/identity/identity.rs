@@ -1,7 +1,10 @@
 use super::cert::*;
+use super::fingerprint::*;
 use super::ssh_ed25519::*;
 use super::ssh_ed25519_cert::*;
+use super::ssh_rsa::*;
 use crate::util::codec::*;
+use std::str::FromStr;
 
 /// A user or host identity.
 ///
@@ -12,26 +15,119 @@ pub struct Identity(Vec<u8>);
 /// A user or host public key.
 pub type PublicKey = Identity;
 
+/// The decoded form of an [Identity] (see [Identity::kind]).
+#[derive(Clone, Debug, PartialEq)]
+pub enum IdentityKind<'a> {
+    SshEd25519(SshEd25519PublicKey<'a>),
+    SshEd25519Cert(Box<SshEd25519Cert>),
+    SshRsa(RsaPublicKey),
+    /// An unsupported algorithm or malformed key.
+    Other(&'a str),
+}
+
 impl Identity {
     pub fn algorithm(&self) -> &str {
         RefDecoder::new(&self.0).take_str_framed().unwrap_or("")
     }
 
+    /// Decode the identity according to its algorithm.
+    pub fn kind(&self) -> IdentityKind<'_> {
+        let algorithm = self.algorithm();
+        let kind = match algorithm {
+            SshEd25519::NAME => SshCodec::decode(&self.0).ok().map(IdentityKind::SshEd25519),
+            SshEd25519Cert::NAME => SshCodec::decode(&self.0)
+                .ok()
+                .map(|x| IdentityKind::SshEd25519Cert(Box::new(x))),
+            SshRsa::NAME => SshCodec::decode(&self.0).ok().map(IdentityKind::SshRsa),
+            _ => None,
+        };
+        kind.unwrap_or(IdentityKind::Other(algorithm))
+    }
+
     pub fn as_ssh_ed25519(&self) -> Option<SshEd25519PublicKey> {
-        SshCodec::decode(&self.0).ok()
+        match self.kind() {
+            IdentityKind::SshEd25519(x) => Some(x),
+            _ => None,
+        }
     }
 
     pub fn as_ssh_ed25519_cert(&self) -> Option<SshEd25519Cert> {
-        SshCodec::decode(&self.0).ok()
+        match self.kind() {
+            IdentityKind::SshEd25519Cert(x) => Some(*x),
+            _ => None,
+        }
     }
 
     pub fn as_cert(&self) -> Option<Box<dyn Cert>> {
-        if let Some(cert) = self.as_ssh_ed25519_cert() {
-            Some(Box::new(cert))
-        } else {
-            None
+        match self.kind() {
+            IdentityKind::SshEd25519Cert(x) => Some(x),
+            _ => None,
         }
     }
+
+    /// The public key itself (the certified key in case of a certificate).
+    pub fn key(&self) -> Identity {
+        match self.as_cert() {
+            Some(cert) => cert.key(),
+            None => self.clone(),
+        }
+    }
+
+    /// Whether both identities have the same key (ignoring certificates).
+    pub fn is_same_key(&self, other: &Identity) -> bool {
+        self.key() == other.key()
+    }
+
+    /// The SHA-256 fingerprint (e.g. `SHA256:XPYY7ucQ...`, see [Fingerprint] for MD5).
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::sha256(self)
+    }
+
+    /// Format as `authorized_keys` line (like the `.pub` files written by `ssh-keygen`).
+    ///
+    /// The comment is omitted if empty.
+    pub fn to_authorized_key(&self, comment: &str) -> String {
+        let mut line = self.to_string();
+        if !comment.is_empty() {
+            line.push(' ');
+            line.push_str(comment);
+        }
+        line
+    }
+
+    /// Parse an `authorized_keys` line without options (returns the identity and comment).
+    ///
+    /// The fields may be separated by any whitespace. The algorithm name must match the
+    /// encoded key.
+    pub fn from_authorized_key(line: &str) -> Option<(Self, String)> {
+        let (algorithm, rest) = line.trim().split_once(char::is_whitespace)?;
+        let rest = rest.trim_start();
+        let (key, comment) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let identity = Self(base64::decode(key).ok()?);
+        let comment = comment.trim();
+        if identity.algorithm() != algorithm {
+            return None;
+        }
+        Some((identity, comment.into()))
+    }
+}
+
+/// Parse a public key line like `ssh-ed25519 AAAAC3Nza... comment` (the comment is ignored).
+impl FromStr for Identity {
+    type Err = IdentityParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_authorized_key(s)
+            .map(|x| x.0)
+            .ok_or(IdentityParseError)
+    }
+}
+
+/// Format as public key line without comment (like `ssh-ed25519 AAAAC3Nza...`).
+impl std::fmt::Display for Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.algorithm(), base64::encode(&self.0))
+    }
 }
 
 impl From<Vec<u8>> for Identity {
@@ -40,6 +136,12 @@ impl From<Vec<u8>> for Identity {
     }
 }
 
+impl<'a> From<SshEd25519PublicKey<'a>> for Identity {
+    fn from(x: SshEd25519PublicKey<'a>) -> Self {
+        Self(SshCodec::encode(&x).expect("encodable"))
+    }
+}
+
 impl SshEncode for Identity {
     fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
         e.push_bytes_framed(&self.0)
@@ -53,3 +155,107 @@ impl SshDecode for Identity {
         Some(Self(Vec::from(bytes)))
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdentityParseError;
+
+impl std::fmt::Display for IdentityParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid public key line")
+    }
+}
+
+impl std::error::Error for IdentityParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAPgR7I6taTcJ/bSgCDOQKe4vhxrjwWJeI3XLZ5IWjw/ test@plain";
+
+    #[test]
+    fn test_authorized_key_01() {
+        let (id, comment) = Identity::from_authorized_key(LINE).unwrap();
+        assert_eq!(comment, "test@plain");
+        assert!(id.as_ssh_ed25519().is_some());
+        assert_eq!(id.to_authorized_key(&comment), LINE);
+        let pk = id.as_ssh_ed25519().unwrap();
+        assert_eq!(Identity::from(pk), id);
+    }
+
+    #[test]
+    fn test_authorized_key_02() {
+        let line = LINE.replace(" test@plain", "\n");
+        let (id, comment) = Identity::from_authorized_key(&line).unwrap();
+        assert_eq!(comment, "");
+        assert_eq!(id.to_authorized_key(""), LINE.replace(" test@plain", ""));
+    }
+
+    #[test]
+    fn test_authorized_key_04() {
+        let line = LINE
+            .replace("ssh-ed25519 ", "ssh-ed25519  ")
+            .replace(" test@plain", "\t test@plain");
+        let (id, comment) = Identity::from_authorized_key(&line).unwrap();
+        assert_eq!(comment, "test@plain");
+        assert_eq!(id.to_authorized_key(&comment), LINE);
+        let line = LINE.replace(' ', "\t\t");
+        let (id, comment) = Identity::from_authorized_key(&line).unwrap();
+        assert_eq!(comment, "test@plain");
+        assert_eq!(id.to_authorized_key(&comment), LINE);
+    }
+
+    #[test]
+    fn test_from_str_01() {
+        let id: Identity = LINE.parse().unwrap();
+        assert_eq!(id.to_string(), LINE.replace(" test@plain", ""));
+        assert!(matches!(id.kind(), IdentityKind::SshEd25519(_)));
+        assert_eq!("ssh-rsa AAAA".parse::<Identity>(), Err(IdentityParseError));
+    }
+
+    #[test]
+    fn test_kind_01() {
+        let cert: Identity = include_str!("../../resources/ed25519-user-cert.pub")
+            .parse()
+            .unwrap();
+        let key: Identity = include_str!("../../resources/ed25519-user.pub")
+            .parse()
+            .unwrap();
+        match cert.kind() {
+            IdentityKind::SshEd25519Cert(x) => assert_eq!(x.key(), key),
+            kind => panic!("{:?}", kind),
+        }
+        assert_eq!(cert.key(), key);
+        assert_eq!(key.key(), key);
+        assert!(cert.is_same_key(&key));
+        assert!(key.is_same_key(&cert));
+        assert!(!key.is_same_key(&LINE.parse().unwrap()));
+        assert_ne!(cert, key);
+    }
+
+    #[test]
+    fn test_kind_02() {
+        let id = Identity::from(
+            SshCodec::encode(&RsaPublicKey {
+                public_e: vec![1, 0, 1],
+                public_n: vec![0xff; 8],
+            })
+            .unwrap(),
+        );
+        assert!(matches!(id.kind(), IdentityKind::SshRsa(_)));
+        let id = Identity::from(vec![0, 0, 0, 3, b'f', b'o', b'o']);
+        assert_eq!(id.kind(), IdentityKind::Other("foo"));
+        let id = Identity::from(vec![
+            0, 0, 0, 11, b's', b's', b'h', b'-', b'e', b'd', b'2', b'5', b'5', b'1', b'9',
+        ]);
+        assert_eq!(id.kind(), IdentityKind::Other("ssh-ed25519"));
+    }
+
+    #[test]
+    fn test_authorized_key_03() {
+        let line = LINE.replace("ssh-ed25519 ", "ssh-rsa ");
+        assert_eq!(Identity::from_authorized_key(&line), None);
+        assert_eq!(Identity::from_authorized_key("ssh-ed25519 !!!"), None);
+        assert_eq!(Identity::from_authorized_key(""), None);
+    }
+}
@@ -0,0 +1,304 @@
+//! OpenSSH's binary Key Revocation List format (see `PROTOCOL.krl` in the OpenSSH sources).
+
+use super::*;
+use crate::util::codec::*;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+const MAGIC: u64 = 0x5353484b524c0a00;
+const FORMAT_VERSION: u32 = 1;
+
+const SECTION_CERTIFICATES: u8 = 1;
+const SECTION_EXPLICIT_KEY: u8 = 2;
+const SECTION_FINGERPRINT_SHA1: u8 = 3;
+const SECTION_SIGNATURE: u8 = 4;
+const SECTION_FINGERPRINT_SHA256: u8 = 5;
+
+const SECTION_CERT_SERIAL_LIST: u8 = 0x20;
+const SECTION_CERT_SERIAL_RANGE: u8 = 0x21;
+const SECTION_CERT_SERIAL_BITMAP: u8 = 0x22;
+const SECTION_CERT_KEY_ID: u8 = 0x23;
+
+/// A Key Revocation List as generated by `ssh-keygen -k`.
+///
+/// Keys are revoked explicitly or by their SHA-1/SHA-256 hash. Certificates are revoked by
+/// serial number or key id (per authority), their certified key or their authority's key.
+/// Signatures of the KRL itself are ignored (like OpenSSH does).
+#[derive(Clone, Debug, Default)]
+pub struct Krl {
+    version: u64,
+    comment: String,
+    certs: Vec<KrlCerts>,
+    keys: Vec<Identity>,
+    sha1: Vec<Vec<u8>>,
+    sha256: Vec<Vec<u8>>,
+}
+
+/// The revoked certificates of one authority (or of any authority if `ca` is `None`).
+#[derive(Clone, Debug, Default)]
+struct KrlCerts {
+    ca: Option<Identity>,
+    serials: Vec<(u64, u64)>,
+    key_ids: Vec<String>,
+}
+
+impl Krl {
+    /// Parse a KRL in binary format.
+    pub fn parse(data: &[u8]) -> Result<Self, KrlError> {
+        Self::decode(&mut RefDecoder::new(data)).ok_or(KrlError::InvalidFormat)?
+    }
+
+    /// Load and parse a KRL file (like `RevokedKeys` and `RevokedHostKeys` in OpenSSH).
+    pub async fn load<P: AsRef<Path>>(path: P) -> Result<Self, KrlError> {
+        Self::parse(&tokio::fs::read(path).await?)
+    }
+
+    /// The KRL version number (set with `ssh-keygen -z`).
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// Whether the key or certificate has been revoked.
+    pub fn is_revoked(&self, identity: &Identity) -> bool {
+        match identity.as_cert() {
+            Some(cert) => {
+                self.is_key_revoked(&cert.key())
+                    || self.is_key_revoked(cert.authority())
+                    || self.is_cert_revoked(cert.as_ref())
+            }
+            None => self.is_key_revoked(identity),
+        }
+    }
+
+    fn is_key_revoked(&self, key: &Identity) -> bool {
+        let blob = SshCodec::encode(key).unwrap_or_default();
+        let blob = blob.get(4..).unwrap_or_default();
+        self.keys.contains(key)
+            || (!self.sha1.is_empty() && self.sha1.contains(&Sha1::digest(blob).to_vec()))
+            || (!self.sha256.is_empty() && self.sha256.contains(&Sha256::digest(blob).to_vec()))
+    }
+
+    fn is_cert_revoked(&self, cert: &dyn Cert) -> bool {
+        let serial = cert.serial();
+        self.certs
+            .iter()
+            .filter(|x| {
+                x.ca.as_ref()
+                    .map(|ca| ca == cert.authority())
+                    .unwrap_or(true)
+            })
+            .any(|x| {
+                x.key_ids.iter().any(|id| id == cert.key_id())
+                    // Serial 0 is the default and never revoked by serial
+                    || (serial != 0 && x.serials.iter().any(|(a, b)| *a <= serial && serial <= *b))
+            })
+    }
+
+    fn decode(d: &mut RefDecoder) -> Option<Result<Self, KrlError>> {
+        let mut krl = Self::default();
+        if d.take_u64be()? != MAGIC || d.take_u32be()? != FORMAT_VERSION {
+            return None;
+        }
+        krl.version = d.take_u64be()?;
+        let _generated_date = d.take_u64be()?;
+        let _flags = d.take_u64be()?;
+        let _reserved = d.take_bytes_framed()?;
+        krl.comment = d.take_str_framed()?.into();
+        while d.expect_eoi().is_none() {
+            let section = d.take_u8()?;
+            let s = &mut RefDecoder::new(d.take_bytes_framed()?);
+            match section {
+                SECTION_CERTIFICATES => match KrlCerts::decode(s)? {
+                    Ok(certs) => krl.certs.push(certs),
+                    Err(e) => return Some(Err(e)),
+                },
+                SECTION_EXPLICIT_KEY => {
+                    while s.expect_eoi().is_none() {
+                        krl.keys.push(s.take()?)
+                    }
+                }
+                SECTION_FINGERPRINT_SHA1 => {
+                    while s.expect_eoi().is_none() {
+                        krl.sha1.push(s.take_bytes_framed()?.into())
+                    }
+                }
+                SECTION_FINGERPRINT_SHA256 => {
+                    while s.expect_eoi().is_none() {
+                        krl.sha256.push(s.take_bytes_framed()?.into())
+                    }
+                }
+                SECTION_SIGNATURE => (),
+                _ => return Some(Err(KrlError::UnsupportedSection(section))),
+            }
+        }
+        Some(Ok(krl))
+    }
+}
+
+impl KrlCerts {
+    fn decode(d: &mut RefDecoder) -> Option<Result<Self, KrlError>> {
+        let mut certs = Self::default();
+        let ca = d.take_bytes_framed()?;
+        if !ca.is_empty() {
+            certs.ca = Some(Identity::from(ca.to_vec()));
+        }
+        let _reserved = d.take_bytes_framed()?;
+        while d.expect_eoi().is_none() {
+            let section = d.take_u8()?;
+            let s = &mut RefDecoder::new(d.take_bytes_framed()?);
+            match section {
+                SECTION_CERT_SERIAL_LIST => {
+                    while s.expect_eoi().is_none() {
+                        let serial = s.take_u64be()?;
+                        certs.serials.push((serial, serial))
+                    }
+                }
+                SECTION_CERT_SERIAL_RANGE => {
+                    let min = s.take_u64be()?;
+                    let max = s.take_u64be()?;
+                    s.expect_eoi()?;
+                    certs.serials.push((min, max))
+                }
+                SECTION_CERT_SERIAL_BITMAP => {
+                    let offset = s.take_u64be()?;
+                    let bitmap = s.take_bytes_framed()?;
+                    s.expect_eoi()?;
+                    // The bitmap is an mpint: bit 0 of the last byte is `offset`
+                    for (i, byte) in bitmap.iter().rev().enumerate() {
+                        for bit in 0..8 {
+                            if byte & (1 << bit) != 0 {
+                                let serial = offset.checked_add(i as u64 * 8 + bit)?;
+                                certs.serials.push((serial, serial))
+                            }
+                        }
+                    }
+                }
+                SECTION_CERT_KEY_ID => {
+                    while s.expect_eoi().is_none() {
+                        certs.key_ids.push(s.take_str_framed()?.into())
+                    }
+                }
+                _ => return Some(Err(KrlError::UnsupportedSection(section))),
+            }
+        }
+        Some(Ok(certs))
+    }
+}
+
+#[derive(Debug)]
+pub enum KrlError {
+    IoError(std::io::Error),
+    InvalidFormat,
+    UnsupportedSection(u8),
+}
+
+impl From<std::io::Error> for KrlError {
+    fn from(e: std::io::Error) -> Self {
+        Self::IoError(e)
+    }
+}
+
+impl std::error::Error for KrlError {}
+
+impl std::fmt::Display for KrlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "IO error: {}", e),
+            Self::InvalidFormat => write!(f, "Invalid KRL format"),
+            Self::UnsupportedSection(x) => write!(f, "Unsupported KRL section: {}", x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::ssh_ed25519::*;
+    use crate::identity::ssh_ed25519_cert::*;
+
+    const KRL: &[u8] = include_bytes!("../../resources/krl");
+
+    fn public_key(line: &str) -> Identity {
+        Identity::from_authorized_key(line).unwrap().0
+    }
+
+    fn ca() -> PrivateKey {
+        let text = include_str!("../../resources/ed25519-ca");
+        PrivateKey::from_openssh(text, None).unwrap().remove(0).0
+    }
+
+    fn cert(ca: &PrivateKey, serial: u64, key_id: &str) -> Identity {
+        let key = SshEd25519PrivateKey::new([1; 32]);
+        let cert = SshEd25519Cert::builder(&key.public(), CertType::USER)
+            .serial(serial)
+            .key_id(key_id)
+            .sign_with(ca);
+        Identity::from(&cert)
+    }
+
+    #[test]
+    fn test_parse_01() {
+        let krl = Krl::parse(KRL).unwrap();
+        assert_eq!(krl.version(), 1);
+        assert_eq!(krl.comment(), "");
+        assert_eq!(krl.certs.len(), 1);
+        assert_eq!(krl.keys.len(), 1);
+        assert_eq!(krl.sha256.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_02() {
+        let e = Krl::parse(&KRL[..KRL.len() - 1]).unwrap_err();
+        assert!(matches!(e, KrlError::InvalidFormat));
+        let mut krl = KRL.to_vec();
+        krl[44] = 9;
+        let e = Krl::parse(&krl).unwrap_err();
+        assert!(matches!(e, KrlError::UnsupportedSection(9)));
+    }
+
+    #[test]
+    fn test_revoked_keys_01() {
+        let krl = Krl::parse(KRL).unwrap();
+        let revoked1 = include_str!("../../resources/krl-revoked1.pub");
+        let revoked2 = include_str!("../../resources/krl-revoked2.pub");
+        let other = include_str!("../../resources/ed25519-user.pub");
+        assert!(krl.is_revoked(&public_key(revoked1)));
+        assert!(krl.is_revoked(&public_key(revoked2)));
+        assert!(!krl.is_revoked(&public_key(other)));
+    }
+
+    #[test]
+    fn test_revoked_certs_01() {
+        let krl = Krl::parse(KRL).unwrap();
+        let host = include_str!("../../resources/ed25519-host-cert.pub");
+        let user = include_str!("../../resources/ed25519-user-cert.pub");
+        assert!(krl.is_revoked(&public_key(host)));
+        assert!(!krl.is_revoked(&public_key(user)));
+    }
+
+    #[test]
+    fn test_revoked_certs_02() {
+        let krl = Krl::parse(KRL).unwrap();
+        let ca = ca();
+        for serial in [10, 15, 20, 100, 102, 107, 1000, 100000] {
+            assert!(krl.is_revoked(&cert(&ca, serial, "foo")), "{}", serial);
+        }
+        for serial in [0, 9, 21, 101, 103, 106, 108, 999, 100001] {
+            assert!(!krl.is_revoked(&cert(&ca, serial, "foo")), "{}", serial);
+        }
+        assert!(krl.is_revoked(&cert(&ca, 0, "cert2")));
+    }
+
+    #[test]
+    fn test_revoked_certs_03() {
+        // Serials and key ids only apply to certificates of the KRL's authority
+        let krl = Krl::parse(KRL).unwrap();
+        let ca = PrivateKey::from(SshEd25519PrivateKey::new([2; 32]));
+        assert!(!krl.is_revoked(&cert(&ca, 15, "cert2")));
+    }
+}
@@ -0,0 +1,24 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrivateKeyError {
+    InvalidFormat,
+    UnsupportedAlgorithm(String),
+    UnsupportedCipher(String),
+    UnsupportedKdf(String),
+    PassphraseRequired,
+    InvalidPassphrase,
+}
+
+impl std::fmt::Display for PrivateKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidFormat => write!(f, "Invalid private key format"),
+            Self::UnsupportedAlgorithm(x) => write!(f, "Unsupported key algorithm: {}", x),
+            Self::UnsupportedCipher(x) => write!(f, "Unsupported cipher: {}", x),
+            Self::UnsupportedKdf(x) => write!(f, "Unsupported key derivation function: {}", x),
+            Self::PassphraseRequired => write!(f, "Passphrase required"),
+            Self::InvalidPassphrase => write!(f, "Invalid passphrase"),
+        }
+    }
+}
+
+impl std::error::Error for PrivateKeyError {}
@@ -0,0 +1,65 @@
+//! The textual encoding of binary data between `-----BEGIN <label>-----` and
+//! `-----END <label>-----` lines (RFC 7468).
+
+/// Decode the first PEM block in `text` (returns its label and contents).
+pub fn decode(text: &str) -> Option<(&str, Vec<u8>)> {
+    let mut lines = text.lines().map(str::trim).skip_while(|l| l.is_empty());
+    let label = lines
+        .next()?
+        .strip_prefix("-----BEGIN ")?
+        .strip_suffix("-----")?;
+    let mut b64 = String::new();
+    for line in lines {
+        if let Some(end) = line.strip_prefix("-----END ") {
+            if end.strip_suffix("-----")? != label {
+                return None;
+            }
+            return Some((label, base64::decode(&b64).ok()?));
+        }
+        b64.push_str(line);
+    }
+    None
+}
+
+/// Encode `data` as PEM block with lines of `width` characters.
+pub fn encode(label: &str, data: &[u8], width: usize) -> String {
+    let b64 = base64::encode(data);
+    let mut text = format!("-----BEGIN {}-----\n", label);
+    for line in b64.as_bytes().chunks(width) {
+        text.push_str(std::str::from_utf8(line).unwrap_or_default());
+        text.push('\n');
+    }
+    text.push_str(&format!("-----END {}-----\n", label));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_01() {
+        let text = "\n-----BEGIN FOO-----\nAQID\nBA==\n-----END FOO-----\n";
+        assert_eq!(decode(text), Some(("FOO", vec![1, 2, 3, 4])));
+    }
+
+    #[test]
+    fn test_decode_02() {
+        assert_eq!(
+            decode("-----BEGIN FOO-----\nAQID\n-----END BAR-----\n"),
+            None
+        );
+        assert_eq!(decode("-----BEGIN FOO-----\nAQID\n"), None);
+        assert_eq!(decode("AQID\n"), None);
+    }
+
+    #[test]
+    fn test_encode_01() {
+        let text = encode("FOO", &[1, 2, 3, 4], 3);
+        assert_eq!(
+            text,
+            "-----BEGIN FOO-----\nAQI\nDBA\n==\n-----END FOO-----\n"
+        );
+        assert_eq!(decode(&text), Some(("FOO", vec![1, 2, 3, 4])));
+    }
+}
@@ -1,7 +1,7 @@
 use super::ssh_ed25519::*;
 use super::*;
-use std::convert::TryFrom;
 use crate::util::codec::*;
+use std::convert::TryFrom;
 
 /// A public key signature.
 #[derive(Clone, Debug, PartialEq)]
@@ -36,12 +36,10 @@ impl Signature {
             SshEd25519::NAME => {
                 use ed25519_dalek::PublicKey as PK;
                 use ed25519_dalek::Signature as SG;
-                let key = if let Some(id) = id.as_ssh_ed25519() {
-                    PK::from_bytes(id.pk().as_ref()).map_err(|_| e)?
-                } else if let Some(id) = id.as_ssh_ed25519_cert() {
-                    PK::from_bytes(id.pk().as_ref()).map_err(|_| e)?
-                } else {
-                    return Err(SignatureError::UnsupportedIdentity);
+                let key = match id.kind() {
+                    IdentityKind::SshEd25519(x) => PK::from_bytes(x.pk()).map_err(|_| e)?,
+                    IdentityKind::SshEd25519Cert(x) => PK::from_bytes(x.pk()).map_err(|_| e)?,
+                    _ => return Err(SignatureError::UnsupportedIdentity),
                 };
                 let sig = SG::try_from(self.data.as_ref()).map_err(|_| e)?;
                 key.verify_strict(data, &sig).map_err(|_| e)
@@ -1,5 +1,8 @@
+use super::{Identity, Signature};
 use crate::util::codec::*;
+use rand_core::{OsRng, RngCore};
 use std::convert::TryInto;
+use zeroize::Zeroize;
 
 #[derive(Debug)]
 pub struct SshEd25519;
@@ -31,5 +34,90 @@ impl<'a> SshDecodeRef<'a> for SshEd25519PublicKey<'a> {
     }
 }
 
+/// An ed25519 private key (seed) together with its public key.
+#[derive(Clone)]
+pub struct SshEd25519PrivateKey {
+    secret: [u8; 32],
+    public: [u8; 32],
+}
+
+impl SshEd25519PrivateKey {
+    /// Create a private key from the 32 byte seed (deriving the public key).
+    pub fn new(secret: [u8; 32]) -> Self {
+        let sk = ed25519_dalek::SecretKey::from_bytes(&secret).expect("32 bytes");
+        let public = ed25519_dalek::PublicKey::from(&sk).to_bytes();
+        Self { secret, public }
+    }
+
+    /// Generate a new random private key.
+    pub fn generate() -> Self {
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        let key = Self::new(secret);
+        secret.zeroize();
+        key
+    }
+
+    pub fn secret(&self) -> &[u8; 32] {
+        &self.secret
+    }
+
+    pub fn public(&self) -> SshEd25519PublicKey<'_> {
+        SshEd25519PublicKey(&self.public)
+    }
+
+    pub fn identity(&self) -> Identity {
+        Identity::from(self.public())
+    }
+
+    pub fn sign(&self, data: &[u8]) -> Signature {
+        use ed25519_dalek::{ExpandedSecretKey, PublicKey, SecretKey};
+        let sk = SecretKey::from_bytes(&self.secret).expect("32 bytes");
+        let pk = PublicKey::from(&sk);
+        let sig = ExpandedSecretKey::from(&sk).sign(data, &pk);
+        Signature::new(SshEd25519::NAME.into(), sig.to_bytes().to_vec())
+    }
+}
+
+impl std::fmt::Debug for SshEd25519PrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SshEd25519PrivateKey(***)")
+    }
+}
+
+impl Drop for SshEd25519PrivateKey {
+    fn drop(&mut self) {
+        self.secret.zeroize()
+    }
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_private_key_sign_01() {
+        let key = SshEd25519PrivateKey::new([7; 32]);
+        let sig = key.sign(b"data");
+        assert_eq!(sig.algo(), SshEd25519::NAME);
+        assert!(sig.verify(&key.identity(), b"data").is_ok());
+        assert!(sig.verify(&key.identity(), b"other").is_err());
+    }
+
+    #[test]
+    fn test_private_key_generate_01() {
+        let key1 = SshEd25519PrivateKey::generate();
+        let key2 = SshEd25519PrivateKey::generate();
+        assert_ne!(key1.secret(), key2.secret());
+        assert_eq!(
+            key1.public(),
+            SshEd25519PrivateKey::new(*key1.secret()).public()
+        );
+    }
+
+    #[test]
+    fn test_private_key_debug_01() {
+        let key = SshEd25519PrivateKey::new([7; 32]);
+        assert_eq!(format!("{:?}", key), "SshEd25519PrivateKey(***)");
+    }
+}
@@ -1,3 +1,8 @@
+mod builder;
+
+pub use self::builder::*;
+
+use super::ssh_ed25519::*;
 use super::*;
 use crate::util::check;
 use crate::util::cidr::Cidr;
@@ -27,6 +32,11 @@ pub struct SshEd25519Cert {
 impl SshEd25519Cert {
     pub const NAME: &'static str = "ssh-ed25519-cert-v01@openssh.com";
 
+    /// Start building a certificate for the given public key (see [SshEd25519CertBuilder]).
+    pub fn builder(pk: &SshEd25519PublicKey, type_: CertType) -> SshEd25519CertBuilder {
+        SshEd25519CertBuilder::new(pk, type_)
+    }
+
     pub fn pk(&self) -> &[u8; 32] {
         &self.pk
     }
@@ -78,12 +88,8 @@ impl SshEd25519Cert {
     }
 
     pub fn is_valid_ca_signature(&self) -> bool {
-        if let Ok(size) = SshCodec::size(&self.signature) {
-            if let Ok(data) = SshCodec::encode(self) {
-                if let Some(data) = data.get(..data.len() - size) {
-                    return self.signature.verify(&self.authority, data).is_ok();
-                }
-            }
+        if let Ok(data) = SshCodec::encode(&Unsigned(self)) {
+            return self.signature.verify(&self.authority, &data).is_ok();
         }
         false
     }
@@ -93,6 +99,15 @@ impl Cert for SshEd25519Cert {
     fn authority(&self) -> &Identity {
         &self.authority
     }
+    fn key(&self) -> Identity {
+        Identity::from(SshEd25519PublicKey(&self.pk))
+    }
+    fn serial(&self) -> u64 {
+        self.serial
+    }
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
     fn verify_for_host(&self, hostname: &str) -> Result<(), CertError> {
         check(self.type_ == CertType::HOST).ok_or(CertError::InvalidType)?;
         check(self.is_valid_principal(hostname)).ok_or(CertError::InvalidPrincipal)?;
@@ -110,27 +125,52 @@ impl Cert for SshEd25519Cert {
         check(self.is_valid_ca_signature()).ok_or(CertError::InvalidSignature)?;
         Ok(())
     }
+    fn verify_for_signer(&self, principal: &str) -> Result<(), CertError> {
+        let explicit = self.valid_principals.iter().any(|x| x == principal);
+        check(self.type_ == CertType::USER).ok_or(CertError::InvalidType)?;
+        check(explicit).ok_or(CertError::InvalidPrincipal)?;
+        check(self.is_valid_period()).ok_or(CertError::InvalidPeriod)?;
+        check(self.is_valid_options()).ok_or(CertError::InvalidOptions)?;
+        check(self.is_valid_ca_signature()).ok_or(CertError::InvalidSignature)?;
+        Ok(())
+    }
+}
+
+impl From<&SshEd25519Cert> for Identity {
+    fn from(x: &SshEd25519Cert) -> Self {
+        Identity::from(SshCodec::encode(x).expect("encodable"))
+    }
 }
 
 impl SshEncode for SshEd25519Cert {
     fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
-        e.push_str_framed(SshEd25519Cert::NAME)?;
-        e.push_bytes_framed(&self.nonce)?;
-        e.push_bytes_framed(&self.pk)?;
-        e.push_u64be(self.serial)?;
-        e.push_u32be(self.type_.0)?;
-        e.push_str_framed(&self.key_id)?;
-        e.push_list(&self.valid_principals)?;
-        e.push_u64be(self.valid_after)?;
-        e.push_u64be(self.valid_before)?;
-        e.push_list(&self.critical_options)?;
-        e.push_list(&self.extensions)?;
-        e.push_bytes_framed(&self.reserved)?;
-        e.push(&self.authority)?;
+        e.push(&Unsigned(self))?;
         e.push(&self.signature)
     }
 }
 
+/// All certificate fields except the signature (the data signed by the authority).
+struct Unsigned<'a>(&'a SshEd25519Cert);
+
+impl<'a> SshEncode for Unsigned<'a> {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        let x = self.0;
+        e.push_str_framed(SshEd25519Cert::NAME)?;
+        e.push_bytes_framed(&x.nonce)?;
+        e.push_bytes_framed(&x.pk)?;
+        e.push_u64be(x.serial)?;
+        e.push_u32be(x.type_.0)?;
+        e.push_str_framed(&x.key_id)?;
+        e.push_list(&x.valid_principals)?;
+        e.push_u64be(x.valid_after)?;
+        e.push_u64be(x.valid_before)?;
+        e.push_list(&x.critical_options)?;
+        e.push_list(&x.extensions)?;
+        e.push_bytes_framed(&x.reserved)?;
+        e.push(&x.authority)
+    }
+}
+
 impl SshDecode for SshEd25519Cert {
     fn decode<'a, D: SshDecoder<'a>>(c: &mut D) -> Option<Self> {
         c.expect_str_framed(SshEd25519Cert::NAME)?;
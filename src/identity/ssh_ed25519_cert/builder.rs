@@ -0,0 +1,186 @@
+use super::*;
+use crate::agent::{AuthAgent, AuthAgentResult};
+use rand_core::{OsRng, RngCore};
+
+/// A builder for [SshEd25519Cert]s (like `ssh-keygen -s`).
+///
+/// The certificate is valid forever and for all principals unless restricted. Options and
+/// extensions are sorted by name when signing (as required by OpenSSH).
+#[derive(Clone, Debug)]
+pub struct SshEd25519CertBuilder {
+    cert: SshEd25519Cert,
+}
+
+impl SshEd25519CertBuilder {
+    pub fn new(pk: &SshEd25519PublicKey, type_: CertType) -> Self {
+        let mut nonce = vec![0; 32];
+        OsRng.fill_bytes(&mut nonce);
+        Self {
+            cert: SshEd25519Cert {
+                nonce,
+                pk: *pk.pk(),
+                serial: 0,
+                type_,
+                key_id: String::new(),
+                valid_principals: vec![],
+                valid_after: 0,
+                valid_before: u64::MAX,
+                critical_options: vec![],
+                extensions: vec![],
+                reserved: vec![],
+                authority: Identity::from(vec![]),
+                signature: Signature::new(String::new(), vec![]),
+            },
+        }
+    }
+
+    /// Replace the random nonce (only useful for reproducible certificates).
+    pub fn nonce(mut self, nonce: &[u8]) -> Self {
+        self.cert.nonce = nonce.into();
+        self
+    }
+
+    pub fn serial(mut self, serial: u64) -> Self {
+        self.cert.serial = serial;
+        self
+    }
+
+    pub fn key_id(mut self, key_id: &str) -> Self {
+        self.cert.key_id = key_id.into();
+        self
+    }
+
+    /// Add a user or host name the certificate is valid for.
+    pub fn principal(mut self, principal: &str) -> Self {
+        self.cert.valid_principals.push(principal.into());
+        self
+    }
+
+    /// Set the validity window (seconds since the Unix epoch, `valid_before` is exclusive).
+    pub fn validity(mut self, valid_after: u64, valid_before: u64) -> Self {
+        self.cert.valid_after = valid_after;
+        self.cert.valid_before = valid_before;
+        self
+    }
+
+    /// Add a critical option (replaces an option with the same name).
+    pub fn option(mut self, option: CertOption) -> Self {
+        self.cert
+            .critical_options
+            .retain(|x| x.name() != option.name());
+        self.cert.critical_options.push(option);
+        self
+    }
+
+    /// Add an extension (replaces an extension with the same name).
+    pub fn extension(mut self, extension: CertExtension) -> Self {
+        self.cert
+            .extensions
+            .retain(|x| x.name() != extension.name());
+        self.cert.extensions.push(extension);
+        self
+    }
+
+    /// Sign the certificate with the authority's key held by an agent.
+    ///
+    /// Returns `Ok(None)` in case the agent refused to sign.
+    pub async fn sign(
+        self,
+        authority: &Identity,
+        agent: &dyn AuthAgent,
+    ) -> AuthAgentResult<Option<SshEd25519Cert>> {
+        let mut cert = self.prepare(authority);
+        let data = SshCodec::encode(&Unsigned(&cert)).expect("encodable");
+        match agent.signature(authority, &data, 0).await? {
+            Some(signature) => {
+                cert.signature = signature;
+                Ok(Some(cert))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Sign the certificate with the authority's in-process private key.
+    pub fn sign_with(self, authority: &PrivateKey) -> SshEd25519Cert {
+        let mut cert = self.prepare(&authority.identity());
+        let data = SshCodec::encode(&Unsigned(&cert)).expect("encodable");
+        cert.signature = authority.sign(&data);
+        cert
+    }
+
+    fn prepare(self, authority: &Identity) -> SshEd25519Cert {
+        let mut cert = self.cert;
+        cert.critical_options.sort_by(|a, b| a.name().cmp(b.name()));
+        cert.extensions.sort_by(|a, b| a.name().cmp(b.name()));
+        cert.authority = authority.clone();
+        cert
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::KeyStore;
+
+    fn ca() -> PrivateKey {
+        let text = include_str!("../../../resources/ed25519-ca");
+        PrivateKey::from_openssh(text, None).unwrap().remove(0).0
+    }
+
+    fn fixture() -> SshEd25519Cert {
+        let raw = include_bytes!("../../../resources/ed25519-user-cert.pub.raw");
+        SshCodec::decode(raw).unwrap()
+    }
+
+    fn builder(x: &SshEd25519Cert) -> SshEd25519CertBuilder {
+        SshEd25519Cert::builder(&SshEd25519PublicKey(&x.pk), CertType::USER)
+            .nonce(&x.nonce)
+            .serial(1)
+            .key_id("cert1")
+            .principal("user1")
+            .principal("user2")
+            .validity(1608329580, 2817929649)
+            .option(CertOption::SourceAddress("10.0.0.0/16,127.0.0.1/32".into()))
+            .option(CertOption::ForceCommand("ls".into()))
+            .extension(CertExtension::PermitUserRc)
+            .extension(CertExtension::PermitPty)
+            .extension(CertExtension::PermitPortForwarding)
+            .extension(CertExtension::PermitAgentForwarding)
+            .extension(CertExtension::PermitX11Forwarding)
+    }
+
+    #[test]
+    fn test_sign_with_01() {
+        let expected = fixture();
+        let cert = builder(&expected).sign_with(&ca());
+        assert!(cert.is_valid_ca_signature());
+        assert_eq!(cert, expected);
+        let raw = include_bytes!("../../../resources/ed25519-user-cert.pub.raw");
+        assert_eq!(SshCodec::encode(&cert).unwrap(), raw.to_vec());
+    }
+
+    #[test]
+    fn test_sign_with_02() {
+        let line = include_str!("../../../resources/ed25519-user-cert.pub");
+        let cert = builder(&fixture()).sign_with(&ca());
+        let id = Identity::from(&cert);
+        assert_eq!(id.as_ssh_ed25519_cert(), Some(cert));
+        let comment = line.trim().rsplit(' ').next().unwrap();
+        assert_eq!(id.to_authorized_key(comment), line.trim());
+    }
+
+    #[tokio::test]
+    async fn test_sign_01() {
+        let ca = ca();
+        let agent = KeyStore::new();
+        let pk = SshEd25519PublicKey(&[1; 32]);
+        let builder = SshEd25519Cert::builder(&pk, CertType::HOST).principal("foo.example.com");
+        let cert = builder.clone().sign(&ca.identity(), &agent).await.unwrap();
+        assert_eq!(cert, None);
+        agent.add(ca.clone(), "ca");
+        let cert = builder.sign(&ca.identity(), &agent).await.unwrap().unwrap();
+        assert!(cert.verify_for_host("foo.example.com").is_ok());
+        assert!(cert.verify_for_host("bar.example.com").is_err());
+        assert_eq!(cert.authority(), &ca.identity());
+    }
+}
@@ -0,0 +1,279 @@
+//! The SSHSIG signature format (see `PROTOCOL.sshsig` in the OpenSSH sources).
+
+mod allowed_signers;
+
+pub use self::allowed_signers::*;
+
+use super::private_key::pem;
+use super::*;
+use crate::agent::{AuthAgent, AuthAgentResult};
+use crate::util::codec::*;
+use sha2::{Digest, Sha256, Sha512};
+
+/// A signature of arbitrary data (like `ssh-keygen -Y sign`).
+///
+/// The signature is bound to a namespace (e.g. `file` or `git`) so that it cannot be used in
+/// another context. The data is hashed before signing (SHA-512 by default).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SshSig {
+    identity: Identity,
+    namespace: String,
+    hash_algorithm: String,
+    signature: Signature,
+}
+
+impl SshSig {
+    pub const SHA256: &'static str = "sha256";
+    pub const SHA512: &'static str = "sha512";
+
+    const MAGIC: &'static [u8] = b"SSHSIG";
+    const VERSION: u32 = 1;
+    const LABEL: &'static str = "SSH SIGNATURE";
+    /// The line width used by `ssh-keygen`.
+    const WIDTH: usize = 70;
+    /// The agent flag to request `rsa-sha2-512` signatures for RSA keys.
+    const SSH_AGENT_RSA_SHA2_512: u32 = 4;
+
+    /// Sign data with a key (or certificate) held by an agent.
+    ///
+    /// Returns `Ok(None)` in case the agent refused to sign.
+    pub async fn sign(
+        agent: &dyn AuthAgent,
+        identity: &Identity,
+        namespace: &str,
+        data: &[u8],
+    ) -> AuthAgentResult<Option<Self>> {
+        let signed = Self::signed_data(namespace, Self::SHA512, data).expect("supported hash");
+        let flags = match identity.key().algorithm() {
+            ssh_rsa::SshRsa::NAME => Self::SSH_AGENT_RSA_SHA2_512,
+            _ => 0,
+        };
+        let signature = agent.signature(identity, &signed, flags).await?;
+        Ok(signature.map(|signature| Self {
+            identity: identity.clone(),
+            namespace: namespace.into(),
+            hash_algorithm: Self::SHA512.into(),
+            signature,
+        }))
+    }
+
+    /// Sign data with an in-process private key.
+    pub fn sign_with(key: &PrivateKey, namespace: &str, data: &[u8]) -> Self {
+        let signed = Self::signed_data(namespace, Self::SHA512, data).expect("supported hash");
+        Self {
+            identity: key.identity(),
+            namespace: namespace.into(),
+            hash_algorithm: Self::SHA512.into(),
+            signature: key.sign(&signed),
+        }
+    }
+
+    /// The signer's key or certificate.
+    pub fn identity(&self) -> &Identity {
+        &self.identity
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn hash_algorithm(&self) -> &str {
+        &self.hash_algorithm
+    }
+
+    /// Verify the signature of the data in the given namespace (like `ssh-keygen -Y
+    /// check-novalidate`).
+    ///
+    /// This does not check whether the signer is trusted (see [AllowedSigners]).
+    pub fn verify(&self, namespace: &str, data: &[u8]) -> Result<(), SshSigError> {
+        if self.namespace != namespace {
+            return Err(SshSigError::InvalidNamespace);
+        }
+        let signed = Self::signed_data(namespace, &self.hash_algorithm, data)
+            .ok_or_else(|| SshSigError::UnsupportedHash(self.hash_algorithm.clone()))?;
+        let key = self.identity.key();
+        match (key.algorithm(), self.signature.algo()) {
+            // Signatures with SHA-1 are not allowed
+            (ssh_rsa::SshRsa::NAME, ssh_rsa::SshRsa::NAME) => {
+                Err(SignatureError::UnsupportedSignature)?
+            }
+            _ => Ok(self.signature.verify(&self.identity, &signed)?),
+        }
+    }
+
+    /// Parse an armored signature (`-----BEGIN SSH SIGNATURE-----`).
+    pub fn parse(text: &str) -> Result<Self, SshSigError> {
+        match pem::decode(text) {
+            Some((Self::LABEL, blob)) => {
+                SshCodec::decode(&blob).map_err(|_| SshSigError::InvalidFormat)
+            }
+            _ => Err(SshSigError::InvalidFormat),
+        }
+    }
+
+    /// Format as armored signature (`-----BEGIN SSH SIGNATURE-----`).
+    pub fn to_armored(&self) -> String {
+        let blob = SshCodec::encode(self).expect("encodable");
+        pem::encode(Self::LABEL, &blob, Self::WIDTH)
+    }
+
+    fn signed_data(namespace: &str, hash_algorithm: &str, data: &[u8]) -> Option<Vec<u8>> {
+        let hash = match hash_algorithm {
+            Self::SHA256 => Sha256::digest(data).to_vec(),
+            Self::SHA512 => Sha512::digest(data).to_vec(),
+            _ => return None,
+        };
+        let signed = SignedData {
+            namespace,
+            hash_algorithm,
+            hash: &hash,
+        };
+        SshCodec::encode(&signed).ok()
+    }
+}
+
+impl SshEncode for SshSig {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_bytes(Self::MAGIC)?;
+        e.push_u32be(Self::VERSION)?;
+        e.push(&self.identity)?;
+        e.push_str_framed(&self.namespace)?;
+        e.push_str_framed("")?;
+        e.push_str_framed(&self.hash_algorithm)?;
+        e.push(&self.signature)
+    }
+}
+
+impl SshDecode for SshSig {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_bytes(Self::MAGIC)?;
+        d.expect_u32be(Self::VERSION)?;
+        let identity = d.take()?;
+        let namespace = d.take_str_framed()?.into();
+        let _reserved = d.take_bytes_framed()?;
+        let hash_algorithm = d.take_str_framed()?.into();
+        let signature = d.take()?;
+        Some(Self {
+            identity,
+            namespace,
+            hash_algorithm,
+            signature,
+        })
+    }
+}
+
+/// The data that actually gets signed.
+struct SignedData<'a> {
+    namespace: &'a str,
+    hash_algorithm: &'a str,
+    hash: &'a [u8],
+}
+
+impl<'a> SshEncode for SignedData<'a> {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_bytes(SshSig::MAGIC)?;
+        e.push_str_framed(self.namespace)?;
+        e.push_str_framed("")?;
+        e.push_str_framed(self.hash_algorithm)?;
+        e.push_bytes_framed(self.hash)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SshSigError {
+    InvalidFormat,
+    InvalidNamespace,
+    UnsupportedHash(String),
+    SignatureError(SignatureError),
+    CertError(CertError),
+    /// The signer is not allowed for the principal.
+    NotAllowed,
+}
+
+impl From<SignatureError> for SshSigError {
+    fn from(e: SignatureError) -> Self {
+        Self::SignatureError(e)
+    }
+}
+
+impl From<CertError> for SshSigError {
+    fn from(e: CertError) -> Self {
+        Self::CertError(e)
+    }
+}
+
+impl std::error::Error for SshSigError {}
+
+impl std::fmt::Display for SshSigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidFormat => write!(f, "Invalid signature format"),
+            Self::InvalidNamespace => write!(f, "Invalid signature namespace"),
+            Self::UnsupportedHash(x) => write!(f, "Unsupported hash algorithm: {}", x),
+            Self::SignatureError(e) => write!(f, "Signature: {:?}", e),
+            Self::CertError(e) => write!(f, "Certificate: {}", e),
+            Self::NotAllowed => write!(f, "Signer not allowed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::KeyStore;
+
+    pub const DATA: &[u8] = b"hello world\n";
+
+    /// `ssh-keygen -Y sign -f ed25519-user -n file`
+    pub const SIG: &str = "-----BEGIN SSH SIGNATURE-----
+U1NIU0lHAAAAAQAAADMAAAALc3NoLWVkMjU1MTkAAAAg2XL9V+11yBCer8zFhOGtYb9hBt
+GFm86yPz9oTlpYyAoAAAAEZmlsZQAAAAAAAAAGc2hhNTEyAAAAUwAAAAtzc2gtZWQyNTUx
+OQAAAEAhxajvELRMapTeMiVOUYn+9vwxP3Pf2GGyBvWvXCyAb/HHxdw4olC2KHgmK/s7TL
+tUMerQAk6hwz53bdjDBM0F
+-----END SSH SIGNATURE-----
+";
+
+    fn user() -> PrivateKey {
+        let text = include_str!("../../resources/ed25519-user");
+        PrivateKey::from_openssh(text, None).unwrap().remove(0).0
+    }
+
+    #[test]
+    fn test_parse_01() {
+        let sig = SshSig::parse(SIG).unwrap();
+        assert_eq!(sig.identity(), &user().identity());
+        assert_eq!(sig.namespace(), "file");
+        assert_eq!(sig.hash_algorithm(), SshSig::SHA512);
+        assert_eq!(sig.to_armored(), SIG);
+        assert_eq!(SshSig::parse("foo"), Err(SshSigError::InvalidFormat));
+    }
+
+    #[test]
+    fn test_verify_01() {
+        let sig = SshSig::parse(SIG).unwrap();
+        assert_eq!(sig.verify("file", DATA), Ok(()));
+        assert_eq!(sig.verify("git", DATA), Err(SshSigError::InvalidNamespace));
+        let e = SshSigError::SignatureError(SignatureError::InvalidSignature);
+        assert_eq!(sig.verify("file", b"hello world"), Err(e));
+    }
+
+    #[test]
+    fn test_sign_with_01() {
+        // Ed25519 signatures are deterministic
+        let sig = SshSig::sign_with(&user(), "file", DATA);
+        assert_eq!(sig.to_armored(), SIG);
+    }
+
+    #[tokio::test]
+    async fn test_sign_01() {
+        let agent = KeyStore::new();
+        let id = user().identity();
+        assert_eq!(SshSig::sign(&agent, &id, "file", DATA).await.unwrap(), None);
+        agent.add(user(), "user");
+        let sig = SshSig::sign(&agent, &id, "file", DATA)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sig.to_armored(), SIG);
+    }
+}
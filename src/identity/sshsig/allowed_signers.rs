@@ -0,0 +1,290 @@
+use super::*;
+use crate::util::glob::GlobList;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// The signers trusted for [SshSig] signatures (an `allowed_signers` file as used by
+/// `ssh-keygen -Y verify`).
+///
+/// Each line consists of principal patterns, optional options and a key:
+///
+/// ```text
+/// *@example.com namespaces="git",valid-after="20240101" ssh-ed25519 AAAAC3Nza...
+/// ```
+///
+/// Principals are comma separated globs (negation with `!`). Supported options are
+/// `cert-authority`, `namespaces`, `valid-after` and `valid-before` (timestamps in UTC). Invalid
+/// lines are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct AllowedSigners {
+    signers: Vec<AllowedSigner>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct AllowedSigner {
+    principals: String,
+    cert_authority: bool,
+    namespaces: Option<String>,
+    valid_after: Option<u64>,
+    valid_before: Option<u64>,
+    key: Identity,
+}
+
+impl AllowedSigners {
+    pub fn parse(text: &str) -> Self {
+        let signers = text.lines().filter_map(AllowedSigner::parse).collect();
+        Self { signers }
+    }
+
+    pub async fn load<P: AsRef<Path>>(path: P) -> Result<Self, std::io::Error> {
+        Ok(Self::parse(&tokio::fs::read_to_string(path).await?))
+    }
+
+    /// Verify a signature and that its signer is allowed for the principal and namespace.
+    pub fn verify(
+        &self,
+        principal: &str,
+        namespace: &str,
+        data: &[u8],
+        sig: &SshSig,
+    ) -> Result<(), SshSigError> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default();
+        self.verify_at(principal, namespace, data, sig, now)
+    }
+
+    fn verify_at(
+        &self,
+        principal: &str,
+        namespace: &str,
+        data: &[u8],
+        sig: &SshSig,
+        now: u64,
+    ) -> Result<(), SshSigError> {
+        sig.verify(namespace, data)?;
+        let mut result = Err(SshSigError::NotAllowed);
+        for signer in &self.signers {
+            if !signer.is_applicable(principal, namespace, now) {
+                continue;
+            }
+            match (signer.cert_authority, sig.identity().as_cert()) {
+                (false, _) if signer.key == *sig.identity() => return Ok(()),
+                (true, Some(cert)) if signer.key == *cert.authority() => {
+                    match cert.verify_for_signer(principal) {
+                        Ok(()) => return Ok(()),
+                        Err(e) => result = Err(e.into()),
+                    }
+                }
+                _ => (),
+            }
+        }
+        result
+    }
+}
+
+impl AllowedSigner {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (principals, rest) = split_token(line)?;
+        let mut signer = Self {
+            principals: principals.trim_matches('"').into(),
+            cert_authority: false,
+            namespaces: None,
+            valid_after: None,
+            valid_before: None,
+            key: Identity::from(vec![]),
+        };
+        signer.key = match rest.parse() {
+            Ok(key) => key,
+            Err(_) => {
+                let (options, rest) = split_token(rest)?;
+                for option in split_options(options) {
+                    match option.split_once('=') {
+                        None if option.eq_ignore_ascii_case("cert-authority") => {
+                            signer.cert_authority = true
+                        }
+                        Some((name, value)) => {
+                            let value = value.strip_prefix('"')?.strip_suffix('"')?;
+                            match name.to_ascii_lowercase().as_str() {
+                                "namespaces" => signer.namespaces = Some(value.into()),
+                                "valid-after" => signer.valid_after = Some(parse_time(value)?),
+                                "valid-before" => signer.valid_before = Some(parse_time(value)?),
+                                _ => return None,
+                            }
+                        }
+                        None => return None,
+                    }
+                }
+                rest.parse().ok()?
+            }
+        };
+        Some(signer)
+    }
+
+    fn is_applicable(&self, principal: &str, namespace: &str, now: u64) -> bool {
+        GlobList(self.principals.as_str()).test(principal)
+            && self
+                .namespaces
+                .as_ref()
+                .map(|x| GlobList(x.as_str()).test(namespace))
+                != Some(false)
+            && self.valid_after.map(|t| t <= now) != Some(false)
+            && self.valid_before.map(|t| now < t) != Some(false)
+    }
+}
+
+/// Split the first whitespace separated token (that may contain quoted whitespace).
+fn split_token(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start();
+    let mut quoted = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => return Some((&s[..i], &s[i..])),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Split options by commas (that may be contained in quoted values).
+fn split_options(s: &str) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    s.split(move |c| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ',' && !quoted
+    })
+}
+
+/// Parse a timestamp like `YYYYMMDD[HHMM[SS]]` with optional `Z` suffix (always UTC).
+fn parse_time(s: &str) -> Option<u64> {
+    let s = s.strip_suffix('Z').unwrap_or(s);
+    if !matches!(s.len(), 8 | 12 | 14) || !s.bytes().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+    let num = |i: usize, n: usize| {
+        s.get(i..i + n)
+            .map(|x| x.parse::<i64>().ok())
+            .unwrap_or(Some(0))
+    };
+    let (y, m, d) = (num(0, 4)?, num(4, 2)?, num(6, 2)?);
+    let (hh, mm, ss) = (num(8, 2)?, num(10, 2)?, num(12, 2)?);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) || hh > 23 || mm > 59 || ss > 59 {
+        return None;
+    }
+    // Days since 1970-01-01 in the proleptic Gregorian calendar
+    let (y, m) = if m <= 2 { (y - 1, m + 9) } else { (y, m - 3) };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * m + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    u64::try_from(days * 86400 + hh * 3600 + mm * 60 + ss).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{DATA, SIG};
+    use super::*;
+
+    const USER: &str = include_str!("../../../resources/ed25519-user.pub");
+    const CA: &str = include_str!("../../../resources/ed25519-ca.pub");
+
+    /// `ssh-keygen -Y sign -f ed25519-user-cert.pub -n git`
+    const SIG_CERT: &str = "-----BEGIN SSH SIGNATURE-----
+U1NIU0lHAAAAAQAAAhAAAAAgc3NoLWVkMjU1MTktY2VydC12MDFAb3BlbnNzaC5jb20AAA
+AgyjCt5KFUKid37QGU6A6amDalVEpjSDzV2KXO/Q/+5i0AAAAg2XL9V+11yBCer8zFhOGt
+Yb9hBtGFm86yPz9oTlpYyAoAAAAAAAAAAQAAAAEAAAAFY2VydDEAAAASAAAABXVzZXIxAA
+AABXVzZXIyAAAAAF/dKWwAAAAAp/YxsQAAAE0AAAANZm9yY2UtY29tbWFuZAAAAAYAAAAC
+bHMAAAAOc291cmNlLWFkZHJlc3MAAAAcAAAAGDEwLjAuMC4wLzE2LDEyNy4wLjAuMS8zMg
+AAAIIAAAAVcGVybWl0LVgxMS1mb3J3YXJkaW5nAAAAAAAAABdwZXJtaXQtYWdlbnQtZm9y
+d2FyZGluZwAAAAAAAAAWcGVybWl0LXBvcnQtZm9yd2FyZGluZwAAAAAAAAAKcGVybWl0LX
+B0eQAAAAAAAAAOcGVybWl0LXVzZXItcmMAAAAAAAAAAAAAADMAAAALc3NoLWVkMjU1MTkA
+AAAg0cKa37+kQ5CHDHkBHZJdgeSfl3KNyHzby+BmIcGpa28AAABTAAAAC3NzaC1lZDI1NT
+E5AAAAQFUq3BYOBz5Vh53gpKuOgb1GbGiDEWUrlTYc1vzIqxDZzaoaGyjsetLoMLu0kbaF
+Y7ukBLIyZCOomW+l1kucUAAAAAADZ2l0AAAAAAAAAAZzaGE1MTIAAABTAAAAC3NzaC1lZD
+I1NTE5AAAAQKjCEWbWPsh//iZl5tkY63+InHDl2CEaF/OXdKYsx0v25xxuxjJsb3yhc2wh
+Ky2WTC9LqhhKOpRwl3Xc3LWwFAA=
+-----END SSH SIGNATURE-----
+";
+
+    #[test]
+    fn test_parse_01() {
+        let text = format!(
+            "# comment\n\n*@example.com,!root@* {}\nfoo cert-authority,namespaces=\"git,file\",valid-after=\"20240101\",valid-before=\"20250101120000Z\" {}\nbar invalid=\"x\" {}\nbaz {}",
+            USER.trim(), CA.trim(), USER.trim(), "ssh-ed25519 !!!"
+        );
+        let signers = AllowedSigners::parse(&text).signers;
+        assert_eq!(signers.len(), 2);
+        assert_eq!(signers[0].principals, "*@example.com,!root@*");
+        assert!(!signers[0].cert_authority);
+        assert_eq!(signers[0].key, USER.parse().unwrap());
+        assert!(signers[1].cert_authority);
+        assert_eq!(signers[1].namespaces.as_deref(), Some("git,file"));
+        assert_eq!(signers[1].valid_after, Some(1704067200));
+        assert_eq!(signers[1].valid_before, Some(1735732800));
+        assert_eq!(signers[1].key, CA.parse().unwrap());
+    }
+
+    #[test]
+    fn test_parse_time_01() {
+        assert_eq!(parse_time("19700101"), Some(0));
+        assert_eq!(parse_time("20001231235959"), Some(978307199));
+        assert_eq!(parse_time("200012312359Z"), Some(978307140));
+        assert_eq!(parse_time("20001301"), None);
+        assert_eq!(parse_time("2000010"), None);
+        assert_eq!(parse_time("2000010a"), None);
+    }
+
+    #[test]
+    fn test_verify_01() {
+        let sig = SshSig::parse(SIG).unwrap();
+        let signers = AllowedSigners::parse(&format!("*@example.com,!root@* {}", USER));
+        assert_eq!(
+            signers.verify("user@example.com", "file", DATA, &sig),
+            Ok(())
+        );
+        let e = Err(SshSigError::NotAllowed);
+        assert_eq!(signers.verify("root@example.com", "file", DATA, &sig), e);
+        assert_eq!(signers.verify("user@example.org", "file", DATA, &sig), e);
+        let e = Err(SshSigError::InvalidNamespace);
+        assert_eq!(signers.verify("user@example.com", "git", DATA, &sig), e);
+    }
+
+    #[test]
+    fn test_verify_02() {
+        let sig = SshSig::parse(SIG).unwrap();
+        let line = format!("user namespaces=\"git\",valid-after=\"20200101\" {}", USER);
+        let signers = AllowedSigners::parse(&line);
+        let e = Err(SshSigError::NotAllowed);
+        assert_eq!(signers.verify("user", "file", DATA, &sig), e);
+        let line = format!("user namespaces=\"f*\",valid-before=\"20200101\" {}", USER);
+        let signers = AllowedSigners::parse(&line);
+        assert_eq!(
+            signers.verify_at("user", "file", DATA, &sig, 1577836799),
+            Ok(())
+        );
+        assert_eq!(signers.verify_at("user", "file", DATA, &sig, 1577836800), e);
+    }
+
+    #[test]
+    fn test_verify_cert_01() {
+        let sig = SshSig::parse(SIG_CERT).unwrap();
+        assert_eq!(sig.verify("git", DATA), Ok(()));
+        let signers = AllowedSigners::parse(&format!("* cert-authority {}", CA));
+        assert_eq!(signers.verify("user1", "git", DATA, &sig), Ok(()));
+        let e = Err(SshSigError::CertError(CertError::InvalidPrincipal));
+        assert_eq!(signers.verify("user3", "git", DATA, &sig), e);
+        // The CA key is not a signer itself and the user key only without cert-authority
+        let signers = AllowedSigners::parse(&format!("* {}\n* cert-authority {}", CA, USER));
+        let e = Err(SshSigError::NotAllowed);
+        assert_eq!(signers.verify("user1", "git", DATA, &sig), e);
+    }
+}
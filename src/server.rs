@@ -1,12 +1,17 @@
 mod config;
 mod error;
+mod sshd_config;
 
 pub use self::config::*;
 pub use self::error::*;
+pub use self::sshd_config::*;
 
 use crate::transport::Transport;
+use crate::user_auth::{UserAuth, UserAuthConfig};
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
 
 #[derive(Debug)]
 pub struct Server {
@@ -22,16 +27,58 @@ impl Server {
         }
     }
 
+    /// Listen on all bind addresses (fails as soon as one of them fails).
     pub async fn listen(&self) -> Result<(), ServerError> {
         let fe = |e: std::io::Error| ServerError::SocketError(e);
-        let ba = self.config.socket.bind_addr;
-        let tl = TcpListener::bind(ba).await.map_err(fe)?;
+        let mut listeners = JoinSet::new();
+        for ba in &self.config.socket.bind_addrs {
+            let tl = TcpListener::bind(ba).await.map_err(fe)?;
+            listeners.spawn(Self::accept(self.config.clone(), tl));
+        }
+        while let Some(result) = listeners.join_next().await {
+            result.map_err(|e| fe(e.into()))??;
+        }
+        Ok(())
+    }
+
+    /// Accept connections and serve each of them in a separate task.
+    async fn accept(config: Arc<ServerConfig>, tl: TcpListener) -> Result<(), ServerError> {
         loop {
-            let (s, _) = tl.accept().await.map_err(fe)?;
-            let ct = &self.config.transport;
-            let ca = &self.config.auth_agent;
-            let _ = Transport::accept(s, ct, ca, "ssh-userauth").await?;
-            log::warn!("ACCEPTED");
+            let (s, addr) = tl.accept().await.map_err(ServerError::SocketError)?;
+            let config = config.clone();
+            drop(tokio::spawn(async move {
+                if let Err(e) = Self::serve(config, s, addr).await {
+                    log::warn!("{}: {}", addr, e);
+                }
+            }));
         }
     }
+
+    /// Serve a connection accepted from `addr`.
+    ///
+    /// The address is the one reported by accept (the policy must never be evaluated without).
+    async fn serve(
+        config: Arc<ServerConfig>,
+        socket: TcpStream,
+        addr: SocketAddr,
+    ) -> Result<(), ServerError> {
+        let ct = &config.transport;
+        let cc = &config.connection;
+        let ca = &config.auth_agent;
+        let ua = match config.auth_policy {
+            Some(ref policy) => {
+                let mut ua = UserAuthConfig::clone(&config.user_auth);
+                Arc::new(policy.for_address(addr.ip())).apply(&mut ua);
+                Arc::new(ua)
+            }
+            None => config.user_auth.clone(),
+        };
+        let ua = &ua;
+        let sv = UserAuth::SSH_USERAUTH;
+        let t = Transport::accept(socket, ct, ca, sv).await?;
+        let mut c = UserAuth::offer_connection(t, cc, |_| Box::new(()), ua).await?;
+        log::debug!("ACCEPTED");
+        c.closed().await;
+        Ok(())
+    }
 }
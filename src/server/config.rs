@@ -1,7 +1,11 @@
+use super::AuthPolicy;
+#[cfg(doc)]
+use super::SshdConfig;
 use crate::agent::AuthAgent;
 use crate::agent::LocalAgent;
 use crate::connection::ConnectionConfig;
 use crate::transport::*;
+use crate::user_auth::UserAuthConfig;
 use std::net::Ipv4Addr;
 use std::net::SocketAddr;
 
@@ -13,6 +17,15 @@ pub struct ServerConfig {
     pub transport: Arc<TransportConfig>,
     pub auth_agent: Arc<dyn AuthAgent>,
     pub connection: Arc<ConnectionConfig>,
+    pub user_auth: Arc<UserAuthConfig>,
+    /// The policy to bind to each client's address (see [SshdConfig::server_config]).
+    ///
+    /// It is bound to the address reported when accepting each connection and replaces the user,
+    /// `publickey` and `password` verifiers of the user auth config, so that `Match Address`
+    /// blocks apply.
+    ///
+    /// Defaults to `None`.
+    pub auth_policy: Option<Arc<AuthPolicy>>,
 }
 
 impl Default for ServerConfig {
@@ -21,6 +34,8 @@ impl Default for ServerConfig {
             socket: Arc::new(SocketConfig::default()),
             transport: Arc::new(TransportConfig::default()),
             connection: Arc::new(ConnectionConfig::default()),
+            user_auth: Arc::new(UserAuthConfig::default()),
+            auth_policy: None,
             auth_agent: match LocalAgent::new_env() {
                 Some(agent) => Arc::new(agent),
                 None => Arc::new(()),
@@ -29,16 +44,18 @@ impl Default for ServerConfig {
     }
 }
 
-
 #[derive(Clone, Debug)]
 pub struct SocketConfig {
-    pub bind_addr: SocketAddr,
+    /// The addresses to listen on (all of them).
+    ///
+    /// Defaults to `127.0.0.1:2200`.
+    pub bind_addrs: Vec<SocketAddr>,
 }
 
 impl Default for SocketConfig {
     fn default() -> Self {
         Self {
-            bind_addr: SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 2200),
+            bind_addrs: vec![SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 2200)],
         }
     }
 }
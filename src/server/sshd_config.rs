@@ -0,0 +1,751 @@
+use super::*;
+use crate::agent::{AuthAgent, KeyStore};
+use crate::connection::ConnectionConfig;
+use crate::identity::{Identity, Krl};
+use crate::transport::TransportConfig;
+use crate::user_auth::*;
+use crate::util::cidr::Cidr;
+use crate::util::config::*;
+use crate::util::glob::*;
+use crate::util::BoxFuture;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+/// An OpenSSH server configuration (`sshd_config`).
+///
+/// The global options are turned into a [ServerConfig] by [server_config](Self::server_config).
+/// Authentication is governed by an [AuthPolicy] that evaluates the per-user options (also in
+/// `Match` blocks) on each request.
+///
+/// Supported are `Match` blocks (criteria `All`, `User` and `Address`) and the following
+/// options: `ListenAddress`, `Port`, `HostKey`, `Ciphers`, `KexAlgorithms`,
+/// `HostKeyAlgorithms`, `MACs`, `RekeyLimit`, `ClientAliveInterval`, `ClientAliveCountMax`,
+/// `MaxAuthTries`, `AuthenticationMethods`, `RevokedKeys`, `Banner` and those of the
+/// [AuthPolicy]. `Match` blocks only apply to the options of the [AuthPolicy]; other options
+/// are ignored there, just like unknown options anywhere. Options restricting access that
+/// cannot be honored are refused: `ForceCommand`, `ChrootDirectory`, `AllowGroups`,
+/// `DenyGroups`, `AuthorizedPrincipalsFile` and `AuthorizedPrincipalsCommand` anywhere as well
+/// as `AuthenticationMethods` and `RevokedKeys` in `Match` blocks.
+#[derive(Clone, Debug, Default)]
+pub struct SshdConfig {
+    global: Vec<ConfigLine>,
+    blocks: Vec<MatchBlock>,
+    password_verifier: Option<Arc<dyn PasswordVerifier>>,
+}
+
+#[derive(Clone, Debug)]
+struct MatchBlock {
+    /// Criteria (lower case) and their pattern lists
+    criteria: Vec<(String, String)>,
+    lines: Vec<ConfigLine>,
+}
+
+impl SshdConfig {
+    /// Options that may be used in `Match` blocks (evaluated by the [AuthPolicy]).
+    const MATCH_KEYWORDS: [&'static str; 7] = [
+        "allowusers",
+        "authorizedkeysfile",
+        "denyusers",
+        "passwordauthentication",
+        "permitopen",
+        "pubkeyauthentication",
+        "trustedusercakeys",
+    ];
+
+    /// Options restricting access that are not supported (refused instead of ignored).
+    const REFUSED_KEYWORDS: [&'static str; 6] = [
+        "allowgroups",
+        "authorizedprincipalscommand",
+        "authorizedprincipalsfile",
+        "chrootdirectory",
+        "denygroups",
+        "forcecommand",
+    ];
+
+    /// Options restricting access that only apply globally (refused in `Match` blocks).
+    const GLOBAL_KEYWORDS: [&'static str; 2] = ["authenticationmethods", "revokedkeys"];
+
+    /// Options whose values accumulate over several lines.
+    const LIST_KEYWORDS: [&'static str; 4] =
+        ["allowusers", "denyusers", "hostkey", "listenaddress"];
+
+    /// Parse a configuration.
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        Self::parse_file(Path::new("sshd_config"), text)
+    }
+
+    /// Load and parse a configuration file.
+    pub async fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = tokio::fs::read_to_string(path).await?;
+        Self::parse_file(path, &text)
+    }
+
+    /// Verify passwords with this verifier (if `PasswordAuthentication` is enabled).
+    ///
+    /// The `password` method is not offered without verifier.
+    pub fn password_verifier(mut self, verifier: Arc<dyn PasswordVerifier>) -> Self {
+        self.password_verifier = Some(verifier);
+        self
+    }
+
+    fn parse_file(path: &Path, text: &str) -> Result<Self, ConfigError> {
+        let mut self_ = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let err = |e| ConfigError::line(path, i + 1, e);
+            let Some(line) = ConfigLine::parse(line).map_err(err)? else {
+                continue;
+            };
+            if line.keyword == "match" {
+                let criteria = Self::parse_match(&line.args).map_err(err)?;
+                self_.blocks.push(MatchBlock {
+                    criteria,
+                    lines: vec![],
+                });
+                continue;
+            }
+            let keyword = line.keyword.as_str();
+            if Self::REFUSED_KEYWORDS.contains(&keyword) {
+                return Err(err(format!("{}: Not supported", line.keyword)));
+            }
+            Self::validate(&line).map_err(err)?;
+            match self_.blocks.last_mut() {
+                None => self_.global.push(line),
+                Some(block) if Self::MATCH_KEYWORDS.contains(&keyword) => block.lines.push(line),
+                Some(_) if Self::GLOBAL_KEYWORDS.contains(&keyword) => {
+                    return Err(err(format!("{}: Not supported in Match", line.keyword)))
+                }
+                Some(_) => log::debug!("{}: Ignored in Match", line.keyword),
+            }
+        }
+        Ok(self_)
+    }
+
+    fn parse_match(args: &[String]) -> Result<Vec<(String, String)>, String> {
+        let mut criteria = vec![];
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let criterion = arg.to_ascii_lowercase();
+            match criterion.as_str() {
+                "all" => criteria.push((criterion, String::new())),
+                "user" | "address" => {
+                    let value = args
+                        .next()
+                        .ok_or(format!("Match {}: Missing argument", arg))?;
+                    criteria.push((criterion, value.clone()));
+                }
+                _ => return Err(format!("Unsupported Match criterion: {}", arg)),
+            }
+        }
+        if criteria.is_empty() {
+            return Err("Missing Match criteria".into());
+        }
+        Ok(criteria)
+    }
+
+    /// Check the value of options that are evaluated per request.
+    fn validate(line: &ConfigLine) -> Result<(), String> {
+        match line.keyword.as_str() {
+            "passwordauthentication" | "pubkeyauthentication" => {
+                parse_flag(line.arg()?)?;
+            }
+            "permitopen" => {
+                for arg in &line.args {
+                    if arg != "any" && arg != "none" && PermitOpen::parse(arg).is_none() {
+                        return Err(format!("PermitOpen: Invalid value {}", arg));
+                    }
+                }
+            }
+            "allowusers" | "denyusers" | "authorizedkeysfile" | "trustedusercakeys"
+                if line.args.is_empty() =>
+            {
+                return Err(format!("{}: Missing argument", line.keyword));
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Collect the options for a user and client address (if known).
+    ///
+    /// Options of the first matching `Match` block take precedence over global ones.
+    fn options(&self, user: Option<&str>, addr: Option<IpAddr>) -> ConfigOptions {
+        let mut values = HashMap::new();
+        let matching = self.blocks.iter().filter(|b| b.test(user, addr));
+        for lines in matching.map(|b| &b.lines).chain([&self.global]) {
+            for (keyword, args) in Self::collect(lines) {
+                values.entry(keyword).or_insert(args);
+            }
+        }
+        ConfigOptions(values)
+    }
+
+    /// The values of the lines (first value wins unless the option accumulates).
+    fn collect(lines: &[ConfigLine]) -> HashMap<String, Vec<String>> {
+        let mut values: HashMap<String, Vec<String>> = HashMap::new();
+        for line in lines {
+            if Self::LIST_KEYWORDS.contains(&line.keyword.as_str()) {
+                let args = values.entry(line.keyword.clone()).or_default();
+                args.extend(line.args.iter().cloned());
+            } else if !values.contains_key(&line.keyword) {
+                values.insert(line.keyword.clone(), line.args.clone());
+            }
+        }
+        values
+    }
+
+    /// Create the server configuration from the global options.
+    ///
+    /// This loads the host keys, revoked keys and banner. It fails if no host key could be
+    /// loaded (defaults to `/etc/ssh/ssh_host_ed25519_key`). The [AuthPolicy] is stored as
+    /// [auth_policy](ServerConfig::auth_policy) and installed by the [Server] for each client
+    /// address: The user auth config itself has no verifiers.
+    pub async fn server_config(self) -> Result<ServerConfig, ConfigError> {
+        let err = |e| ConfigError::InvalidValue(e);
+        let options = ConfigOptions(Self::collect(&self.global));
+        let mut config = ServerConfig::default();
+        if let Some(bind_addrs) = Self::bind_addrs(&options).map_err(err)? {
+            config.socket = Arc::new(SocketConfig { bind_addrs });
+        }
+        config.auth_agent = Self::host_keys(&options).await?;
+
+        let mut tc = TransportConfig::default();
+        options.transport(&mut tc).map_err(err)?;
+        config.transport = Arc::new(tc);
+
+        let mut cc = ConnectionConfig::default();
+        if let Some(interval) = options.arg("clientaliveinterval").map_err(err)? {
+            let interval = parse_time(interval).map_err(err)?;
+            cc.keepalive_interval = (!interval.is_zero()).then_some(interval);
+        }
+        if let Some(count) = options.arg("clientalivecountmax").map_err(err)? {
+            cc.keepalive_count_max = parse_number("clientalivecountmax", count).map_err(err)?;
+        }
+        config.connection = Arc::new(cc);
+
+        let mut ua = UserAuthConfig::default();
+        if let Some(tries) = options.arg("maxauthtries").map_err(err)? {
+            ua.max_attempts = parse_number("maxauthtries", tries).map_err(err)?;
+        }
+        if let Some(args) = options.get("authenticationmethods") {
+            ua.required_methods = Self::required_methods(args).map_err(err)?;
+        }
+        if let Some(path) = options.arg("revokedkeys").map_err(err)? {
+            if path != "none" {
+                let krl = Krl::load(path)
+                    .await
+                    .map_err(|e| err(format!("RevokedKeys {}: {}", path, e)))?;
+                ua.revoked_keys = Some(Arc::new(krl));
+            }
+        }
+        if let Some(path) = options.arg("banner").map_err(err)? {
+            if path != "none" {
+                ua.banner = Some(tokio::fs::read_to_string(path).await?);
+            }
+        }
+        // The policy is only installed once the client's address is known
+        let policy = Arc::new(AuthPolicy {
+            config: Arc::new(self),
+            addr: None,
+        });
+        config.user_auth = Arc::new(ua);
+        config.auth_policy = Some(policy);
+        Ok(config)
+    }
+
+    /// The addresses to listen on (`None` keeps the default).
+    ///
+    /// Addresses without port use `Port` (or 22).
+    fn bind_addrs(options: &ConfigOptions) -> Result<Option<Vec<SocketAddr>>, String> {
+        let port = match options.arg("port")? {
+            Some(port) => Some(parse_number("port", port)?),
+            None => None,
+        };
+        let addrs = match options.get("listenaddress") {
+            Some(addrs) => addrs,
+            None => return Ok(port.map(|p| vec![SocketAddr::new([0, 0, 0, 0].into(), p)])),
+        };
+        let parse = |addr: &String| {
+            if let Ok(addr) = addr.parse::<SocketAddr>() {
+                return Ok(addr);
+            }
+            let ip = addr.strip_prefix('[').and_then(|a| a.strip_suffix(']'));
+            match ip.unwrap_or(addr).parse::<IpAddr>() {
+                Ok(ip) => Ok(SocketAddr::new(ip, port.unwrap_or(22))),
+                Err(_) => Err(format!("ListenAddress: Invalid address {}", addr)),
+            }
+        };
+        addrs.iter().map(parse).collect::<Result<_, _>>().map(Some)
+    }
+
+    async fn host_keys(options: &ConfigOptions) -> Result<Arc<dyn AuthAgent>, ConfigError> {
+        let keys = KeyStore::new();
+        let default = vec!["/etc/ssh/ssh_host_ed25519_key".to_string()];
+        let mut loaded = 0;
+        for path in options.get("hostkey").unwrap_or(&default) {
+            match keys.load(path, &()).await {
+                Ok(ids) => loaded += ids.len(),
+                Err(e) => log::warn!("HostKey {}: {}", path, e),
+            }
+        }
+        if loaded == 0 {
+            return Err(ConfigError::InvalidValue("No host key loaded".into()));
+        }
+        Ok(Arc::new(keys))
+    }
+
+    fn required_methods(args: &[String]) -> Result<Vec<Vec<&'static str>>, String> {
+        if let [any] = args {
+            if any == "any" {
+                return Ok(vec![]);
+            }
+        }
+        let methods = [
+            UserAuth::HOSTBASED,
+            UserAuth::PUBLICKEY,
+            UserAuth::KEYBOARD_INTERACTIVE,
+            UserAuth::PASSWORD,
+        ];
+        let method = |name: &str| {
+            let method = methods.iter().find(|m| **m == name);
+            method
+                .copied()
+                .ok_or(format!("Unsupported method: {}", name))
+        };
+        args.iter()
+            .map(|list| list.split(',').map(method).collect())
+            .collect()
+    }
+}
+
+impl MatchBlock {
+    fn test(&self, user: Option<&str>, addr: Option<IpAddr>) -> bool {
+        self.criteria
+            .iter()
+            .all(|(criterion, patterns)| match criterion.as_str() {
+                "all" => true,
+                "user" => user.is_some_and(|user| GlobList(patterns.as_str()).test(user)),
+                "address" => addr.is_some_and(|addr| test_address(patterns, addr)),
+                _ => false,
+            })
+    }
+}
+
+/// Test an address against a list of CIDR ranges, addresses or wildcards (may be negated).
+fn test_address(patterns: &str, addr: IpAddr) -> bool {
+    GlobList(patterns).test_by(&addr.to_string(), |pattern, input| {
+        if pattern.contains('/') {
+            Cidr(pattern).contains(&addr)
+        } else {
+            Glob(pattern).test(input)
+        }
+    })
+}
+
+fn parse_number<T: std::str::FromStr>(keyword: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{}: Invalid number {}", keyword, value))
+}
+
+/// A `PermitOpen` destination (`host:port`, both may be `*`).
+struct PermitOpen<'a> {
+    host: &'a str,
+    port: Option<u16>,
+}
+
+impl<'a> PermitOpen<'a> {
+    fn parse(s: &'a str) -> Option<Self> {
+        let (host, port) = s.rsplit_once(':')?;
+        let bracketed = host.strip_prefix('[').and_then(|h| h.strip_suffix(']'));
+        let host = bracketed.unwrap_or(host);
+        let port = match port {
+            "*" => None,
+            port => Some(port.parse().ok()?),
+        };
+        (!host.is_empty()).then_some(Self { host, port })
+    }
+
+    fn permits(&self, host: &str, port: u16) -> bool {
+        (self.host == "*" || self.host.eq_ignore_ascii_case(host))
+            && self.port.is_none_or(|p| p == port)
+    }
+}
+
+/// The authentication policy of an [SshdConfig].
+///
+/// The policy is evaluated for each request with the options for the user and client address
+/// (see [for_address](Self::for_address)):
+///
+/// - `AllowUsers` and `DenyUsers` (patterns `USER` or `USER@ADDRESS`, the address may be given
+///   in CIDR notation)
+/// - `PubkeyAuthentication` and `AuthorizedKeysFile` (defaults to `.ssh/authorized_keys` and
+///   `.ssh/authorized_keys2`, relative to the user's home directory). Lines with options are
+///   skipped.
+/// - `TrustedUserCAKeys` (certificates are accepted for their principals)
+/// - `PasswordAuthentication` (requires a [password verifier](SshdConfig::password_verifier))
+/// - `PermitOpen` (see [permits_open](Self::permits_open))
+#[derive(Clone, Debug)]
+pub struct AuthPolicy {
+    config: Arc<SshdConfig>,
+    addr: Option<IpAddr>,
+}
+
+impl AuthPolicy {
+    /// The policy for a client connecting from the given address.
+    pub fn for_address(&self, addr: IpAddr) -> Self {
+        Self {
+            config: self.config.clone(),
+            addr: Some(addr),
+        }
+    }
+
+    /// Install the policy as user, `publickey` and `password` verifier.
+    pub fn apply(self: &Arc<Self>, user_auth: &mut UserAuthConfig) {
+        user_auth.user_verifier = Some(self.clone());
+        user_auth.public_key_verifier = Some(self.clone());
+        if self.config.password_verifier.is_some() {
+            user_auth.password_verifier = Some(self.clone());
+        }
+    }
+
+    /// Whether the user may log in at all (`AllowUsers` and `DenyUsers`).
+    ///
+    /// This is checked once per user by [UserAuth::offer_connection] (see [UserVerifier]), not
+    /// by the method verifiers.
+    pub fn allows_user(&self, user: &str) -> bool {
+        let options = self.config.options(Some(user), self.addr);
+        let test = |pattern: &String| match pattern.split_once('@') {
+            Some((u, a)) => Glob(u).test(user) && self.addr.is_some_and(|x| test_address(a, x)),
+            None => Glob(pattern.as_str()).test(user),
+        };
+        if let Some(deny) = options.get("denyusers") {
+            if deny.iter().any(test) {
+                log::debug!("User {} denied by DenyUsers", user);
+                return false;
+            }
+        }
+        if let Some(allow) = options.get("allowusers") {
+            if !allow.iter().any(test) {
+                log::debug!("User {} not listed in AllowUsers", user);
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Whether the user may open a `direct-tcpip` channel to the destination (`PermitOpen`).
+    ///
+    /// The [Server] itself refuses such channels. Connection handlers forwarding ports shall
+    /// check each request with the policy for the client's address.
+    pub fn permits_open(&self, user: &str, host: &str, port: u16) -> bool {
+        let options = self.config.options(Some(user), self.addr);
+        let Some(args) = options.get("permitopen") else {
+            return true;
+        };
+        args.iter().any(|arg| match arg.as_str() {
+            "any" => true,
+            "none" => false,
+            arg => PermitOpen::parse(arg).is_some_and(|p| p.permits(host, port)),
+        })
+    }
+
+    async fn is_authorized(&self, user: &str, identity: &Identity) -> bool {
+        let options = self.config.options(Some(user), self.addr);
+        if options.flag("pubkeyauthentication") == Ok(Some(false)) {
+            return false;
+        }
+        if let Some(cert) = identity.as_cert() {
+            let Some(files) = options.get("trustedusercakeys") else {
+                return false;
+            };
+            if !Self::contains_key(files, cert.authority()).await {
+                return false;
+            }
+            let addr = self.addr.unwrap_or(IpAddr::from([0, 0, 0, 0]));
+            return match cert.verify_for_client(user, &addr) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::debug!("Certificate of {} rejected: {:?}", user, e);
+                    false
+                }
+            };
+        }
+        let defaults = [
+            ".ssh/authorized_keys".into(),
+            ".ssh/authorized_keys2".into(),
+        ];
+        let files = options.get("authorizedkeysfile");
+        let files = files.unwrap_or(&defaults);
+        if files.iter().all(|f| f == "none") {
+            return false;
+        }
+        let home = home_dir(user).await;
+        let mut paths = vec![];
+        for file in files.iter().filter(|f| *f != "none") {
+            match authorized_keys_path(file, user, home.as_deref()) {
+                Some(path) => paths.push(path),
+                None => log::debug!("AuthorizedKeysFile {}: No home for {}", file, user),
+            }
+        }
+        Self::contains_key(&paths, identity).await
+    }
+
+    /// Whether any of the files contains the key (in `authorized_keys` format).
+    async fn contains_key<P: AsRef<Path>>(paths: &[P], key: &Identity) -> bool {
+        for path in paths {
+            let path = path.as_ref();
+            let text = match tokio::fs::read_to_string(path).await {
+                Ok(text) => text,
+                Err(e) => {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        log::warn!("{}: {}", path.display(), e);
+                    }
+                    continue;
+                }
+            };
+            for line in text.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                match Identity::from_authorized_key(line) {
+                    Some((id, _)) if id.is_same_key(key) => return true,
+                    Some(_) => (),
+                    None => log::debug!("{}: Skipped line with options", path.display()),
+                }
+            }
+        }
+        false
+    }
+}
+
+impl UserVerifier for AuthPolicy {
+    fn is_allowed(&self, user: &str) -> BoxFuture<bool> {
+        let allowed = self.allows_user(user);
+        Box::pin(async move { allowed })
+    }
+}
+
+impl PublicKeyVerifier for AuthPolicy {
+    fn is_authorized(&self, user: &str, identity: &Identity) -> BoxFuture<bool> {
+        let self_ = self.clone();
+        let user = user.to_string();
+        let identity = identity.clone();
+        Box::pin(async move { self_.is_authorized(&user, &identity).await })
+    }
+}
+
+impl PasswordVerifier for AuthPolicy {
+    fn verify(&self, user: &str, password: &str) -> BoxFuture<PasswordVerification> {
+        let options = self.config.options(Some(user), self.addr);
+        match &self.config.password_verifier {
+            Some(v) if options.flag("passwordauthentication") != Ok(Some(false)) => {
+                v.verify(user, password)
+            }
+            _ => Box::pin(async { PasswordVerification::Rejected }),
+        }
+    }
+
+    fn change(
+        &self,
+        user: &str,
+        password: &str,
+        new_password: &str,
+    ) -> BoxFuture<PasswordVerification> {
+        let options = self.config.options(Some(user), self.addr);
+        match &self.config.password_verifier {
+            Some(v) if options.flag("passwordauthentication") != Ok(Some(false)) => {
+                v.change(user, password, new_password)
+            }
+            _ => Box::pin(async { PasswordVerification::Rejected }),
+        }
+    }
+}
+
+/// Expand `%%`, `%h` (home directory) and `%u` (user) and resolve relative to the home.
+fn authorized_keys_path(file: &str, user: &str, home: Option<&Path>) -> Option<PathBuf> {
+    let mut path = String::new();
+    let mut chars = file.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('%', Some('%')) => path.push('%'),
+            ('%', Some('u')) => path.push_str(user),
+            ('%', Some('h')) => path.push_str(&home?.to_string_lossy()),
+            _ => {
+                path.push(c);
+                continue;
+            }
+        }
+        let _ = chars.next();
+    }
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        Some(path)
+    } else {
+        Some(home?.join(path))
+    }
+}
+
+/// The home directory of a user (from `/etc/passwd`).
+async fn home_dir(user: &str) -> Option<PathBuf> {
+    let passwd = tokio::fs::read_to_string("/etc/passwd").await.ok()?;
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        (fields.len() >= 6 && fields[0] == user).then(|| fields[5].into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const USER_PUB: &str = include_str!("../../resources/ed25519-user.pub");
+    const USER_CERT: &str = include_str!("../../resources/ed25519-user-cert.pub");
+    const CA_PUB: &str = include_str!("../../resources/ed25519-ca.pub");
+
+    fn identity(line: &str) -> Identity {
+        Identity::from_authorized_key(line).unwrap().0
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = format!("shellfish-sshd-config-{}-{}", name, std::process::id());
+        let dir = std::env::temp_dir().join(dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn policy(config: SshdConfig) -> AuthPolicy {
+        AuthPolicy {
+            config: Arc::new(config),
+            addr: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_01() {
+        let text = "
+            Port 2222
+            PasswordAuthentication yes
+            Match User alice Address 10.0.0.0/8,!10.0.0.1
+                PasswordAuthentication no
+            Match all
+                PermitOpen localhost:80
+        ";
+        let config = SshdConfig::parse(text).unwrap();
+        assert_eq!(config.global.len(), 2);
+        assert_eq!(config.blocks.len(), 2);
+        let options = |user, addr: &str| config.options(Some(user), addr.parse().ok());
+        let flag =
+            |user, addr| options(user, addr).flag("passwordauthentication") != Ok(Some(false));
+        assert!(!flag("alice", "10.1.2.3"));
+        assert!(flag("alice", "10.0.0.1"));
+        assert!(flag("alice", "192.168.0.1"));
+        assert!(flag("bob", "10.1.2.3"));
+        // Unsupported options are ignored in Match blocks unless they restrict access
+        let text = "Match User alice\n  X11Forwarding no\n  Port 22\n";
+        assert!(SshdConfig::parse(text).unwrap().blocks[0].lines.is_empty());
+        assert!(SshdConfig::parse("Match User alice\n  ForceCommand true").is_err());
+        assert!(SshdConfig::parse("Match all\n  AuthenticationMethods publickey").is_err());
+        assert!(SshdConfig::parse("ChrootDirectory /srv").is_err());
+        assert!(SshdConfig::parse("Match Group wheel").is_err());
+        assert!(SshdConfig::parse("PermitOpen localhost").is_err());
+        assert!(SshdConfig::parse("PubkeyAuthentication maybe").is_err());
+    }
+
+    #[test]
+    fn test_allowed_01() {
+        let text = "
+            AllowUsers alice bob@10.0.0.0/8
+            AllowUsers carol
+            DenyUsers *@10.0.0.1
+            Match Address 192.168.0.0/16
+                AllowUsers dave
+        ";
+        let policy = policy(SshdConfig::parse(text).unwrap());
+        let at = |addr: &str| policy.for_address(addr.parse().unwrap());
+        assert!(at("10.1.1.1").allows_user("alice"));
+        assert!(at("10.1.1.1").allows_user("bob"));
+        assert!(at("10.1.1.1").allows_user("carol"));
+        assert!(!at("10.1.1.1").allows_user("dave"));
+        assert!(!at("127.0.0.1").allows_user("bob"));
+        assert!(!at("10.0.0.1").allows_user("alice"));
+        assert!(at("192.168.1.1").allows_user("dave"));
+        assert!(!at("192.168.1.1").allows_user("alice"));
+    }
+
+    #[test]
+    fn test_permits_open_01() {
+        let text = "PermitOpen db:5432 *:80 [::1]:*\nMatch User root\n  PermitOpen none\n";
+        let policy = policy(SshdConfig::parse(text).unwrap());
+        assert!(policy.permits_open("alice", "db", 5432));
+        assert!(policy.permits_open("alice", "example.com", 80));
+        assert!(policy.permits_open("alice", "::1", 22));
+        assert!(!policy.permits_open("alice", "db", 22));
+        assert!(!policy.permits_open("root", "db", 5432));
+    }
+
+    #[tokio::test]
+    async fn test_authorized_keys_01() {
+        let dir = temp_dir("keys");
+        std::fs::write(dir.join("alice"), format!("# keys\n{}", USER_PUB)).unwrap();
+        std::fs::write(dir.join("bob"), format!("restrict {}", USER_PUB)).unwrap();
+        std::fs::write(dir.join("ca"), CA_PUB).unwrap();
+        let text = format!(
+            "AuthorizedKeysFile {0}/%u\nTrustedUserCAKeys {0}/ca\nMatch User carol\n  PubkeyAuthentication no\n",
+            dir.display()
+        );
+        let policy = policy(SshdConfig::parse(&text).unwrap());
+        let key = identity(USER_PUB);
+        assert!(policy.is_authorized("alice", &key).await);
+        assert!(!policy.is_authorized("bob", &key).await);
+        assert!(!policy.is_authorized("carol", &key).await);
+        // The certificate is restricted to `user1` and `user2` from 10.0.0.0/16 and 127.0.0.1
+        let cert = identity(USER_CERT);
+        let local = policy.for_address("127.0.0.1".parse().unwrap());
+        assert!(local.is_authorized("user1", &cert).await);
+        assert!(!local.is_authorized("alice", &cert).await);
+        assert!(!policy.is_authorized("user1", &cert).await);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_server_config_01() {
+        let dir = temp_dir("server");
+        let key = dir.join("host_key");
+        std::fs::write(&key, include_str!("../../resources/ed25519-host")).unwrap();
+        let text = format!(
+            "ListenAddress [::1]\nListenAddress 127.0.0.1:2222\nPort 2022\nHostKey {}\nCiphers +chacha20*\nMaxAuthTries 3\nClientAliveInterval 30\nAuthenticationMethods publickey,password publickey\n",
+            key.display()
+        );
+        let config = SshdConfig::parse(&text).unwrap();
+        let config = config.server_config().await.unwrap();
+        let bind_addrs = [
+            "[::1]:2022".parse().unwrap(),
+            "127.0.0.1:2222".parse().unwrap(),
+        ];
+        assert_eq!(config.socket.bind_addrs, bind_addrs);
+        assert_eq!(config.auth_agent.identities().await.unwrap().len(), 1);
+        let ua = &config.user_auth;
+        assert_eq!(ua.max_attempts, 3);
+        assert_eq!(
+            ua.required_methods,
+            vec![vec!["publickey", "password"], vec!["publickey"]]
+        );
+        assert!(ua.user_verifier.is_none());
+        assert!(ua.public_key_verifier.is_none());
+        let policy = config.auth_policy.as_ref().unwrap();
+        let mut ua = UserAuthConfig::clone(ua);
+        Arc::new(policy.for_address([127, 0, 0, 1].into())).apply(&mut ua);
+        assert!(ua.user_verifier.is_some());
+        assert!(ua.public_key_verifier.is_some());
+        assert!(ua.password_verifier.is_none());
+        let cc = &config.connection;
+        assert_eq!(cc.keepalive_interval, Some(Duration::from_secs(30)));
+        let text = format!("HostKey {}/missing", dir.display());
+        let config = SshdConfig::parse(&text).unwrap();
+        assert!(config.server_config().await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -20,12 +20,14 @@ pub use self::ident::Identification;
 use self::trx::*;
 use crate::agent::AuthAgent;
 use crate::host::HostVerifier;
+use crate::identity::{Identity, Signature};
 use crate::util::codec::SshCodec;
 use crate::util::codec::SshDecode;
 use crate::util::codec::SshEncode;
 use crate::util::secret::Secret;
 use crate::util::socket::Socket;
 use std::future::{poll_fn, Future};
+use std::net::IpAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{ready, Context, Poll};
@@ -50,6 +52,9 @@ pub struct Transport {
 impl Transport {
     /// Create a new transport acting as client.
     ///
+    /// The host key is verified for the host name and port as well as the address the name has
+    /// been resolved to (if known, see [HostVerifier::verify]).
+    ///
     /// The initial key exchange has completed successfully when function returns.
     pub async fn connect<S: Socket>(
         socket: S,
@@ -57,12 +62,13 @@ impl Transport {
         host_verifier: &Arc<dyn HostVerifier>,
         host_name: &str,
         host_port: u16,
+        host_addr: Option<IpAddr>,
         service: &str,
     ) -> Result<Self, TransportError> {
         let mut trx = Transceiver::new(config, socket);
         trx.tx_id(&config.identification).await?;
         let id = trx.rx_id(true).await?;
-        let kex = ClientKex::new(config, host_verifier, host_name, host_port, id);
+        let kex = ClientKex::new(config, host_verifier, host_name, host_port, host_addr, id);
         let mut t = Self::new(config, trx, kex);
         t.send(&MsgServiceRequest(service)).await?;
         t.flush().await?;
@@ -333,6 +339,24 @@ impl Transport {
             .expect("called before first kex complete")
     }
 
+    /// Return the server's host key and its signature of the session id.
+    ///
+    /// This proves the server's identity to third parties (like an agent restricting keys to
+    /// destinations). It is only available on the client side.
+    pub fn host_key(&self) -> Option<&(Identity, Signature)> {
+        self.kex.host_key()
+    }
+
+    /// Return the host verifier with the host name and port (client side only).
+    pub(crate) fn host_verifier(&self) -> Option<(&Arc<dyn HostVerifier>, &str, u16)> {
+        self.kex.host_verifier()
+    }
+
+    /// Return the agent holding the host keys (server side only).
+    pub(crate) fn host_key_agent(&self) -> Option<&Arc<dyn AuthAgent>> {
+        self.kex.host_key_agent()
+    }
+
     // ---------------------------------------------------------------------------------------------
     //  PRIVATE METHODS
     // ---------------------------------------------------------------------------------------------
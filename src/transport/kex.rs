@@ -55,6 +55,23 @@ pub trait Kex: std::fmt::Debug + Send {
     /// Will raise an error if called before the first key exchange has been completed.
     fn session_id(&self) -> Option<&Secret>;
 
+    /// Get the server's host key and its signature of the session id (client side only).
+    ///
+    /// Returns `None` before the first key exchange has been completed.
+    fn host_key(&self) -> Option<&(Identity, Signature)> {
+        None
+    }
+
+    /// Get the host verifier with the host name and port it verifies (client side only).
+    fn host_verifier(&self) -> Option<(&Arc<dyn HostVerifier>, &str, u16)> {
+        None
+    }
+
+    /// Get the agent holding the host keys (server side only).
+    fn host_key_agent(&self) -> Option<&Arc<dyn AuthAgent>> {
+        None
+    }
+
     /// Poll internal tasks and get a mutable reference on the outgoing messages queue.
     ///
     /// The messages in the queue shall be send to the peer in strict order and be
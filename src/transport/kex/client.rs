@@ -1,5 +1,6 @@
 use super::super::*;
 use crate::host::HostVerificationError;
+use crate::identity::{Identity, Signature};
 use crate::transport::keys::KeyAlgorithm;
 use crate::util::BoxFuture;
 use std::collections::VecDeque;
@@ -21,12 +22,16 @@ pub struct ClientKex {
     host_name: String,
     /// Remote port
     host_port: u16,
+    /// Remote address (if known)
+    host_addr: Option<IpAddr>,
     /// Remote identification string
     host_id: Identification,
     /// Host identity verification task
     verify: Option<BoxFuture<Result<(), HostVerificationError>>>,
     /// Session id (only after after initial kex, constant afterwards)
     session_id: Option<Secret>,
+    /// Host key and its signature of the session id (only after initial kex)
+    host_key: Option<(Identity, Signature)>,
     /// Mutable state (when kex in progress)
     state: State,
     /// Output buffer
@@ -39,6 +44,7 @@ impl ClientKex {
         host_verifier: &Arc<dyn HostVerifier>,
         host_name: &str,
         host_port: u16,
+        host_addr: Option<IpAddr>,
         host_id: Identification<String>,
     ) -> Box<dyn Kex> {
         let mut self_ = Self {
@@ -46,9 +52,11 @@ impl ClientKex {
             host_verifier: host_verifier.clone(),
             host_name: host_name.into(),
             host_port,
+            host_addr,
             host_id,
             verify: None,
             session_id: None,
+            host_key: None,
             state: State::Idle,
             output: VecDeque::new(),
         };
@@ -130,6 +138,9 @@ impl Kex for ClientKex {
                 // Verify the host key signature
                 msg.signature.verify(&msg.host_key, h.as_ref())?;
                 // The session id is only computed during first kex and constant afterwards
+                if self.session_id.is_none() {
+                    self.host_key = Some((msg.host_key.clone(), msg.signature.clone()));
+                }
                 let sid = self.session_id.get_or_insert_with(|| h.clone());
                 let alg = KeyAlgorithm::Sha256;
                 let kis = &x.init_server;
@@ -138,7 +149,8 @@ impl Kex for ClientKex {
                 let hn = &self.host_name;
                 let hp = self.host_port;
                 let hk = &msg.host_key;
-                self.verify = Some(self.host_verifier.verify(hn, hp, hk));
+                log::debug!("Server host key: {} {}", hk.algorithm(), hk.fingerprint());
+                self.verify = Some(self.host_verifier.verify(hn, hp, self.host_addr, hk));
                 self.output.push_back(KexMessage::NewKeys(Box::new(c2s)));
                 self.state = State::NewKeys(s2c);
                 Ok(())
@@ -158,6 +170,14 @@ impl Kex for ClientKex {
         self.session_id.as_ref()
     }
 
+    fn host_key(&self) -> Option<&(Identity, Signature)> {
+        self.host_key.as_ref()
+    }
+
+    fn host_verifier(&self) -> Option<(&Arc<dyn HostVerifier>, &str, u16)> {
+        Some((&self.host_verifier, &self.host_name, self.host_port))
+    }
+
     fn poll(
         &mut self,
         cx: &mut Context,
@@ -120,6 +120,10 @@ impl Kex for ServerKex {
         self.session_id.as_ref()
     }
 
+    fn host_key_agent(&self) -> Option<&Arc<dyn AuthAgent>> {
+        Some(&self.agent)
+    }
+
     fn poll(
         &mut self,
         cx: &mut Context,
@@ -1,9 +1,25 @@
+mod banner;
+mod client;
+mod config;
 mod error;
+mod hostbased;
+mod keyboard_interactive;
 mod method;
 mod msg;
+mod password;
+mod publickey;
+mod server;
 mod signature;
+mod user;
 
+pub use self::banner::*;
+pub use self::config::*;
 pub use self::error::*;
+pub use self::hostbased::*;
+pub use self::keyboard_interactive::*;
+pub use self::password::*;
+pub use self::publickey::*;
+pub use self::user::*;
 
 use self::method::*;
 use self::msg::*;
@@ -26,59 +42,14 @@ impl UserAuth {
     pub const SSH_USERAUTH: &'static str = "ssh-userauth";
     pub const SSH_CONNECTION: &'static str = "ssh-connection";
 
-    /// Request another service with user authentication.
-    pub async fn request_connection<F: FnOnce(&Connection) -> Box<dyn ConnectionHandler>>(
-        transport: Transport,
-        config: &Arc<ConnectionConfig>,
-        handle: F,
-        user: &str,
-        agent: &Arc<dyn AuthAgent>,
-    ) -> Result<Connection, UserAuthError> {
-        let mut t = transport;
-        let service = Self::SSH_CONNECTION;
-        let identities = agent.identities().await?;
-
-        for (id, comment) in identities {
-            log::debug!("Trying identity: {} ({})", comment, id.algorithm());
-            if Self::try_pubkey(&mut t, &agent, service, user, id).await? {
-                return Ok(Connection::new(config, t, handle));
-            }
-        }
-
-        Err(UserAuthError::NoMoreAuthMethods)
-    }
-
-    async fn try_pubkey(
-        transport: &mut Transport,
-        agent: &Arc<dyn AuthAgent>,
-        service: &str,
-        user: &str,
-        identity: Identity,
-    ) -> Result<bool, UserAuthError> {
-        let data = SignatureData {
-            session_id: transport.session_id(),
-            user_name: user,
-            service_name: service,
-            identity: &identity,
-        };
-        let data = SshCodec::encode(&data)?;
-        let signature = agent.signature(&identity, &data, 0).await?;
-        if signature.is_none() {
-            return Ok(false);
-        }
-        let msg = MsgUserAuthRequest::<PublicKeyMethod> {
-            user_name: user,
-            service_name: service,
-            method: PublicKeyMethod {
-                identity,
-                signature,
-            },
-        };
-        transport.send(&msg).await?;
-        transport.flush().await?;
-        Ok(transport
-            .receive::<Result<MsgSuccess, MsgFailure>>()
-            .await?
-            .is_ok())
-    }
+    /// The `none` method name.
+    pub const NONE: &'static str = NoneMethod::NAME;
+    /// The `hostbased` method name.
+    pub const HOSTBASED: &'static str = HostbasedMethod::NAME;
+    /// The `publickey` method name.
+    pub const PUBLICKEY: &'static str = PublicKeyMethod::NAME;
+    /// The `password` method name.
+    pub const PASSWORD: &'static str = PasswordMethod::NAME;
+    /// The `keyboard-interactive` method name.
+    pub const KEYBOARD_INTERACTIVE: &'static str = KeyboardInteractiveMethod::NAME;
 }
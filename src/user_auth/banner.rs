@@ -0,0 +1,20 @@
+use crate::util::BoxFuture;
+
+/// Receives banners sent by the server during authentication (client side).
+///
+/// Banners usually contain legal notices that shall be displayed before the user enters any
+/// credentials. The message is passed as received and may contain control characters, so it
+/// should be sanitized before writing it to a terminal.
+pub trait BannerHandler: std::fmt::Debug + Send + Sync + 'static {
+    /// Display the banner `message` sent by `host`.
+    ///
+    /// Authentication continues when the returned future resolves.
+    fn banner(&self, host: &str, message: &str) -> BoxFuture<()>;
+}
+
+/// The unit handler ignores all banners.
+impl BannerHandler for () {
+    fn banner(&self, _: &str, _: &str) -> BoxFuture<()> {
+        Box::pin(async {})
+    }
+}
@@ -0,0 +1,446 @@
+use super::*;
+
+/// The client's knowledge about the authentication progress (as announced by the server).
+#[derive(Debug, Default)]
+struct Progress {
+    /// The host name passed to the banner handler.
+    host: String,
+    /// The receiver of banners.
+    banner_handler: Option<Arc<dyn BannerHandler>>,
+    /// The methods that may continue (`None` until the server tells otherwise).
+    methods: Option<Vec<String>>,
+    /// The number of methods that succeeded without completing the authentication.
+    partial_successes: usize,
+    /// The methods that have been tried (in order).
+    tried: Vec<&'static str>,
+}
+
+impl Progress {
+    fn new(auth: &UserAuthConfig, host: &str) -> Self {
+        Self {
+            host: host.into(),
+            banner_handler: auth.banner_handler.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, failure: MsgFailure) {
+        if failure.partial_success {
+            log::debug!("Partial success, continue with: {:?}", failure.methods);
+            self.partial_successes += 1;
+        }
+        self.methods = Some(failure.methods);
+    }
+
+    /// Record an attempt with the given method.
+    fn attempt(&mut self, method: &'static str) {
+        if !self.tried.contains(&method) {
+            self.tried.push(method);
+        }
+    }
+
+    /// Whether the given method may continue.
+    ///
+    /// All methods are considered allowed as long as the server did not tell otherwise.
+    fn is_allowed(&self, method: &str) -> bool {
+        match self.methods {
+            None => true,
+            Some(ref methods) => methods.iter().any(|m| m == method),
+        }
+    }
+}
+
+impl UserAuth {
+    /// Request another service with user authentication.
+    ///
+    /// The client starts with the `none` method in order to learn which methods the server
+    /// accepts. The [methods](UserAuthConfig::methods) are then tried in order of preference:
+    ///
+    ///   - `hostbased` with the local [host_keys](UserAuthConfig::host_keys) if the
+    ///     [local_host_name](UserAuthConfig::local_host_name) is set.
+    ///   - `publickey` with the in-process [keys](UserAuthConfig::keys) followed by the
+    ///     identities offered by the agent (unless
+    ///     [identities_only](UserAuthConfig::identities_only) is set).
+    ///   - `password` if a [PasswordPrompt] is configured.
+    ///   - `keyboard-interactive` if a [KeyboardInteractivePrompt] is configured.
+    ///
+    /// Methods the server does not accept are skipped. When the server reports partial success
+    /// (multi-factor authentication) the methods are walked again until no more progress is
+    /// made.
+    pub async fn request_connection<F: FnOnce(&Connection) -> Box<dyn ConnectionHandler>>(
+        transport: Transport,
+        config: &Arc<ConnectionConfig>,
+        handle: F,
+        auth: &Arc<UserAuthConfig>,
+        user: &str,
+        host: &str,
+        agent: &Arc<dyn AuthAgent>,
+    ) -> Result<Connection, UserAuthError> {
+        let mut t = transport;
+        let mut p = Progress::new(auth, host);
+        let service = Self::SSH_CONNECTION;
+
+        if Self::try_none(&mut t, service, user, &mut p).await? {
+            return Ok(Connection::new(config, t, handle));
+        }
+
+        'outer: loop {
+            let partial_successes = p.partial_successes;
+
+            for method in &auth.methods {
+                if !p.is_allowed(method) {
+                    continue;
+                }
+                let t_ = &mut t;
+                let p_ = &mut p;
+                let success = match *method {
+                    Self::HOSTBASED => match (&auth.host_keys, &auth.local_host_name) {
+                        (Some(keys), Some(name)) => {
+                            let local_user = auth.local_user_name.as_deref().unwrap_or(user);
+                            let local = (name.as_str(), local_user);
+                            Self::try_hostbased(t_, keys, local, service, user, p_).await?
+                        }
+                        _ => continue,
+                    },
+                    Self::PUBLICKEY => {
+                        Self::try_pubkeys(t_, auth, agent, service, user, p_).await?
+                    }
+                    Self::PASSWORD => match auth.password_prompt {
+                        None => continue,
+                        Some(ref prompt) => {
+                            let n = auth.password_attempts;
+                            Self::try_password(t_, prompt, n, service, user, host, p_).await?
+                        }
+                    },
+                    Self::KEYBOARD_INTERACTIVE => match auth.keyboard_interactive_prompt {
+                        None => continue,
+                        Some(ref prompt) => {
+                            Self::try_keyboard_interactive(t_, prompt, service, user, host, p_)
+                                .await?
+                        }
+                    },
+                    method => {
+                        log::warn!("Unsupported auth method: {}", method);
+                        continue;
+                    }
+                };
+                if success {
+                    return Ok(Connection::new(config, t, handle));
+                }
+                // Start over with the most preferred method after each partial success.
+                if p.partial_successes != partial_successes {
+                    continue 'outer;
+                }
+            }
+
+            break;
+        }
+
+        Err(UserAuthError::NoMoreAuthMethods(p.tried))
+    }
+
+    async fn try_none(
+        transport: &mut Transport,
+        service: &str,
+        user: &str,
+        progress: &mut Progress,
+    ) -> Result<bool, UserAuthError> {
+        let msg = MsgUserAuthRequest {
+            user_name: user,
+            service_name: service,
+            method: NoneMethod,
+        };
+        transport.send(&msg).await?;
+        transport.flush().await?;
+        match Self::receive::<Result<MsgSuccess, MsgFailure>>(transport, progress).await? {
+            Ok(_) => Ok(true),
+            Err(failure) => {
+                log::debug!("Methods that may continue: {:?}", failure.methods);
+                progress.update(failure);
+                Ok(false)
+            }
+        }
+    }
+
+    async fn try_pubkeys(
+        transport: &mut Transport,
+        auth: &Arc<UserAuthConfig>,
+        agent: &Arc<dyn AuthAgent>,
+        service: &str,
+        user: &str,
+        progress: &mut Progress,
+    ) -> Result<bool, UserAuthError> {
+        let mut candidates: Vec<(Identity, String, &Arc<dyn AuthAgent>)> = vec![];
+        if let Some(ref keys) = auth.keys {
+            for (id, comment) in keys.identities().await? {
+                candidates.push((id, comment, keys));
+            }
+        }
+        if !auth.identities_only {
+            if let Some(bind) = Self::session_bind(transport) {
+                Self::bind_agent(agent, bind).await?;
+            }
+            for (id, comment) in agent.identities().await? {
+                if !candidates.iter().any(|(x, _, _)| x == &id) {
+                    candidates.push((id, comment, agent));
+                }
+            }
+        }
+        let partial_successes = progress.partial_successes;
+        for (id, comment, signer) in candidates {
+            log::debug!("Trying identity: {} ({})", comment, id.algorithm());
+            if Self::try_pubkey(transport, signer, service, user, id, progress).await? {
+                return Ok(true);
+            }
+            if progress.partial_successes != partial_successes
+                || !progress.is_allowed(Self::PUBLICKEY)
+            {
+                break;
+            }
+        }
+        Ok(false)
+    }
+
+    /// Bind the agent to the session with the server's host key (`session-bind@openssh.com`).
+    ///
+    /// This allows the agent to restrict keys to destinations. Agents that do not support the
+    /// extension are used anyway. Note that [LocalAgent] shares one connection to `ssh-agent`
+    /// between all sessions and does not forward the binding, so this is a no-op with it.
+    async fn bind_agent(
+        agent: &Arc<dyn AuthAgent>,
+        bind: SessionBind,
+    ) -> Result<(), UserAuthError> {
+        let data = SshCodec::encode(&bind)?;
+        if agent.extension(SessionBind::NAME, &data).await?.is_none() {
+            log::debug!("Agent did not bind to the session");
+        }
+        Ok(())
+    }
+
+    /// The session binding for agents (client side only).
+    fn session_bind(transport: &Transport) -> Option<SessionBind> {
+        let (host_key, signature) = transport.host_key()?;
+        Some(SessionBind {
+            host_key: host_key.clone(),
+            session_id: transport.session_id().as_ref().into(),
+            signature: signature.clone(),
+            forwarding: false,
+        })
+    }
+
+    /// Try a single identity.
+    ///
+    /// The identity is offered without signature first and only signed after the server
+    /// answered with `SSH_MSG_USERAUTH_PK_OK`. This spares the user from confirming keys (or
+    /// touching hardware tokens) the server would reject anyway.
+    async fn try_pubkey(
+        transport: &mut Transport,
+        agent: &Arc<dyn AuthAgent>,
+        service: &str,
+        user: &str,
+        identity: Identity,
+        progress: &mut Progress,
+    ) -> Result<bool, UserAuthError> {
+        let mut msg = MsgUserAuthRequest::<PublicKeyMethod> {
+            user_name: user,
+            service_name: service,
+            method: PublicKeyMethod {
+                identity,
+                signature: None,
+            },
+        };
+        progress.attempt(Self::PUBLICKEY);
+        transport.send(&msg).await?;
+        transport.flush().await?;
+        match Self::receive::<Result<MsgPkOk, MsgFailure>>(transport, progress).await? {
+            Ok(ok) if ok.identity == msg.method.identity => {
+                log::debug!("Rx USERAUTH_PK_OK: {}", ok.algorithm)
+            }
+            Ok(_) => return Err(TransportError::InvalidState.into()),
+            Err(failure) => {
+                progress.update(failure);
+                return Ok(false);
+            }
+        }
+        let identity = &msg.method.identity;
+        let data = SignatureData {
+            session_id: transport.session_id(),
+            user_name: user,
+            service_name: service,
+            identity,
+        };
+        let data = SshCodec::encode(&data)?;
+        msg.method.signature = agent.signature(identity, &data, 0).await?;
+        if msg.method.signature.is_none() {
+            return Ok(false);
+        }
+        transport.send(&msg).await?;
+        transport.flush().await?;
+        match Self::receive::<Result<MsgSuccess, MsgFailure>>(transport, progress).await? {
+            Ok(_) => Ok(true),
+            Err(failure) => {
+                progress.update(failure);
+                Ok(false)
+            }
+        }
+    }
+
+    async fn try_hostbased(
+        transport: &mut Transport,
+        host_keys: &Arc<dyn AuthAgent>,
+        (local_host, local_user): (&str, &str),
+        service: &str,
+        user: &str,
+        progress: &mut Progress,
+    ) -> Result<bool, UserAuthError> {
+        let partial_successes = progress.partial_successes;
+        for (identity, comment) in host_keys.identities().await? {
+            log::debug!("Trying host key: {} ({})", comment, identity.algorithm());
+            let data = HostbasedSignatureData {
+                session_id: transport.session_id(),
+                user_name: user,
+                service_name: service,
+                identity: &identity,
+                client_host_name: local_host,
+                client_user_name: local_user,
+            };
+            let data = SshCodec::encode(&data)?;
+            let signature = match host_keys.signature(&identity, &data, 0).await? {
+                Some(signature) => signature,
+                None => continue,
+            };
+            let msg = MsgUserAuthRequest {
+                user_name: user,
+                service_name: service,
+                method: HostbasedMethod {
+                    identity,
+                    host_name: local_host.into(),
+                    user_name: local_user.into(),
+                    signature,
+                },
+            };
+            progress.attempt(Self::HOSTBASED);
+            transport.send(&msg).await?;
+            transport.flush().await?;
+            match Self::receive::<Result<MsgSuccess, MsgFailure>>(transport, progress).await? {
+                Ok(_) => return Ok(true),
+                Err(failure) => progress.update(failure),
+            }
+            if progress.partial_successes != partial_successes
+                || !progress.is_allowed(Self::HOSTBASED)
+            {
+                break;
+            }
+        }
+        Ok(false)
+    }
+
+    async fn try_password(
+        transport: &mut Transport,
+        prompt: &Arc<dyn PasswordPrompt>,
+        attempts: u32,
+        service: &str,
+        user: &str,
+        host: &str,
+        progress: &mut Progress,
+    ) -> Result<bool, UserAuthError> {
+        for _ in 0..attempts {
+            let password = match prompt.password(user, host).await {
+                Some(password) => password,
+                None => return Ok(false),
+            };
+            let mut msg = MsgUserAuthRequest {
+                user_name: user,
+                service_name: service,
+                method: PasswordMethod::new(password),
+            };
+            progress.attempt(Self::PASSWORD);
+            loop {
+                transport.send(&msg).await?;
+                transport.flush().await?;
+                type Response = Result<MsgSuccess, Result<MsgFailure, MsgPasswdChangeReq>>;
+                match Self::receive::<Response>(transport, progress).await? {
+                    Ok(_) => return Ok(true),
+                    Err(Ok(failure)) => {
+                        let partial_success = failure.partial_success;
+                        progress.update(failure);
+                        if partial_success {
+                            return Ok(false);
+                        }
+                        break;
+                    }
+                    Err(Err(req)) => {
+                        log::debug!("Rx USERAUTH_PASSWD_CHANGEREQ: {}", req.prompt);
+                        let new = match prompt.new_password(user, host, &req.prompt).await {
+                            Some(new) => new,
+                            None => return Ok(false),
+                        };
+                        let old = msg.method.password.clone();
+                        msg.method = PasswordMethod::change(old, new);
+                    }
+                }
+            }
+            if !progress.is_allowed(PasswordMethod::NAME) {
+                break;
+            }
+        }
+        Ok(false)
+    }
+
+    async fn try_keyboard_interactive(
+        transport: &mut Transport,
+        prompt: &Arc<dyn KeyboardInteractivePrompt>,
+        service: &str,
+        user: &str,
+        host: &str,
+        progress: &mut Progress,
+    ) -> Result<bool, UserAuthError> {
+        let msg = MsgUserAuthRequest {
+            user_name: user,
+            service_name: service,
+            method: KeyboardInteractiveMethod::default(),
+        };
+        progress.attempt(Self::KEYBOARD_INTERACTIVE);
+        transport.send(&msg).await?;
+        transport.flush().await?;
+        loop {
+            type Response = Result<MsgSuccess, Result<MsgFailure, MsgInfoRequest>>;
+            match Self::receive::<Response>(transport, progress).await? {
+                Ok(_) => return Ok(true),
+                Err(Ok(failure)) => {
+                    progress.update(failure);
+                    return Ok(false);
+                }
+                Err(Err(req)) => {
+                    log::debug!("Rx USERAUTH_INFO_REQUEST: {} prompts", req.prompts.len());
+                    let challenge = Challenge::from(req);
+                    let responses = match prompt.respond(user, host, &challenge).await {
+                        Some(responses) => responses,
+                        None => return Ok(false),
+                    };
+                    let msg = MsgInfoResponse { responses };
+                    transport.send(&msg).await?;
+                    transport.flush().await?;
+                }
+            }
+        }
+    }
+
+    /// Receive the next message and pass any banners to the handler on the way.
+    async fn receive<M: SshDecode>(
+        transport: &mut Transport,
+        progress: &mut Progress,
+    ) -> Result<M, UserAuthError> {
+        loop {
+            match transport.receive::<Result<MsgBanner, M>>().await? {
+                Ok(banner) => {
+                    log::debug!("Rx USERAUTH_BANNER: {:?}", banner.message);
+                    if let Some(ref handler) = progress.banner_handler {
+                        handler.banner(&progress.host, &banner.message).await
+                    }
+                }
+                Err(msg) => return Ok(msg),
+            }
+        }
+    }
+}
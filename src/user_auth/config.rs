@@ -0,0 +1,186 @@
+use super::banner::*;
+use super::hostbased::*;
+use super::keyboard_interactive::*;
+use super::password::*;
+use super::publickey::*;
+use super::user::*;
+use super::UserAuth;
+use crate::agent::AuthAgent;
+#[cfg(doc)]
+use crate::agent::LocalAgent;
+use crate::identity::Krl;
+use std::sync::Arc;
+
+/// The user authentication configuration (shared by client and server).
+///
+/// Fields marked as _client_ or _server_ are only relevant for the respective role.
+#[derive(Clone, Debug)]
+pub struct UserAuthConfig {
+    /// The methods to try in order of preference (client).
+    ///
+    /// Methods not accepted by the server are skipped. After a partial success the list is
+    /// walked again from the start. This is the equivalent of OpenSSH's
+    /// `PreferredAuthentications`.
+    ///
+    /// Defaults to `hostbased`, `publickey`, `keyboard-interactive` and `password`.
+    pub methods: Vec<&'static str>,
+    /// The in-process keys for the `publickey` method (client).
+    ///
+    /// The keys are offered before those of the agent. Any [AuthAgent] implementation that
+    /// holds private keys in memory may serve as key source.
+    ///
+    /// Defaults to `None`.
+    pub keys: Option<Arc<dyn AuthAgent>>,
+    /// Only offer the in-process [keys](Self::keys) and ignore the agent's identities (client).
+    ///
+    /// This is the equivalent of OpenSSH's `IdentitiesOnly`. Unless set, the client binds the
+    /// agent to the session (`session-bind@openssh.com`) before querying its identities. This
+    /// is a no-op with [LocalAgent] which does not pass bindings on to `ssh-agent`.
+    ///
+    /// Defaults to `false`.
+    pub identities_only: bool,
+    /// The local host keys for the `hostbased` method (client).
+    ///
+    /// The method is only tried if the [local_host_name](Self::local_host_name) is set as well.
+    ///
+    /// Defaults to `None` (method disabled).
+    pub host_keys: Option<Arc<dyn AuthAgent>>,
+    /// The local host name (FQDN) for the `hostbased` method (client).
+    ///
+    /// Defaults to `None` (method disabled).
+    pub local_host_name: Option<String>,
+    /// The local user name for the `hostbased` method (client).
+    ///
+    /// Defaults to `None` (the remote user name is used).
+    pub local_user_name: Option<String>,
+    /// The credential callback for the `password` method (client).
+    ///
+    /// Defaults to `None` (method disabled).
+    pub password_prompt: Option<Arc<dyn PasswordPrompt>>,
+    /// The maximum number of passwords to request from the prompt (client).
+    ///
+    /// Defaults to `3`.
+    pub password_attempts: u32,
+    /// The challenge callback for the `keyboard-interactive` method (client).
+    ///
+    /// Defaults to `None` (method disabled).
+    pub keyboard_interactive_prompt: Option<Arc<dyn KeyboardInteractivePrompt>>,
+    /// The receiver of banners sent by the server (client).
+    ///
+    /// Defaults to `None` (banners are logged and dropped).
+    pub banner_handler: Option<Arc<dyn BannerHandler>>,
+    /// The banner to send to the client before authentication (server).
+    ///
+    /// Defaults to `None`.
+    pub banner: Option<String>,
+    /// The verifier deciding whether a user may log in at all (server).
+    ///
+    /// It is consulted once per user name. Requests of users not allowed fail after the
+    /// method's verifier has run.
+    ///
+    /// Defaults to `None` (all users).
+    pub user_verifier: Option<Arc<dyn UserVerifier>>,
+    /// The verifier for the `publickey` method (server).
+    ///
+    /// Defaults to `None` (method disabled).
+    pub public_key_verifier: Option<Arc<dyn PublicKeyVerifier>>,
+    /// The verifier for the `hostbased` method (server).
+    ///
+    /// See [HostsEquiv] for a `known_hosts` and `hosts.equiv` based implementation.
+    ///
+    /// Defaults to `None` (method disabled).
+    pub hostbased_verifier: Option<Arc<dyn HostbasedVerifier>>,
+    /// The revoked keys and certificates for `publickey` and `hostbased` (server).
+    ///
+    /// This is the equivalent of OpenSSH's `RevokedKeys`. Revoked identities are rejected
+    /// before consulting the method's verifier.
+    ///
+    /// Defaults to `None`.
+    pub revoked_keys: Option<Arc<Krl>>,
+    /// The verifier for the `password` method (server).
+    ///
+    /// Defaults to `None` (method disabled).
+    pub password_verifier: Option<Arc<dyn PasswordVerifier>>,
+    /// The verifier for the `keyboard-interactive` method (server).
+    ///
+    /// Defaults to `None` (method disabled).
+    pub keyboard_interactive_verifier: Option<Arc<dyn KeyboardInteractiveVerifier>>,
+    /// Lists of methods that must all succeed (in order) for successful authentication (server).
+    ///
+    /// This is the equivalent of OpenSSH's `AuthenticationMethods`: Each method of a list that
+    /// succeeds is answered with partial success until one list has been completed, e.g.
+    /// `vec![vec!["password", "keyboard-interactive"]]` for two-factor authentication.
+    ///
+    /// Defaults to empty (any single configured method suffices).
+    pub required_methods: Vec<Vec<&'static str>>,
+    /// The maximum number of failed authentication attempts per connection (server).
+    ///
+    /// The client gets disconnected when exceeding the limit. Queries with method `none` do not
+    /// count as attempts.
+    ///
+    /// Defaults to `6`.
+    pub max_attempts: u32,
+}
+
+impl Default for UserAuthConfig {
+    fn default() -> Self {
+        Self {
+            methods: vec![
+                UserAuth::HOSTBASED,
+                UserAuth::PUBLICKEY,
+                UserAuth::KEYBOARD_INTERACTIVE,
+                UserAuth::PASSWORD,
+            ],
+            keys: None,
+            identities_only: false,
+            host_keys: None,
+            local_host_name: None,
+            local_user_name: None,
+            password_prompt: None,
+            password_attempts: 3,
+            keyboard_interactive_prompt: None,
+            banner_handler: None,
+            banner: None,
+            user_verifier: None,
+            public_key_verifier: None,
+            hostbased_verifier: None,
+            revoked_keys: None,
+            password_verifier: None,
+            keyboard_interactive_verifier: None,
+            required_methods: vec![],
+            max_attempts: 6,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_01() {
+        let c = UserAuthConfig::default();
+        assert_eq!(
+            c.methods,
+            ["hostbased", "publickey", "keyboard-interactive", "password"]
+        );
+        assert!(c.keys.is_none());
+        assert!(!c.identities_only);
+        assert!(c.host_keys.is_none());
+        assert!(c.local_host_name.is_none());
+        assert!(c.local_user_name.is_none());
+        assert!(c.password_prompt.is_none());
+        assert_eq!(c.password_attempts, 3);
+        assert!(c.keyboard_interactive_prompt.is_none());
+        assert!(c.banner_handler.is_none());
+        assert!(c.banner.is_none());
+        assert!(c.user_verifier.is_none());
+        assert!(c.public_key_verifier.is_none());
+        assert!(c.hostbased_verifier.is_none());
+        assert!(c.revoked_keys.is_none());
+        assert!(c.password_verifier.is_none());
+        assert!(c.keyboard_interactive_verifier.is_none());
+        assert!(c.required_methods.is_empty());
+        assert_eq!(c.max_attempts, 6);
+    }
+}
@@ -8,7 +8,9 @@ pub enum UserAuthError {
     CodecError(SshCodecError),
     AuthAgentError(AuthAgentError),
     TransportError(TransportError),
-    NoMoreAuthMethods,
+    /// All methods failed or were rejected (contains the methods that have been tried).
+    NoMoreAuthMethods(Vec<&'static str>),
+    MaxAttemptsExceeded,
 }
 
 impl From<SshCodecError> for UserAuthError {
@@ -37,7 +39,13 @@ impl std::fmt::Display for UserAuthError {
             Self::CodecError(e) => write!(f, "Codec error: {}", e),
             Self::AuthAgentError(e) => write!(f, "Auth agent: {}", e),
             Self::TransportError(e) => write!(f, "Transport: {}", e),
-            Self::NoMoreAuthMethods => write!(f, "No more auth methods"),
+            Self::NoMoreAuthMethods(tried) if tried.is_empty() => {
+                write!(f, "No more auth methods (none tried)")
+            }
+            Self::NoMoreAuthMethods(tried) => {
+                write!(f, "No more auth methods (tried {})", tried.join(", "))
+            }
+            Self::MaxAttemptsExceeded => write!(f, "Max auth attempts exceeded"),
         }
     }
 }
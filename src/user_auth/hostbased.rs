@@ -0,0 +1,184 @@
+use crate::host::HostVerifier;
+use crate::identity::Identity;
+use crate::util::BoxFuture;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A verifier for the `hostbased` method (server side).
+///
+/// Signatures are checked by the caller. The verifier only decides whether the client host key
+/// belongs to the claimed client host and whether the client user may log in.
+pub trait HostbasedVerifier: std::fmt::Debug + Send + Sync + 'static {
+    /// Whether `client_user` at `client_host` (presenting the host key `identity`) may
+    /// authenticate as `user`.
+    fn is_authorized(
+        &self,
+        user: &str,
+        client_host: &str,
+        client_user: &str,
+        identity: &Identity,
+    ) -> BoxFuture<bool>;
+}
+
+/// The unit verifier rejects all hosts.
+impl HostbasedVerifier for () {
+    fn is_authorized(&self, _: &str, _: &str, _: &str, _: &Identity) -> BoxFuture<bool> {
+        Box::pin(async { false })
+    }
+}
+
+/// A `hosts.equiv` style verifier for the `hostbased` method.
+///
+/// The client host key is looked up with the given [HostVerifier] (usually
+/// [KnownHosts](crate::host::KnownHosts) with the system wide `ssh_known_hosts`) and the host
+/// and user are checked against an allow list with lines of the form `host [user]`:
+///
+///   - `host` allows the user with the same name on `host`.
+///   - `host user` allows `user` on `host` to log in as any user.
+///   - `+` matches any host or user, a leading `-` denies the host or user.
+///
+/// The first matching line decides. Netgroups (`@group`) are not supported and ignored. Just
+/// like OpenSSH does for `hosts.equiv`, `root` is never authorized. The client host name is
+/// taken from the request as is (the equivalent of `HostbasedUsesNameFromPacketOnly`).
+#[derive(Clone, Debug)]
+pub struct HostsEquiv {
+    known_hosts: Arc<dyn HostVerifier>,
+    lines: Vec<(String, Option<String>)>,
+}
+
+impl HostsEquiv {
+    /// Create a verifier with an empty allow list.
+    pub fn new(known_hosts: Arc<dyn HostVerifier>) -> Self {
+        Self {
+            known_hosts,
+            lines: vec![],
+        }
+    }
+
+    /// Create a verifier with the allow list parsed from `text`.
+    pub fn parse(known_hosts: Arc<dyn HostVerifier>, text: &str) -> Self {
+        let mut lines = vec![];
+        for line in text.lines() {
+            let mut fields = line.split_whitespace();
+            let host = match fields.next() {
+                None => continue,
+                Some(host) if host.starts_with('#') => continue,
+                Some(host) if host.trim_start_matches(['+', '-']).starts_with('@') => {
+                    log::warn!("Netgroups are not supported: {}", host);
+                    continue;
+                }
+                Some(host) => host.into(),
+            };
+            let user = fields.next().map(Into::into);
+            lines.push((host, user));
+        }
+        Self { known_hosts, lines }
+    }
+
+    /// Create a verifier with the allow list read from a file.
+    pub async fn load<P: AsRef<Path>>(
+        known_hosts: Arc<dyn HostVerifier>,
+        path: P,
+    ) -> std::io::Result<Self> {
+        let text = tokio::fs::read_to_string(path).await?;
+        Ok(Self::parse(known_hosts, &text))
+    }
+
+    /// Whether `client_user` at `client_host` may log in as `user` according to the allow list.
+    pub fn is_allowed(&self, user: &str, client_host: &str, client_user: &str) -> bool {
+        if user == "root" {
+            return false;
+        }
+        let client_host = client_host.strip_suffix('.').unwrap_or(client_host);
+        for (host, u) in &self.lines {
+            let (host_negated, host) = match host.strip_prefix('-') {
+                Some(host) => (true, host),
+                None => (false, host.as_str()),
+            };
+            if host != "+" && !host.eq_ignore_ascii_case(client_host) {
+                continue;
+            }
+            let user_negated = match u {
+                None if client_user == user => false,
+                None => continue,
+                Some(u) => match u.strip_prefix('-') {
+                    Some(u) if u == client_user || u == "+" => true,
+                    Some(_) => continue,
+                    None if u == client_user || u == "+" => false,
+                    None => continue,
+                },
+            };
+            return !host_negated && !user_negated;
+        }
+        false
+    }
+}
+
+impl HostbasedVerifier for HostsEquiv {
+    fn is_authorized(
+        &self,
+        user: &str,
+        client_host: &str,
+        client_user: &str,
+        identity: &Identity,
+    ) -> BoxFuture<bool> {
+        let allowed = self.is_allowed(user, client_host, client_user);
+        let known_hosts = self.known_hosts.clone();
+        let client_host = client_host.strip_suffix('.').unwrap_or(client_host);
+        let client_host = String::from(client_host);
+        let identity = identity.clone();
+        Box::pin(async move {
+            allowed
+                && known_hosts
+                    .verify(&client_host, 22, None, &identity)
+                    .await
+                    .is_ok()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn equiv(text: &str) -> HostsEquiv {
+        HostsEquiv::parse(Arc::new(crate::host::KnownHosts::default()), text)
+    }
+
+    #[test]
+    fn test_is_allowed_same_user() {
+        let e = equiv("node1.cluster\n");
+        assert!(e.is_allowed("alice", "node1.cluster", "alice"));
+        assert!(e.is_allowed("alice", "NODE1.cluster.", "alice"));
+        assert!(!e.is_allowed("alice", "node1.cluster", "bob"));
+        assert!(!e.is_allowed("alice", "node2.cluster", "alice"));
+    }
+
+    #[test]
+    fn test_is_allowed_other_user() {
+        let e = equiv("node1.cluster bob\n");
+        assert!(e.is_allowed("alice", "node1.cluster", "bob"));
+        assert!(!e.is_allowed("alice", "node1.cluster", "alice"));
+    }
+
+    #[test]
+    fn test_is_allowed_wildcards() {
+        let e = equiv("-node3.cluster\n+ -mallory\n+ +\n");
+        assert!(e.is_allowed("alice", "node1.cluster", "bob"));
+        assert!(!e.is_allowed("alice", "node3.cluster", "alice"));
+        assert!(!e.is_allowed("alice", "node1.cluster", "mallory"));
+    }
+
+    #[test]
+    fn test_is_allowed_root() {
+        let e = equiv("+ +\n");
+        assert!(!e.is_allowed("root", "node1.cluster", "root"));
+    }
+
+    #[test]
+    fn test_is_allowed_comments_and_netgroups() {
+        let e = equiv("# comment\n\n+@trusted\n");
+        assert!(e.lines.is_empty());
+        assert!(!e.is_allowed("alice", "node1.cluster", "alice"));
+    }
+}
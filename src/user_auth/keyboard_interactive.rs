@@ -0,0 +1,111 @@
+use crate::util::codec::*;
+use crate::util::BoxFuture;
+
+/// A round of questions sent by the server with the `keyboard-interactive` method (RFC 4256).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Challenge {
+    /// A short name (like a window title).
+    pub name: String,
+    /// Instructions to be displayed before the prompts.
+    pub instruction: String,
+    /// The prompts to answer (may be empty).
+    pub prompts: Vec<ChallengePrompt>,
+}
+
+/// A single prompt of a [Challenge].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChallengePrompt {
+    pub prompt: String,
+    /// Whether the response may be echoed (`false` for passwords, OTPs etc).
+    pub echo: bool,
+}
+
+impl ChallengePrompt {
+    pub fn new(prompt: &str, echo: bool) -> Self {
+        Self {
+            prompt: prompt.into(),
+            echo,
+        }
+    }
+}
+
+impl SshEncode for ChallengePrompt {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_str_framed(&self.prompt)?;
+        e.push_bool(self.echo)
+    }
+}
+
+impl SshDecode for ChallengePrompt {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        let prompt = d.take_str_framed()?.into();
+        let echo = d.take_bool()?;
+        Some(Self { prompt, echo })
+    }
+}
+
+/// Answers challenges of the `keyboard-interactive` method (client side).
+pub trait KeyboardInteractivePrompt: std::fmt::Debug + Send + Sync + 'static {
+    /// Answer all prompts of the challenge (in order).
+    ///
+    /// The server might send several challenges in a row and challenges without prompts
+    /// (which shall just be displayed). Returns `None` in order to cancel the method.
+    fn respond(
+        &self,
+        user: &str,
+        host: &str,
+        challenge: &Challenge,
+    ) -> BoxFuture<Option<Vec<String>>>;
+}
+
+/// Poses challenges for the `keyboard-interactive` method (server side).
+///
+/// The verifier is stateless with respect to the connection: Each call to
+/// [verify](Self::verify) gets the challenge that has been answered.
+pub trait KeyboardInteractiveVerifier: std::fmt::Debug + Send + Sync + 'static {
+    /// Create the first challenge for `user`.
+    ///
+    /// The `submethods` are a hint by the client (comma separated, usually empty). Returns `None`
+    /// if the method is not available for this user.
+    fn challenge(&self, user: &str, submethods: &str) -> BoxFuture<Option<Challenge>>;
+
+    /// Verify the responses to a challenge.
+    fn verify(
+        &self,
+        user: &str,
+        challenge: &Challenge,
+        responses: &[String],
+    ) -> BoxFuture<ChallengeVerification>;
+}
+
+/// The outcome of a challenge verification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChallengeVerification {
+    /// All responses are correct.
+    Accepted,
+    /// At least one response is incorrect.
+    Rejected,
+    /// The responses are correct, but another challenge follows.
+    Continue(Challenge),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_encode_01() {
+        let prompt = ChallengePrompt::new("OTP: ", false);
+        assert_eq!(
+            &[0, 0, 0, 5, 79, 84, 80, 58, 32, 0][..],
+            &SshCodec::encode(&prompt).unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_prompt_decode_01() {
+        let buf: [u8; 10] = [0, 0, 0, 5, 79, 84, 80, 58, 32, 1];
+        let prompt: ChallengePrompt = SshCodec::decode(&buf[..]).unwrap();
+        assert_eq!(prompt, ChallengePrompt::new("OTP: ", true));
+    }
+}
@@ -1,6 +1,12 @@
+mod hostbased;
+mod keyboard_interactive;
+mod none;
 mod password;
 mod publickey;
 
+pub use hostbased::*;
+pub use keyboard_interactive::*;
+pub use none::*;
 pub use password::*;
 pub use publickey::*;
 
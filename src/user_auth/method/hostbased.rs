@@ -0,0 +1,67 @@
+use super::*;
+use crate::identity::*;
+
+/// The `hostbased` method as described in RFC 4252 section 9.
+#[derive(Debug)]
+pub struct HostbasedMethod {
+    /// The client's host key (or certificate).
+    pub identity: Identity,
+    /// The client's host name (FQDN).
+    pub host_name: String,
+    /// The user name on the client host.
+    pub user_name: String,
+    pub signature: Signature,
+}
+
+impl AuthMethod for HostbasedMethod {
+    const NAME: &'static str = "hostbased";
+}
+
+impl SshEncode for HostbasedMethod {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_str_framed(self.identity.algorithm())?;
+        e.push(&self.identity)?;
+        e.push_str_framed(&self.host_name)?;
+        e.push_str_framed(&self.user_name)?;
+        e.push(&self.signature)
+    }
+}
+
+impl<'a> SshDecodeRef<'a> for HostbasedMethod {
+    fn decode<D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        let _ = d.take_str_framed()?;
+        let identity = d.take()?;
+        let host_name = d.take_str_framed()?.into();
+        let user_name = d.take_str_framed()?.into();
+        let signature = d.take()?;
+        Some(Self {
+            identity,
+            host_name,
+            user_name,
+            signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_01() {
+        let buf: [u8; 54] = [
+            0, 0, 0, 3, 97, 98, 99, // algorithm
+            0, 0, 0, 7, 0, 0, 0, 3, 97, 98, 99, // identity
+            0, 0, 0, 4, 104, 111, 115, 116, // host name
+            0, 0, 0, 4, 117, 115, 101, 114, // user name
+            0, 0, 0, 16, 0, 0, 0, 3, 97, 98, 99, 0, 0, 0, 5, 1, 2, 3, 4, 5, // signature
+        ];
+        let msg: HostbasedMethod = SshCodec::decode(&buf[..]).unwrap();
+        assert_eq!(msg.identity, Identity::from(vec![0, 0, 0, 3, 97, 98, 99]));
+        assert_eq!(msg.host_name, "host");
+        assert_eq!(msg.user_name, "user");
+        assert_eq!(msg.signature.algo(), "abc");
+        assert_eq!(msg.signature.data(), &[1, 2, 3, 4, 5][..]);
+        assert_eq!(&buf[..], &SshCodec::encode(&msg).unwrap()[..]);
+    }
+}
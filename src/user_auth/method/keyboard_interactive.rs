@@ -0,0 +1,55 @@
+use super::*;
+
+/// The `keyboard-interactive` method as described in RFC 4256.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KeyboardInteractiveMethod {
+    pub language: String,
+    pub submethods: String,
+}
+
+impl AuthMethod for KeyboardInteractiveMethod {
+    const NAME: &'static str = "keyboard-interactive";
+}
+
+impl SshEncode for KeyboardInteractiveMethod {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_str_framed(&self.language)?;
+        e.push_str_framed(&self.submethods)
+    }
+}
+
+impl<'a> SshDecodeRef<'a> for KeyboardInteractiveMethod {
+    fn decode<D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        let language = d.take_str_framed()?.into();
+        let submethods = d.take_str_framed()?.into();
+        Some(Self {
+            language,
+            submethods,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_01() {
+        let msg = KeyboardInteractiveMethod {
+            language: "".into(),
+            submethods: "pam".into(),
+        };
+        assert_eq!(
+            &[0, 0, 0, 0, 0, 0, 0, 3, 112, 97, 109][..],
+            &SshCodec::encode(&msg).unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_decode_01() {
+        let buf: [u8; 11] = [0, 0, 0, 0, 0, 0, 0, 3, 112, 97, 109];
+        let msg: KeyboardInteractiveMethod = SshCodec::decode(&buf[..]).unwrap();
+        assert_eq!(msg.language, "");
+        assert_eq!(msg.submethods, "pam");
+    }
+}
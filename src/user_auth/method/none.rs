@@ -0,0 +1,39 @@
+use super::*;
+
+/// The `none` method (RFC 4252 section 5.2).
+///
+/// The client uses it to query the list of methods that may continue.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NoneMethod;
+
+impl AuthMethod for NoneMethod {
+    const NAME: &'static str = "none";
+}
+
+impl SshEncode for NoneMethod {
+    fn encode<E: SshEncoder>(&self, _: &mut E) -> Option<()> {
+        Some(())
+    }
+}
+
+impl<'a> SshDecodeRef<'a> for NoneMethod {
+    fn decode<D: SshDecoder<'a>>(_: &mut D) -> Option<Self> {
+        Some(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_01() {
+        assert_eq!(&[0u8; 0][..], &SshCodec::encode(&NoneMethod).unwrap()[..]);
+    }
+
+    #[test]
+    fn test_decode_01() {
+        let buf: [u8; 0] = [];
+        assert_eq!(NoneMethod, SshCodec::decode(&buf[..]).unwrap());
+    }
+}
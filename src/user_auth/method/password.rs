@@ -1,21 +1,77 @@
 use super::*;
+use zeroize::Zeroize;
 
-#[derive(Debug)]
-pub struct PasswordMethod(pub String);
+/// The `password` method as described in RFC 4252 section 8.
+///
+/// The request either contains the plaintext password or (in reply to
+/// `SSH_MSG_USERAUTH_PASSWD_CHANGEREQ`) the old and a new password.
+#[derive(Clone, PartialEq)]
+pub struct PasswordMethod {
+    pub password: String,
+    pub new_password: Option<String>,
+}
+
+impl PasswordMethod {
+    pub fn new(password: String) -> Self {
+        Self {
+            password,
+            new_password: None,
+        }
+    }
 
-impl<'a> AuthMethod for PasswordMethod {
+    pub fn change(password: String, new_password: String) -> Self {
+        Self {
+            password,
+            new_password: Some(new_password),
+        }
+    }
+}
+
+impl AuthMethod for PasswordMethod {
     const NAME: &'static str = "password";
 }
 
 impl SshEncode for PasswordMethod {
     fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
-        e.push_str_framed(&self.0)
+        e.push_bool(self.new_password.is_some())?;
+        e.push_str_framed(&self.password)?;
+        match self.new_password {
+            None => Some(()),
+            Some(ref x) => e.push_str_framed(x),
+        }
     }
 }
 
 impl<'a> SshDecodeRef<'a> for PasswordMethod {
     fn decode<D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
-        d.take().map(Self)
+        let change = d.take_bool()?;
+        let password = d.take_str_framed()?.into();
+        let new_password = if change {
+            Some(d.take_str_framed()?.into())
+        } else {
+            None
+        };
+        Some(Self {
+            password,
+            new_password,
+        })
+    }
+}
+
+/// Passwords must not end up in log output.
+impl std::fmt::Debug for PasswordMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.new_password {
+            None => write!(f, "PasswordMethod(***)"),
+            Some(_) => write!(f, "PasswordMethod(***, ***)"),
+        }
+    }
+}
+
+impl Drop for PasswordMethod {
+    fn drop(&mut self) {
+        self.password.zeroize();
+        self.new_password.zeroize();
     }
 }
 
@@ -25,23 +81,47 @@ mod tests {
 
     #[test]
     fn test_debug_01() {
-        let msg = PasswordMethod("abcd".into());
-        assert_eq!("PasswordMethod(\"abcd\")", format!("{:?}", msg));
+        let msg = PasswordMethod::new("abcd".into());
+        assert_eq!("PasswordMethod(***)", format!("{:?}", msg));
+    }
+
+    #[test]
+    fn test_debug_02() {
+        let msg = PasswordMethod::change("abcd".into(), "efgh".into());
+        assert_eq!("PasswordMethod(***, ***)", format!("{:?}", msg));
     }
 
     #[test]
     fn test_encode_01() {
-        let msg = PasswordMethod("abcd".into());
+        let msg = PasswordMethod::new("abcd".into());
+        assert_eq!(
+            &[0, 0, 0, 0, 4, 97, 98, 99, 100][..],
+            &SshCodec::encode(&msg).unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_encode_02() {
+        let msg = PasswordMethod::change("abcd".into(), "ef".into());
         assert_eq!(
-            &[0, 0, 0, 4, 97, 98, 99, 100][..],
+            &[1, 0, 0, 0, 4, 97, 98, 99, 100, 0, 0, 0, 2, 101, 102][..],
             &SshCodec::encode(&msg).unwrap()[..]
         );
     }
 
     #[test]
     fn test_decode_01() {
-        let buf: [u8; 8] = [0, 0, 0, 4, 97, 98, 99, 100];
+        let buf: [u8; 9] = [0, 0, 0, 0, 4, 97, 98, 99, 100];
+        let msg: PasswordMethod = SshCodec::decode(&buf[..]).unwrap();
+        assert_eq!(msg.password, "abcd");
+        assert_eq!(msg.new_password, None);
+    }
+
+    #[test]
+    fn test_decode_02() {
+        let buf: [u8; 15] = [1, 0, 0, 0, 4, 97, 98, 99, 100, 0, 0, 0, 2, 101, 102];
         let msg: PasswordMethod = SshCodec::decode(&buf[..]).unwrap();
-        assert_eq!(msg.0, "abcd");
+        assert_eq!(msg.password, "abcd");
+        assert_eq!(msg.new_password.as_deref(), Some("ef"));
     }
 }
@@ -22,3 +22,32 @@ impl SshEncode for PublicKeyMethod {
         }
     }
 }
+
+impl<'a> SshDecodeRef<'a> for PublicKeyMethod {
+    fn decode<D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        let has_signature = d.take_bool()?;
+        let _ = d.take_str_framed()?;
+        let identity = d.take()?;
+        let signature = if has_signature { Some(d.take()?) } else { None };
+        Some(Self {
+            identity,
+            signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_01() {
+        let buf: [u8; 19] = [
+            0, 0, 0, 0, 3, 97, 98, 99, 0, 0, 0, 7, 0, 0, 0, 3, 97, 98, 99,
+        ];
+        let msg: PublicKeyMethod = SshCodec::decode(&buf[..]).unwrap();
+        assert_eq!(msg.identity, Identity::from(vec![0, 0, 0, 3, 97, 98, 99]));
+        assert!(msg.signature.is_none());
+        assert_eq!(&buf[..], &SshCodec::encode(&msg).unwrap()[..]);
+    }
+}
@@ -1,7 +1,17 @@
+mod banner;
 mod failure;
+mod info_request;
+mod info_response;
+mod passwd_changereq;
+mod pk_ok;
 mod success;
 mod userauth_request;
 
+pub use self::banner::*;
 pub use self::failure::*;
+pub use self::info_request::*;
+pub use self::info_response::*;
+pub use self::passwd_changereq::*;
+pub use self::pk_ok::*;
 pub use self::success::*;
 pub use self::userauth_request::*;
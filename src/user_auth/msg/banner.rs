@@ -0,0 +1,57 @@
+use crate::transport::Message;
+use crate::util::codec::*;
+
+/// A message to be displayed to the user before authentication (RFC 4252 section 5.4).
+///
+/// The server may send it at any time before authentication succeeds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgBanner {
+    pub message: String,
+    pub language: String,
+}
+
+impl Message for MsgBanner {
+    const NUMBER: u8 = 53;
+}
+
+impl SshEncode for MsgBanner {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_u8(<Self as Message>::NUMBER)?;
+        e.push_str_framed(&self.message)?;
+        e.push_str_framed(&self.language)
+    }
+}
+
+impl SshDecode for MsgBanner {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_u8(<Self as Message>::NUMBER)?;
+        let message = d.take_str_framed()?.into();
+        let language = d.take_str_framed()?.into();
+        Some(Self { message, language })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_01() {
+        let msg = MsgBanner {
+            message: "abc".into(),
+            language: "".into(),
+        };
+        assert_eq!(
+            &[53, 0, 0, 0, 3, 97, 98, 99, 0, 0, 0, 0][..],
+            &SshCodec::encode(&msg).unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_decode_01() {
+        let buf: [u8; 12] = [53, 0, 0, 0, 3, 97, 98, 99, 0, 0, 0, 0];
+        let msg: MsgBanner = SshCodec::decode(&buf[..]).unwrap();
+        assert_eq!(msg.message, "abc");
+        assert_eq!(msg.language, "");
+    }
+}
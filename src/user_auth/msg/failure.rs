@@ -7,13 +7,13 @@ pub struct MsgFailure<T = String> {
     pub partial_success: bool,
 }
 
-impl Message for MsgFailure {
+impl<T> Message for MsgFailure<T> {
     const NUMBER: u8 = 51;
 }
 
 impl SshEncode for MsgFailure<&'static str> {
     fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
-        e.push_u8(MsgFailure::NUMBER)?;
+        e.push_u8(<Self as Message>::NUMBER)?;
         e.push_name_list(&self.methods)?;
         e.push_bool(self.partial_success)
     }
@@ -69,7 +69,10 @@ mod tests {
             methods: vec![],
             partial_success: false,
         };
-        assert_eq!(&[51, 0, 0, 0, 0, 0][..], &SshCodec::encode(&msg).unwrap()[..]);
+        assert_eq!(
+            &[51, 0, 0, 0, 0, 0][..],
+            &SshCodec::encode(&msg).unwrap()[..]
+        );
     }
 
     #[test]
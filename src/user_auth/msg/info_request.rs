@@ -0,0 +1,111 @@
+use super::super::keyboard_interactive::*;
+use crate::transport::Message;
+use crate::util::codec::*;
+
+/// The server's challenge in the `keyboard-interactive` method (RFC 4256 section 3.2).
+///
+/// The message number is shared with other method specific messages and must only be
+/// interpreted as such in reply to a `keyboard-interactive` request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgInfoRequest {
+    pub name: String,
+    pub instruction: String,
+    pub language: String,
+    pub prompts: Vec<ChallengePrompt>,
+}
+
+impl Message for MsgInfoRequest {
+    const NUMBER: u8 = 60;
+}
+
+impl SshEncode for MsgInfoRequest {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_u8(<Self as Message>::NUMBER)?;
+        e.push_str_framed(&self.name)?;
+        e.push_str_framed(&self.instruction)?;
+        e.push_str_framed(&self.language)?;
+        e.push_usize(self.prompts.len())?;
+        for prompt in &self.prompts {
+            e.push(prompt)?;
+        }
+        Some(())
+    }
+}
+
+impl SshDecode for MsgInfoRequest {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_u8(<Self as Message>::NUMBER)?;
+        let name = d.take_str_framed()?.into();
+        let instruction = d.take_str_framed()?.into();
+        let language = d.take_str_framed()?.into();
+        let len = d.take_usize()?;
+        let mut prompts = vec![];
+        for _ in 0..len {
+            prompts.push(d.take()?);
+        }
+        Some(Self {
+            name,
+            instruction,
+            language,
+            prompts,
+        })
+    }
+}
+
+impl From<Challenge> for MsgInfoRequest {
+    fn from(x: Challenge) -> Self {
+        Self {
+            name: x.name,
+            instruction: x.instruction,
+            language: String::new(),
+            prompts: x.prompts,
+        }
+    }
+}
+
+impl From<MsgInfoRequest> for Challenge {
+    fn from(x: MsgInfoRequest) -> Self {
+        Self {
+            name: x.name,
+            instruction: x.instruction,
+            prompts: x.prompts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_01() {
+        let msg = MsgInfoRequest {
+            name: "a".into(),
+            instruction: "b".into(),
+            language: "".into(),
+            prompts: vec![ChallengePrompt::new("c", false)],
+        };
+        assert_eq!(
+            &[60, 0, 0, 0, 1, 97, 0, 0, 0, 1, 98, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 99, 0][..],
+            &SshCodec::encode(&msg).unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_decode_01() {
+        let buf: [u8; 25] = [
+            60, 0, 0, 0, 1, 97, 0, 0, 0, 1, 98, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 99, 0,
+        ];
+        let msg: MsgInfoRequest = SshCodec::decode(&buf[..]).unwrap();
+        assert_eq!(msg.name, "a");
+        assert_eq!(msg.instruction, "b");
+        assert_eq!(msg.language, "");
+        assert_eq!(msg.prompts, vec![ChallengePrompt::new("c", false)]);
+    }
+
+    #[test]
+    fn test_decode_02() {
+        let buf: [u8; 19] = [60, 0, 0, 0, 1, 97, 0, 0, 0, 1, 98, 0, 0, 0, 0, 0, 0, 0, 1];
+        assert!(SshCodec::decode::<MsgInfoRequest>(&buf[..]).is_err());
+    }
+}
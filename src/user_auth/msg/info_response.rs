@@ -0,0 +1,80 @@
+use crate::transport::Message;
+use crate::util::codec::*;
+use zeroize::Zeroize;
+
+/// The client's answers in the `keyboard-interactive` method (RFC 4256 section 3.4).
+#[derive(Clone, PartialEq)]
+pub struct MsgInfoResponse {
+    pub responses: Vec<String>,
+}
+
+impl Message for MsgInfoResponse {
+    const NUMBER: u8 = 61;
+}
+
+impl SshEncode for MsgInfoResponse {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_u8(<Self as Message>::NUMBER)?;
+        e.push_usize(self.responses.len())?;
+        for response in &self.responses {
+            e.push_str_framed(response)?;
+        }
+        Some(())
+    }
+}
+
+impl SshDecode for MsgInfoResponse {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_u8(<Self as Message>::NUMBER)?;
+        let len = d.take_usize()?;
+        let mut responses = vec![];
+        for _ in 0..len {
+            responses.push(d.take_str_framed()?.into());
+        }
+        Some(Self { responses })
+    }
+}
+
+/// Responses are most likely secret and must not end up in log output.
+impl std::fmt::Debug for MsgInfoResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MsgInfoResponse({} responses)", self.responses.len())
+    }
+}
+
+impl Drop for MsgInfoResponse {
+    fn drop(&mut self) {
+        self.responses.zeroize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_01() {
+        let msg = MsgInfoResponse {
+            responses: vec!["123456".into()],
+        };
+        assert_eq!("MsgInfoResponse(1 responses)", format!("{:?}", msg));
+    }
+
+    #[test]
+    fn test_encode_01() {
+        let msg = MsgInfoResponse {
+            responses: vec!["ab".into(), "".into()],
+        };
+        assert_eq!(
+            &[61, 0, 0, 0, 2, 0, 0, 0, 2, 97, 98, 0, 0, 0, 0][..],
+            &SshCodec::encode(&msg).unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_decode_01() {
+        let buf: [u8; 15] = [61, 0, 0, 0, 2, 0, 0, 0, 2, 97, 98, 0, 0, 0, 0];
+        let msg: MsgInfoResponse = SshCodec::decode(&buf[..]).unwrap();
+        assert_eq!(msg.responses, vec!["ab", ""]);
+    }
+}
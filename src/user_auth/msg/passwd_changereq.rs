@@ -0,0 +1,58 @@
+use crate::transport::Message;
+use crate::util::codec::*;
+
+/// The server's demand to change the password (RFC 4252 section 8).
+///
+/// The message number is shared with other method specific messages and must only be
+/// interpreted as such in reply to a `password` request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgPasswdChangeReq {
+    pub prompt: String,
+    pub language: String,
+}
+
+impl Message for MsgPasswdChangeReq {
+    const NUMBER: u8 = 60;
+}
+
+impl SshEncode for MsgPasswdChangeReq {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_u8(<Self as Message>::NUMBER)?;
+        e.push_str_framed(&self.prompt)?;
+        e.push_str_framed(&self.language)
+    }
+}
+
+impl SshDecode for MsgPasswdChangeReq {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_u8(<Self as Message>::NUMBER)?;
+        let prompt = d.take_str_framed()?.into();
+        let language = d.take_str_framed()?.into();
+        Some(Self { prompt, language })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_01() {
+        let msg = MsgPasswdChangeReq {
+            prompt: "abc".into(),
+            language: "".into(),
+        };
+        assert_eq!(
+            &[60, 0, 0, 0, 3, 97, 98, 99, 0, 0, 0, 0][..],
+            &SshCodec::encode(&msg).unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_decode_01() {
+        let buf: [u8; 12] = [60, 0, 0, 0, 3, 97, 98, 99, 0, 0, 0, 0];
+        let msg: MsgPasswdChangeReq = SshCodec::decode(&buf[..]).unwrap();
+        assert_eq!(msg.prompt, "abc");
+        assert_eq!(msg.language, "");
+    }
+}
@@ -0,0 +1,62 @@
+use crate::identity::*;
+use crate::transport::Message;
+use crate::util::codec::*;
+
+/// The server's acceptance of a public key query (RFC 4252 section 7).
+///
+/// The message number is shared with other method specific messages and must only be
+/// interpreted as such in reply to a `publickey` request without signature.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgPkOk {
+    pub algorithm: String,
+    pub identity: Identity,
+}
+
+impl Message for MsgPkOk {
+    const NUMBER: u8 = 60;
+}
+
+impl SshEncode for MsgPkOk {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push_u8(<Self as Message>::NUMBER)?;
+        e.push_str_framed(&self.algorithm)?;
+        e.push(&self.identity)
+    }
+}
+
+impl SshDecode for MsgPkOk {
+    fn decode<'a, D: SshDecoder<'a>>(d: &mut D) -> Option<Self> {
+        d.expect_u8(<Self as Message>::NUMBER)?;
+        let algorithm = d.take_str_framed()?.into();
+        let identity = d.take()?;
+        Some(Self {
+            algorithm,
+            identity,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_01() {
+        let msg = MsgPkOk {
+            algorithm: "abc".into(),
+            identity: Identity::from(vec![1, 2, 3]),
+        };
+        assert_eq!(
+            &[60, 0, 0, 0, 3, 97, 98, 99, 0, 0, 0, 3, 1, 2, 3][..],
+            &SshCodec::encode(&msg).unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_decode_01() {
+        let buf: [u8; 15] = [60, 0, 0, 0, 3, 97, 98, 99, 0, 0, 0, 3, 1, 2, 3];
+        let msg: MsgPkOk = SshCodec::decode(&buf[..]).unwrap();
+        assert_eq!(msg.algorithm, "abc");
+        assert_eq!(msg.identity, Identity::from(vec![1, 2, 3]));
+    }
+}
@@ -37,3 +37,64 @@ impl<'a, M: AuthMethod + SshDecodeRef<'a>> SshDecodeRef<'a> for MsgUserAuthReque
         })
     }
 }
+
+/// The method independent part of a user auth request.
+///
+/// The server needs to inspect the method name before it is able to decode the whole request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgUserAuthRequestHeader<'a> {
+    pub user_name: &'a str,
+    pub service_name: &'a str,
+    pub method_name: &'a str,
+}
+
+impl<'a> MsgUserAuthRequestHeader<'a> {
+    /// Decode only the header and ignore the method specific remainder.
+    pub fn peek(buf: &'a [u8]) -> Option<Self> {
+        let mut d = RefDecoder::new(buf);
+        d.expect_u8(<MsgUserAuthRequest<PasswordMethod> as Message>::NUMBER)?;
+        Some(Self {
+            user_name: d.take_str_framed()?,
+            service_name: d.take_str_framed()?,
+            method_name: d.take_str_framed()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_01() {
+        let msg = MsgUserAuthRequest {
+            user_name: "user",
+            service_name: "service",
+            method: PasswordMethod::new("abcd".into()),
+        };
+        let buf = SshCodec::encode(&msg).unwrap();
+        let msg: MsgUserAuthRequest<PasswordMethod> = SshCodec::decode(&buf).unwrap();
+        assert_eq!(msg.user_name, "user");
+        assert_eq!(msg.service_name, "service");
+        assert_eq!(msg.method.password, "abcd");
+    }
+
+    #[test]
+    fn test_peek_01() {
+        let msg = MsgUserAuthRequest {
+            user_name: "user",
+            service_name: "service",
+            method: PasswordMethod::new("abcd".into()),
+        };
+        let buf = SshCodec::encode(&msg).unwrap();
+        let hdr = MsgUserAuthRequestHeader::peek(&buf).unwrap();
+        assert_eq!(hdr.user_name, "user");
+        assert_eq!(hdr.service_name, "service");
+        assert_eq!(hdr.method_name, "password");
+    }
+
+    #[test]
+    fn test_peek_02() {
+        assert_eq!(MsgUserAuthRequestHeader::peek(&[51, 0, 0]), None);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::util::BoxFuture;
+
+/// A source of passwords for the `password` method (client side).
+///
+/// Passwords are requested on demand and never stored in any configuration. Implementations
+/// may ask the user interactively or fetch the password from a vault or similar.
+pub trait PasswordPrompt: std::fmt::Debug + Send + Sync + 'static {
+    /// Request the password for `user` at `host`.
+    ///
+    /// Returns `None` in order to cancel the `password` method.
+    fn password(&self, user: &str, host: &str) -> BoxFuture<Option<String>>;
+
+    /// Request a new password after the server demanded a password change.
+    ///
+    /// The `prompt` is the message sent by the server. Returns `None` in order to cancel the
+    /// `password` method (the default).
+    fn new_password(&self, user: &str, host: &str, prompt: &str) -> BoxFuture<Option<String>> {
+        let _ = (user, host, prompt);
+        Box::pin(async { None })
+    }
+}
+
+/// A verifier for the `password` method (server side).
+///
+/// Use [constant_time_eq](crate::util::secret::constant_time_eq) for comparing secrets.
+/// Limiting the number of attempts is done by the caller (see
+/// [UserAuthConfig::max_attempts](super::UserAuthConfig::max_attempts)).
+pub trait PasswordVerifier: std::fmt::Debug + Send + Sync + 'static {
+    /// Verify the password of `user`.
+    fn verify(&self, user: &str, password: &str) -> BoxFuture<PasswordVerification>;
+
+    /// Verify the old password of `user` and change it to `new_password`.
+    ///
+    /// This is only called after [verify](Self::verify) returned
+    /// [ChangeRequired](PasswordVerification::ChangeRequired) (or when the client decided to
+    /// change the password on its own). The default implementation rejects all changes.
+    fn change(
+        &self,
+        user: &str,
+        password: &str,
+        new_password: &str,
+    ) -> BoxFuture<PasswordVerification> {
+        let _ = (user, password, new_password);
+        Box::pin(async { PasswordVerification::Rejected })
+    }
+}
+
+/// The outcome of a password verification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PasswordVerification {
+    /// The password is correct.
+    Accepted,
+    /// The password is incorrect (or the user unknown).
+    Rejected,
+    /// The password is correct, but expired. The client shall be asked to change it using
+    /// the given prompt.
+    ChangeRequired(String),
+}
+
+/// The unit prompt never supplies a password.
+impl PasswordPrompt for () {
+    fn password(&self, _: &str, _: &str) -> BoxFuture<Option<String>> {
+        Box::pin(async { None })
+    }
+}
+
+/// The unit verifier rejects all passwords.
+impl PasswordVerifier for () {
+    fn verify(&self, _: &str, _: &str) -> BoxFuture<PasswordVerification> {
+        Box::pin(async { PasswordVerification::Rejected })
+    }
+}
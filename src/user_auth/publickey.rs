@@ -0,0 +1,21 @@
+use crate::identity::Identity;
+use crate::util::BoxFuture;
+
+/// A verifier for the `publickey` method (server side).
+///
+/// The verifier only decides whether an identity is authorized for a user (like an
+/// `authorized_keys` file). Signatures are checked by the caller.
+pub trait PublicKeyVerifier: std::fmt::Debug + Send + Sync + 'static {
+    /// Whether `identity` may be used to authenticate as `user`.
+    ///
+    /// This is called for queries without signature as well, so it should be cheap and must
+    /// not have side effects.
+    fn is_authorized(&self, user: &str, identity: &Identity) -> BoxFuture<bool>;
+}
+
+/// The unit verifier rejects all identities.
+impl PublicKeyVerifier for () {
+    fn is_authorized(&self, _: &str, _: &Identity) -> BoxFuture<bool> {
+        Box::pin(async { false })
+    }
+}
@@ -0,0 +1,389 @@
+use super::*;
+
+/// The server's verdict on a single authentication request.
+enum Verdict {
+    /// The method succeeded (send `SSH_MSG_USERAUTH_SUCCESS` unless more methods are required).
+    Success,
+    /// The method failed (send `SSH_MSG_USERAUTH_FAILURE`).
+    Failure,
+    /// A method specific message has been sent and the method continues.
+    Continue,
+}
+
+/// The server's state of the authentication process.
+#[derive(Debug, Default)]
+struct State {
+    /// The user of the previous request.
+    user: Option<String>,
+    /// Whether the user of the previous request may log in at all.
+    allowed: bool,
+    /// The number of failed attempts.
+    attempts: u32,
+    /// The methods that succeeded so far (in order).
+    completed: Vec<&'static str>,
+    /// The `keyboard-interactive` challenge awaiting a response.
+    pending: Option<Challenge>,
+    /// Whether the banner has been sent.
+    banner_sent: bool,
+}
+
+impl UserAuth {
+    /// Offer another service with user authentication (server side).
+    ///
+    /// Requests are processed until either one succeeds or the client exceeds the maximum
+    /// number of attempts. Only methods configured in `auth` are offered to the client. If
+    /// [required_methods](UserAuthConfig::required_methods) is set, the client is told about
+    /// partial success until one of the required method lists has been completed.
+    pub async fn offer_connection<F: FnOnce(&Connection) -> Box<dyn ConnectionHandler>>(
+        transport: Transport,
+        config: &Arc<ConnectionConfig>,
+        handle: F,
+        auth: &Arc<UserAuthConfig>,
+    ) -> Result<Connection, UserAuthError> {
+        let mut t = transport;
+        let mut s = State::default();
+        loop {
+            let buf: Vec<u8> = t.receive().await?;
+            let (user, method, counted, verdict) = if buf.first() == Some(&MsgInfoResponse::NUMBER)
+            {
+                log::debug!("Rx USERAUTH_INFO_RESPONSE");
+                let user = s.user.clone().unwrap_or_default();
+                let verdict = Self::verify_challenge(&mut t, auth, &buf, &user, &mut s).await?;
+                (user, KeyboardInteractiveMethod::NAME, true, verdict)
+            } else {
+                let hdr =
+                    MsgUserAuthRequestHeader::peek(&buf).ok_or(SshCodecError::DecodingFailed)?;
+                log::debug!(
+                    "Rx USERAUTH_REQUEST: {} ({})",
+                    hdr.user_name,
+                    hdr.method_name
+                );
+                if hdr.service_name != Self::SSH_CONNECTION {
+                    let reason = DisconnectReason::SERVICE_NOT_AVAILABLE;
+                    t.send(&MsgDisconnect::new(reason)).await?;
+                    t.flush().await?;
+                    let e = TransportError::InvalidServiceRequest(hdr.service_name.into());
+                    return Err(e.into());
+                }
+                if let Some(ref message) = auth.banner {
+                    if !s.banner_sent {
+                        let msg = MsgBanner {
+                            message: message.clone(),
+                            language: String::new(),
+                        };
+                        t.send(&msg).await?;
+                        log::debug!("Tx USERAUTH_BANNER");
+                        s.banner_sent = true;
+                    }
+                }
+                // A new request aborts any pending challenge. Methods completed by another
+                // user don't count for this one.
+                s.pending = None;
+                if s.user.as_deref() != Some(hdr.user_name) {
+                    s.user = Some(hdr.user_name.into());
+                    s.completed.clear();
+                    s.allowed = match auth.user_verifier {
+                        Some(ref verifier) => verifier.is_allowed(hdr.user_name).await,
+                        None => true,
+                    };
+                }
+                let offered = Self::offered_methods(auth, &s.completed);
+                let (method, verdict) = match hdr.method_name {
+                    m if !offered.contains(&m) => (Self::NONE, Verdict::Failure),
+                    HostbasedMethod::NAME => {
+                        let verdict = Self::verify_hostbased(&mut t, auth, &buf).await?;
+                        (HostbasedMethod::NAME, verdict)
+                    }
+                    PublicKeyMethod::NAME => {
+                        let verdict = Self::verify_pubkey(&mut t, auth, &buf).await?;
+                        (PublicKeyMethod::NAME, verdict)
+                    }
+                    PasswordMethod::NAME => {
+                        let verdict = Self::verify_password(&mut t, auth, &buf).await?;
+                        (PasswordMethod::NAME, verdict)
+                    }
+                    KeyboardInteractiveMethod::NAME => {
+                        let verdict = Self::start_challenge(&mut t, auth, &buf, &mut s).await?;
+                        (KeyboardInteractiveMethod::NAME, verdict)
+                    }
+                    _ => (Self::NONE, Verdict::Failure),
+                };
+                let counted = hdr.method_name != Self::NONE;
+                (hdr.user_name.to_string(), method, counted, verdict)
+            };
+            // Users not allowed go through the method's verification as well so that the
+            // response doesn't reveal which user names are allowed.
+            let verdict = match verdict {
+                Verdict::Success if !s.allowed => Verdict::Failure,
+                verdict => verdict,
+            };
+            let partial_success = match verdict {
+                Verdict::Success => {
+                    s.completed.push(method);
+                    if Self::is_complete(auth, &s.completed) {
+                        t.send(&MsgSuccess).await?;
+                        t.flush().await?;
+                        log::debug!("Tx USERAUTH_SUCCESS: {}", user);
+                        return Ok(Connection::new(config, t, handle));
+                    }
+                    true
+                }
+                Verdict::Failure => {
+                    if counted {
+                        s.attempts += 1;
+                    }
+                    if s.attempts >= auth.max_attempts {
+                        let reason = DisconnectReason::NO_MORE_AUTH_METHODS_AVAILABLE;
+                        t.send(&MsgDisconnect::new(reason)).await?;
+                        t.flush().await?;
+                        return Err(UserAuthError::MaxAttemptsExceeded);
+                    }
+                    false
+                }
+                Verdict::Continue => continue,
+            };
+            let msg = MsgFailure {
+                methods: Self::offered_methods(auth, &s.completed),
+                partial_success,
+            };
+            t.send(&msg).await?;
+            t.flush().await?;
+            log::debug!(
+                "Tx USERAUTH_FAILURE: {:?} ({})",
+                msg.methods,
+                partial_success
+            );
+        }
+    }
+
+    async fn verify_pubkey(
+        transport: &mut Transport,
+        auth: &Arc<UserAuthConfig>,
+        buf: &[u8],
+    ) -> Result<Verdict, UserAuthError> {
+        let verifier = match auth.public_key_verifier {
+            Some(ref verifier) => verifier,
+            None => return Ok(Verdict::Failure),
+        };
+        let msg: MsgUserAuthRequest<PublicKeyMethod> = SshCodec::decode(buf)?;
+        let identity = &msg.method.identity;
+        if Self::is_revoked(auth, identity) {
+            return Ok(Verdict::Failure);
+        }
+        if !verifier.is_authorized(msg.user_name, identity).await {
+            return Ok(Verdict::Failure);
+        }
+        match msg.method.signature {
+            None => {
+                let msg = MsgPkOk {
+                    algorithm: identity.algorithm().into(),
+                    identity: identity.clone(),
+                };
+                transport.send(&msg).await?;
+                transport.flush().await?;
+                log::debug!("Tx USERAUTH_PK_OK: {}", msg.algorithm);
+                Ok(Verdict::Continue)
+            }
+            Some(ref signature) => {
+                let data = SignatureData {
+                    session_id: transport.session_id(),
+                    user_name: msg.user_name,
+                    service_name: msg.service_name,
+                    identity,
+                };
+                let data = SshCodec::encode(&data)?;
+                match signature.verify(identity, &data) {
+                    Ok(()) => Ok(Verdict::Success),
+                    Err(e) => {
+                        log::debug!("Invalid signature: {:?}", e);
+                        Ok(Verdict::Failure)
+                    }
+                }
+            }
+        }
+    }
+
+    async fn verify_hostbased(
+        transport: &mut Transport,
+        auth: &Arc<UserAuthConfig>,
+        buf: &[u8],
+    ) -> Result<Verdict, UserAuthError> {
+        let verifier = match auth.hostbased_verifier {
+            Some(ref verifier) => verifier,
+            None => return Ok(Verdict::Failure),
+        };
+        let msg: MsgUserAuthRequest<HostbasedMethod> = SshCodec::decode(buf)?;
+        let m = &msg.method;
+        if Self::is_revoked(auth, &m.identity) {
+            return Ok(Verdict::Failure);
+        }
+        let data = HostbasedSignatureData {
+            session_id: transport.session_id(),
+            user_name: msg.user_name,
+            service_name: msg.service_name,
+            identity: &m.identity,
+            client_host_name: &m.host_name,
+            client_user_name: &m.user_name,
+        };
+        let data = SshCodec::encode(&data)?;
+        if let Err(e) = m.signature.verify(&m.identity, &data) {
+            log::debug!("Invalid signature: {:?}", e);
+            return Ok(Verdict::Failure);
+        }
+        let user = msg.user_name;
+        if verifier
+            .is_authorized(user, &m.host_name, &m.user_name, &m.identity)
+            .await
+        {
+            Ok(Verdict::Success)
+        } else {
+            Ok(Verdict::Failure)
+        }
+    }
+
+    fn is_revoked(auth: &UserAuthConfig, identity: &Identity) -> bool {
+        let revoked = auth
+            .revoked_keys
+            .as_ref()
+            .map(|krl| krl.is_revoked(identity));
+        if revoked == Some(true) {
+            log::debug!("Revoked key: {}", identity.algorithm());
+        }
+        revoked == Some(true)
+    }
+
+    async fn verify_password(
+        transport: &mut Transport,
+        auth: &Arc<UserAuthConfig>,
+        buf: &[u8],
+    ) -> Result<Verdict, UserAuthError> {
+        let verifier = match auth.password_verifier {
+            Some(ref verifier) => verifier,
+            None => return Ok(Verdict::Failure),
+        };
+        let msg: MsgUserAuthRequest<PasswordMethod> = SshCodec::decode(buf)?;
+        let user = msg.user_name;
+        let password = &msg.method.password;
+        let verification = match msg.method.new_password {
+            None => verifier.verify(user, password).await,
+            Some(ref new) => verifier.change(user, password, new).await,
+        };
+        match verification {
+            PasswordVerification::Accepted => Ok(Verdict::Success),
+            PasswordVerification::Rejected => Ok(Verdict::Failure),
+            PasswordVerification::ChangeRequired(prompt) => {
+                let msg = MsgPasswdChangeReq {
+                    prompt,
+                    language: String::new(),
+                };
+                transport.send(&msg).await?;
+                transport.flush().await?;
+                log::debug!("Tx USERAUTH_PASSWD_CHANGEREQ");
+                Ok(Verdict::Continue)
+            }
+        }
+    }
+
+    async fn start_challenge(
+        transport: &mut Transport,
+        auth: &Arc<UserAuthConfig>,
+        buf: &[u8],
+        state: &mut State,
+    ) -> Result<Verdict, UserAuthError> {
+        let verifier = match auth.keyboard_interactive_verifier {
+            Some(ref verifier) => verifier,
+            None => return Ok(Verdict::Failure),
+        };
+        let msg: MsgUserAuthRequest<KeyboardInteractiveMethod> = SshCodec::decode(buf)?;
+        let user = msg.user_name;
+        match verifier.challenge(user, &msg.method.submethods).await {
+            None => Ok(Verdict::Failure),
+            Some(challenge) => {
+                Self::send_challenge(transport, &challenge).await?;
+                state.pending = Some(challenge);
+                Ok(Verdict::Continue)
+            }
+        }
+    }
+
+    async fn verify_challenge(
+        transport: &mut Transport,
+        auth: &Arc<UserAuthConfig>,
+        buf: &[u8],
+        user: &str,
+        state: &mut State,
+    ) -> Result<Verdict, UserAuthError> {
+        let (verifier, challenge) =
+            match (&auth.keyboard_interactive_verifier, state.pending.take()) {
+                (Some(verifier), Some(challenge)) => (verifier, challenge),
+                _ => return Err(TransportError::InvalidState.into()),
+            };
+        let msg: MsgInfoResponse = SshCodec::decode(buf)?;
+        if msg.responses.len() != challenge.prompts.len() {
+            return Ok(Verdict::Failure);
+        }
+        match verifier.verify(user, &challenge, &msg.responses).await {
+            ChallengeVerification::Accepted => Ok(Verdict::Success),
+            ChallengeVerification::Rejected => Ok(Verdict::Failure),
+            ChallengeVerification::Continue(challenge) => {
+                Self::send_challenge(transport, &challenge).await?;
+                state.pending = Some(challenge);
+                Ok(Verdict::Continue)
+            }
+        }
+    }
+
+    async fn send_challenge(
+        transport: &mut Transport,
+        challenge: &Challenge,
+    ) -> Result<(), UserAuthError> {
+        let msg = MsgInfoRequest::from(challenge.clone());
+        transport.send(&msg).await?;
+        transport.flush().await?;
+        log::debug!("Tx USERAUTH_INFO_REQUEST: {} prompts", msg.prompts.len());
+        Ok(())
+    }
+
+    /// The methods that may continue after the given methods have been completed.
+    ///
+    /// Only methods with a configured verifier are offered.
+    fn offered_methods(auth: &UserAuthConfig, completed: &[&'static str]) -> Vec<&'static str> {
+        let mut configured = vec![];
+        if auth.hostbased_verifier.is_some() {
+            configured.push(HostbasedMethod::NAME);
+        }
+        if auth.public_key_verifier.is_some() {
+            configured.push(PublicKeyMethod::NAME);
+        }
+        if auth.password_verifier.is_some() {
+            configured.push(PasswordMethod::NAME);
+        }
+        if auth.keyboard_interactive_verifier.is_some() {
+            configured.push(KeyboardInteractiveMethod::NAME);
+        }
+        if auth.required_methods.is_empty() {
+            return configured;
+        }
+        let mut methods = vec![];
+        for list in &auth.required_methods {
+            if list.len() > completed.len() && list[..completed.len()] == *completed {
+                let next = list[completed.len()];
+                if configured.contains(&next) && !methods.contains(&next) {
+                    methods.push(next);
+                }
+            }
+        }
+        methods
+    }
+
+    /// Whether the given completed methods suffice for successful authentication.
+    fn is_complete(auth: &UserAuthConfig, completed: &[&'static str]) -> bool {
+        if auth.required_methods.is_empty() {
+            !completed.is_empty()
+        } else {
+            auth.required_methods
+                .iter()
+                .any(|list| list[..] == *completed)
+        }
+    }
+}
@@ -32,3 +32,35 @@ impl<'a> SshEncode for SignatureData<'a> {
         e.push(self.identity)
     }
 }
+
+/// string    session identifier
+/// byte      SSH_MSG_USERAUTH_REQUEST
+/// string    user name
+/// string    service name
+/// string    "hostbased"
+/// string    public key algorithm for host key
+/// string    public host key and certificates for client host
+/// string    client host name expressed as the FQDN in US-ASCII
+/// string    user name on the client host in ISO-10646 UTF-8 encoding
+pub struct HostbasedSignatureData<'a> {
+    pub session_id: &'a Secret,
+    pub user_name: &'a str,
+    pub service_name: &'a str,
+    pub identity: &'a Identity,
+    pub client_host_name: &'a str,
+    pub client_user_name: &'a str,
+}
+
+impl<'a> SshEncode for HostbasedSignatureData<'a> {
+    fn encode<E: SshEncoder>(&self, e: &mut E) -> Option<()> {
+        e.push(self.session_id)?;
+        e.push_u8(<MsgUserAuthRequest<HostbasedMethod> as Message>::NUMBER)?;
+        e.push_str_framed(self.user_name)?;
+        e.push_str_framed(self.service_name)?;
+        e.push_str_framed(<HostbasedMethod as AuthMethod>::NAME)?;
+        e.push_str_framed(self.identity.algorithm())?;
+        e.push(self.identity)?;
+        e.push_str_framed(self.client_host_name)?;
+        e.push_str_framed(self.client_user_name)
+    }
+}
@@ -0,0 +1,18 @@
+use crate::util::BoxFuture;
+
+/// A verifier deciding whether a user may log in at all (server side).
+///
+/// This is the place for account checks like OpenSSH's `AllowUsers` and `DenyUsers`. It is
+/// consulted once per user name. Requests of users not allowed still run the method's
+/// verification and then fail just like requests with wrong credentials.
+pub trait UserVerifier: std::fmt::Debug + Send + Sync + 'static {
+    /// Whether `user` may authenticate.
+    fn is_allowed(&self, user: &str) -> BoxFuture<bool>;
+}
+
+/// The unit verifier rejects all users.
+impl UserVerifier for () {
+    fn is_allowed(&self, _: &str) -> BoxFuture<bool> {
+        Box::pin(async { false })
+    }
+}
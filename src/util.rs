@@ -1,10 +1,11 @@
 pub mod buffer;
 pub mod cidr;
 pub mod codec;
+pub mod config;
 pub mod glob;
+pub mod secret;
 pub mod socket;
 pub mod socks5;
-pub mod secret;
 
 pub type ArcError = std::sync::Arc<dyn std::error::Error + Send + Sync + 'static>;
 pub type BoxFuture<T> = core::pin::Pin<Box<dyn std::future::Future<Output = T> + Send>>;
@@ -0,0 +1,379 @@
+//! Line-based configuration files in the style of OpenSSH's `ssh_config` and `sshd_config`.
+
+use super::glob::Glob;
+use crate::transport::TransportConfig;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A line of a configuration file consisting of a keyword and its arguments.
+///
+/// Keyword and arguments are separated by whitespace and/or a single `=`. Arguments containing
+/// whitespace may be enclosed in double quotes. Empty lines and comments (starting with `#`) are
+/// skipped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigLine {
+    /// The keyword (in lower case as keywords are case-insensitive)
+    pub keyword: String,
+    pub args: Vec<String>,
+}
+
+impl ConfigLine {
+    /// Parse a line (returns `Ok(None)` for empty lines and comments).
+    pub fn parse(line: &str) -> Result<Option<Self>, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let end = line
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(line.len());
+        let (keyword, rest) = line.split_at(end);
+        let rest = rest.trim_start();
+        let rest = rest.strip_prefix('=').unwrap_or(rest);
+        let args = Self::split(rest)?;
+        Ok(Some(Self {
+            keyword: keyword.to_ascii_lowercase(),
+            args,
+        }))
+    }
+
+    /// The single argument (error if there is none or more than one).
+    pub fn arg(&self) -> Result<&str, String> {
+        single_arg(&self.keyword, &self.args)
+    }
+
+    /// Split the arguments at whitespace (respecting double quotes, a `#` starts a comment).
+    fn split(s: &str) -> Result<Vec<String>, String> {
+        let mut args = vec![];
+        let mut chars = s.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.peek() {
+                None | Some('#') => return Ok(args),
+                Some('"') => {
+                    let _ = chars.next();
+                    let mut arg = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(c) => arg.push(c),
+                            None => return Err("Unterminated quote".into()),
+                        }
+                    }
+                    args.push(arg);
+                }
+                Some(_) => {
+                    let mut arg = String::new();
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                        arg.push(c);
+                    }
+                    args.push(arg);
+                }
+            }
+        }
+    }
+}
+
+/// The option values collected from the lines that apply (by keyword in lower case).
+#[derive(Clone, Debug, Default)]
+pub struct ConfigOptions(pub HashMap<String, Vec<String>>);
+
+impl ConfigOptions {
+    /// The arguments of an option (`None` if not set).
+    pub fn get(&self, keyword: &str) -> Option<&[String]> {
+        self.0.get(keyword).map(Vec::as_slice)
+    }
+
+    /// The single argument of an option (error if there is none or more than one).
+    pub fn arg(&self, keyword: &str) -> Result<Option<&str>, String> {
+        self.get(keyword)
+            .map(|args| single_arg(keyword, args))
+            .transpose()
+    }
+
+    /// The value of a flag (`yes` or `no`).
+    pub fn flag(&self, keyword: &str) -> Result<Option<bool>, String> {
+        self.arg(keyword)?.map(parse_flag).transpose()
+    }
+
+    /// Apply the algorithm lists (`KexAlgorithms`, `HostKeyAlgorithms`, `Ciphers` and `MACs`)
+    /// and `RekeyLimit` to a transport configuration.
+    pub fn transport(&self, tc: &mut TransportConfig) -> Result<(), String> {
+        let supported = TransportConfig::default();
+        let lists = [
+            (
+                "kexalgorithms",
+                &mut tc.kex_algorithms,
+                &supported.kex_algorithms,
+            ),
+            (
+                "hostkeyalgorithms",
+                &mut tc.host_key_algorithms,
+                &supported.host_key_algorithms,
+            ),
+            (
+                "ciphers",
+                &mut tc.encryption_algorithms,
+                &supported.encryption_algorithms,
+            ),
+            ("macs", &mut tc.mac_algorithms, &supported.mac_algorithms),
+        ];
+        for (keyword, list, supported) in lists {
+            if let Some(spec) = self.arg(keyword)? {
+                *list = parse_algorithms(list, supported, spec)?;
+                if list.is_empty() && !supported.is_empty() {
+                    return Err(format!("{}: No supported algorithm in {}", keyword, spec));
+                }
+            }
+        }
+        if let Some(args) = self.get("rekeylimit") {
+            match args {
+                [bytes, rest @ ..] if rest.len() <= 1 => {
+                    if bytes != "default" {
+                        tc.kex_interval_bytes = parse_size(bytes)?;
+                    }
+                    match rest.first().map(String::as_str) {
+                        None | Some("default") => (),
+                        Some("none") => tc.kex_interval_duration = Duration::MAX,
+                        Some(time) => tc.kex_interval_duration = parse_time(time)?,
+                    }
+                }
+                _ => return Err("RekeyLimit: Invalid arguments".into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn single_arg<'a>(keyword: &str, args: &'a [String]) -> Result<&'a str, String> {
+    match args {
+        [arg] => Ok(arg),
+        [] => Err(format!("{}: Missing argument", keyword)),
+        _ => Err(format!("{}: Too many arguments", keyword)),
+    }
+}
+
+/// An error reading a configuration file.
+#[derive(Debug)]
+pub enum ConfigError {
+    IoError(std::io::Error),
+    /// An invalid line (file, line number and reason)
+    InvalidLine(PathBuf, usize, String),
+    /// An invalid option value (reason)
+    InvalidValue(String),
+}
+
+impl ConfigError {
+    pub(crate) fn line(path: &Path, number: usize, reason: String) -> Self {
+        Self::InvalidLine(path.into(), number, reason)
+    }
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        Self::IoError(e)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "IO error: {}", e),
+            Self::InvalidLine(path, n, e) => write!(f, "{} line {}: {}", path.display(), n, e),
+            Self::InvalidValue(e) => write!(f, "Invalid value: {}", e),
+        }
+    }
+}
+
+/// Parse a flag (`yes` or `no`).
+pub fn parse_flag(s: &str) -> Result<bool, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "yes" | "true" => Ok(true),
+        "no" | "false" => Ok(false),
+        _ => Err(format!("Invalid flag: {}", s)),
+    }
+}
+
+/// Parse a time interval (like `90`, `1h30m` or `2d`, seconds without unit).
+pub fn parse_time(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid time: {}", s);
+    let mut secs: u64 = 0;
+    let mut num = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            num.push(c);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let n: u64 = num.parse().map_err(|_| invalid())?;
+        secs = n
+            .checked_mul(unit)
+            .and_then(|n| secs.checked_add(n))
+            .ok_or_else(invalid)?;
+        num.clear();
+    }
+    if !num.is_empty() {
+        let n: u64 = num.parse().map_err(|_| invalid())?;
+        secs = secs.checked_add(n).ok_or_else(invalid)?;
+    } else if s.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Parse a size in bytes (like `512`, `64K`, `1G`).
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid size: {}", s);
+    let (num, unit) = match s.char_indices().last().ok_or_else(invalid)? {
+        (i, 'k' | 'K') => (&s[..i], 1 << 10),
+        (i, 'm' | 'M') => (&s[..i], 1 << 20),
+        (i, 'g' | 'G') => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+    let num: u64 = num.parse().map_err(|_| invalid())?;
+    num.checked_mul(unit).ok_or_else(invalid)
+}
+
+/// Replace a leading `~/` with the home directory.
+pub fn expand_tilde(path: &str) -> PathBuf {
+    #[allow(deprecated)]
+    match (path.strip_prefix("~/"), std::env::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Apply an algorithm list specification to the current list.
+///
+/// A list starting with `+` is appended, `-` removed and `^` prepended. Otherwise the list
+/// replaces the current one. The lists may contain wildcards which are matched against the
+/// supported algorithms (unsupported ones are skipped).
+pub fn parse_algorithms(
+    current: &[&'static str],
+    supported: &[&'static str],
+    spec: &str,
+) -> Result<Vec<&'static str>, String> {
+    let matching = |patterns: &str| {
+        let mut result = vec![];
+        for pattern in patterns.split(',') {
+            if pattern.is_empty() {
+                return Err(format!("Invalid algorithm list: {}", spec));
+            }
+            for name in supported {
+                if Glob(pattern).test(name) && !result.contains(name) {
+                    result.push(*name);
+                }
+            }
+        }
+        Ok(result)
+    };
+    let result = if let Some(spec) = spec.strip_prefix('+') {
+        let mut result = current.to_vec();
+        result.extend(matching(spec)?.into_iter().filter(|n| !current.contains(n)));
+        result
+    } else if let Some(spec) = spec.strip_prefix('-') {
+        let removed = matching(spec)?;
+        current
+            .iter()
+            .copied()
+            .filter(|n| !removed.contains(n))
+            .collect()
+    } else if let Some(spec) = spec.strip_prefix('^') {
+        let mut result = matching(spec)?;
+        result.extend(
+            current
+                .iter()
+                .filter(|n| !result.contains(n))
+                .collect::<Vec<_>>(),
+        );
+        result
+    } else {
+        matching(spec)?
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Option<(String, Vec<String>)> {
+        ConfigLine::parse(line)
+            .unwrap()
+            .map(|l| (l.keyword, l.args))
+    }
+
+    #[test]
+    fn test_parse_01() {
+        let line =
+            |k: &str, args: &[&str]| Some((k.into(), args.iter().map(|&a| a.into()).collect()));
+        assert_eq!(parse("  # comment"), None);
+        assert_eq!(parse(""), None);
+        assert_eq!(
+            parse("HostName example.com"),
+            line("hostname", &["example.com"])
+        );
+        assert_eq!(parse("Port=2222"), line("port", &["2222"]));
+        assert_eq!(parse("Port = 2222 # comment"), line("port", &["2222"]));
+        assert_eq!(parse("Host a b\t c"), line("host", &["a", "b", "c"]));
+        assert_eq!(
+            parse("IdentityFile \"~/my key\" x"),
+            line("identityfile", &["~/my key", "x"])
+        );
+        assert_eq!(parse("ForwardAgent"), line("forwardagent", &[]));
+        assert!(ConfigLine::parse("IdentityFile \"~/my key").is_err());
+    }
+
+    #[test]
+    fn test_parse_time_01() {
+        assert_eq!(parse_time("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_time("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_time("2d10"), Ok(Duration::from_secs(172810)));
+        assert!(parse_time("").is_err());
+        assert!(parse_time("1x").is_err());
+        assert!(parse_time("h").is_err());
+    }
+
+    #[test]
+    fn test_parse_size_01() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("64K"), Ok(65536));
+        assert_eq!(parse_size("1G"), Ok(1 << 30));
+        assert!(parse_size("").is_err());
+        assert!(parse_size("G").is_err());
+    }
+
+    #[test]
+    fn test_algorithms_01() {
+        let supported = ["a-1", "a-2", "b-1"];
+        let current = ["a-1", "b-1"];
+        assert_eq!(
+            parse_algorithms(&current, &supported, "+a-*"),
+            Ok(vec!["a-1", "b-1", "a-2"])
+        );
+        assert_eq!(
+            parse_algorithms(&current, &supported, "-a-*"),
+            Ok(vec!["b-1"])
+        );
+        assert_eq!(
+            parse_algorithms(&current, &supported, "^b-1,a-2"),
+            Ok(vec!["b-1", "a-2", "a-1"])
+        );
+        assert_eq!(
+            parse_algorithms(&current, &supported, "b-1,c,a-*"),
+            Ok(vec!["b-1", "a-1", "a-2"])
+        );
+        assert_eq!(parse_algorithms(&current, &supported, "c"), Ok(vec![]));
+        assert!(parse_algorithms(&current, &supported, "a-1,,b-1").is_err());
+    }
+}
@@ -75,10 +75,51 @@ impl<T: Deref<Target = str>> Glob<T> {
     }
 }
 
+/// A comma separated list of globs that may be negated with `!` (like OpenSSH's host and
+/// principal patterns).
+///
+/// The list matches if any glob matches and no negated glob matches.
+///
+/// Example: `GlobList("*.example.com,!bar.example.com").test("foo.example.com") == true`
+#[derive(Debug, Clone)]
+pub struct GlobList<T: Deref<Target = str> = String>(pub T);
+
+impl<T: Deref<Target = str>> GlobList<T> {
+    pub fn test(&self, input: &str) -> bool {
+        self.test_by(input, |glob, input| Glob(glob).test(input))
+    }
+
+    /// Like [test](Self::test), but with a custom function to test the individual globs.
+    pub fn test_by<F: Fn(&str, &str) -> bool>(&self, input: &str, test: F) -> bool {
+        // Test all globs. Stop immediately on negated match or try all.
+        let mut result = false;
+        for glob in self.0.split(',') {
+            if let Some(glob) = glob.strip_prefix('!') {
+                if test(glob, input) {
+                    return false;
+                }
+            } else if test(glob, input) {
+                // DO NOT return early: Negated match might follow!
+                result = true
+            }
+        }
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_glob_list_test_01() {
+        let list = GlobList("*@example.com,!root@*,admin");
+        assert!(list.test("user@example.com"));
+        assert!(list.test("admin"));
+        assert!(!list.test("root@example.com"));
+        assert!(!list.test("user@example.org"));
+    }
+
     #[test]
     fn test_glob_test_01() {
         let glob = Glob("");
@@ -11,6 +11,22 @@ impl Secret {
     }
 }
 
+/// Compare two byte strings in constant time (with respect to their contents).
+///
+/// Use this for comparing passwords, tokens and the like in order to not leak information
+/// about how many leading bytes matched. Only the length is not secret.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut x = 0;
+    for (a, b) in a.iter().zip(b) {
+        x |= a ^ b;
+    }
+    // Hinder the compiler from turning this into an early return
+    std::hint::black_box(x) == 0
+}
+
 impl AsRef<[u8]> for Secret {
     fn as_ref(&self) -> &[u8] {
         self.0.as_ref()
@@ -67,6 +83,14 @@ mod tests {
         assert_eq!(x1.as_ref(), x2.as_ref());
     }
 
+    #[test]
+    fn test_constant_time_eq_01() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"abcd", b"abcd"));
+        assert!(!constant_time_eq(b"abcd", b"abce"));
+        assert!(!constant_time_eq(b"abcd", b"abc"));
+    }
+
     #[test]
     fn test_encode_01() {
         let expected = [
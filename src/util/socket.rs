@@ -27,7 +27,11 @@ pub async fn read_exact<S: Socket>(socket: &mut S, buf: &mut [u8]) -> Result<(),
     let mut s = socket;
     let mut b = tokio::io::ReadBuf::new(buf);
     while b.filled().len() < b.capacity() {
+        let n = b.filled().len();
         poll_fn(|cx| Pin::new(&mut s).poll_read(cx, &mut b)).await?;
+        if b.filled().len() == n {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
     }
     Ok(())
 }
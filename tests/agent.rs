@@ -1,101 +1,41 @@
-use ed25519_dalek as ed25519;
+mod common;
+
+use common::agent::*;
+use common::*;
 use shellfish::agent::*;
-use shellfish::identity::ssh_ed25519::SshEd25519PublicKey;
-use shellfish::identity::*;
-use shellfish::util::codec::*;
+use shellfish::connection::{AuthAgentRequest, ConnectionHandler};
+use std::sync::Arc;
 
 #[derive(Debug)]
-pub struct AuthAgentForTesting {
-    delay: std::time::Duration,
-    identities: Vec<(Identity, String, ed25519::Keypair)>,
-    is_no_identities: bool,
-    is_unable_to_sign: bool,
-    is_invalid_signature: bool,
-    is_sign_error: bool
-}
-
-impl AuthAgentForTesting {
-    pub fn new() -> Self {
-        let secret_key_bytes: [u8; ed25519::SECRET_KEY_LENGTH] = [
-            157, 097, 177, 157, 239, 253, 090, 096, 186, 132, 074, 244, 146, 236, 044, 196, 068,
-            073, 197, 105, 123, 050, 105, 025, 112, 059, 172, 003, 028, 174, 127, 096,
-        ];
-        let secret = ed25519::SecretKey::from_bytes(&secret_key_bytes).unwrap();
-        let public = ed25519::PublicKey::from(&secret);
-        let identity = SshCodec::encode(&SshEd25519PublicKey(public.as_bytes())).unwrap();
-        let identity = Identity::from(identity);
-        let comment = "KEY 1 (ed25519)".to_string();
-        let keypair = ed25519::Keypair { public, secret };
-
-        Self {
-            delay: std::time::Duration::from_millis(7),
-            identities: vec![(identity, comment, keypair)],
-            is_no_identities: false,
-            is_unable_to_sign: false,
-            is_invalid_signature: false,
-            is_sign_error: false
-        }
-    }
-
-    pub fn no_identities(mut self) -> Self {
-        self.is_no_identities = true;
-        self
-    }
+struct AgentForwardingHandler(Arc<dyn AuthAgent>);
 
-    pub fn unable_to_sign(mut self) -> Self {
-        self.is_unable_to_sign = true;
-        self
-    }
-
-    pub fn invalid_signature(mut self) -> Self {
-        self.is_invalid_signature = true;
-        self
-    }
-
-    pub fn sign_error(mut self) -> Self {
-        self.is_sign_error = true;
-        self
+impl ConnectionHandler for AgentForwardingHandler {
+    fn on_auth_agent_request(&mut self, request: AuthAgentRequest) {
+        request.bridge(self.0.clone())
     }
 }
 
-impl AuthAgent for AuthAgentForTesting {
-    fn identities(&self) -> AuthAgentFuture<Vec<(Identity, String)>> {
-        let delay = self.delay;
-        let ids: Vec<_> = if self.is_no_identities {
-            vec![]
-        } else  {
-            self.identities
-                .iter()
-                .map(|(i, c, _)| (i.clone(), c.clone()))
-                .collect()
-        };
-        Box::pin(async move {
-            tokio::time::sleep(delay).await;
-            Ok(ids)
-        })
-    }
+#[tokio::test]
+async fn test_agent_forwarding() -> Result<(), Box<dyn std::error::Error>> {
+    let agent: Arc<dyn AuthAgent> = Arc::new(AuthAgentForTesting::new());
+    let mut pair = Pair::new().await?;
+    pair.client_handler = Box::new(AgentForwardingHandler(agent.clone()));
+    let (server, _client) = pair.connect().await?;
+    let forwarded = ForwardedAgent::new(&server);
+
+    let identities = forwarded.identities().await?;
+    assert_eq!(identities, agent.identities().await?);
+    let id = &identities[0].0;
+    let sig = forwarded.signature(id, b"data", 0).await?;
+    assert!(sig.unwrap().verify(id, b"data").is_ok());
+    Ok(())
+}
 
-    fn signature(&self, id: &Identity, data: &[u8], _: u32) -> AuthAgentFuture<Option<Signature>> {
-        let delay = self.delay;
-        let sig = if self.is_sign_error {
-            Err(AuthAgentError::new(std::io::Error::new(std::io::ErrorKind::InvalidData.into(),"")))
-        } else if self.is_unable_to_sign {
-            Ok(None)
-        } else if let Some(x) = self.identities.iter().find(|x| &x.0 == id) {
-            use ed25519_dalek::Signer;
-            let algo = "ssh-ed25519".to_string();
-            let mut blob = x.2.sign(data).to_bytes().to_vec();
-            if self.is_invalid_signature {
-                blob[23] += 1;
-            }
-            Ok(Some(Signature::new(algo, blob)))
-        } else {
-            Ok(None)
-        };
+#[tokio::test]
+async fn test_agent_forwarding_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let (server, _client) = Pair::new().await?.connect().await?;
+    let forwarded = ForwardedAgent::new(&server);
 
-        Box::pin(async move {
-            tokio::time::sleep(delay).await;
-            sig
-        })
-    }
+    assert!(forwarded.identities().await.is_err());
+    Ok(())
 }
@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+
+pub mod agent;
+pub mod host;
+pub mod password;
+pub mod socket;
+
+use agent::*;
+use host::*;
+use password::*;
+use shellfish::agent::AuthAgent;
+use shellfish::connection::{Connection, ConnectionConfig, ConnectionHandler};
+use shellfish::host::HostVerifier;
+use shellfish::transport::{Transport, TransportConfig};
+use shellfish::user_auth::*;
+use socket::*;
+use std::sync::Arc;
+
+pub const HOST: &str = "localhost";
+pub const PORT: u16 = 22;
+pub const USER: &str = "user";
+
+/// A connected pair of server and client.
+///
+/// The defaults use the [AuthAgentForTesting] key as host key and authenticate the client by
+/// password. Adjust the fields before calling [connect](Self::connect).
+pub struct Pair {
+    pub host_keys: Arc<dyn AuthAgent>,
+    pub host_verifier: Arc<dyn HostVerifier>,
+    pub server_config: ConnectionConfig,
+    pub client_config: ConnectionConfig,
+    pub server_handler: Box<dyn ConnectionHandler>,
+    pub client_handler: Box<dyn ConnectionHandler>,
+}
+
+impl Pair {
+    pub async fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        let host_keys: Arc<dyn AuthAgent> = Arc::new(AuthAgentForTesting::new());
+        let identity = host_keys.identities().await?[0].0.clone();
+        let host_verifier = Arc::new(HostVerifierForTesting::new(HOST, PORT, &identity));
+        Ok(Self {
+            host_keys,
+            host_verifier,
+            server_config: ConnectionConfig::default(),
+            client_config: ConnectionConfig::default(),
+            server_handler: Box::new(()),
+            client_handler: Box::new(()),
+        })
+    }
+
+    /// Return the server and client transports (ready for user authentication).
+    pub async fn transports(&self) -> Result<(Transport, Transport), Box<dyn std::error::Error>> {
+        let (sock1, sock2) = Socket::new_tcp().await?;
+        let conf = Arc::new(TransportConfig::default());
+        let conf_ = conf.clone();
+        let host_keys = self.host_keys.clone();
+        let verifier = self.host_verifier.clone();
+        let srv = UserAuth::SSH_USERAUTH;
+        let task1 = async move { Transport::accept(sock1, &conf, &host_keys, srv).await };
+        let task2 = async move {
+            Transport::connect(sock2, &conf_, &verifier, HOST, PORT, None, srv).await
+        };
+        let task1 = tokio::spawn(task1);
+        let task2 = tokio::spawn(task2);
+        Ok((task1.await??, task2.await??))
+    }
+
+    /// Return the server and client connections (after password authentication).
+    pub async fn connect(self) -> Result<(Connection, Connection), Box<dyn std::error::Error>> {
+        let (t1, t2) = self.transports().await?;
+        let cc1 = Arc::new(self.server_config);
+        let cc2 = Arc::new(self.client_config);
+        let (h1, h2) = (self.server_handler, self.client_handler);
+        let server = Arc::new(UserAuthConfig {
+            password_verifier: Some(Arc::new(PasswordVerifierForTesting::new("secret"))),
+            ..Default::default()
+        });
+        let client = Arc::new(UserAuthConfig {
+            password_prompt: Some(Arc::new(PasswordPromptForTesting::new(vec!["secret"]))),
+            ..Default::default()
+        });
+        let agent: Arc<dyn AuthAgent> = Arc::new(());
+        let task1 = async move { UserAuth::offer_connection(t1, &cc1, |_| h1, &server).await };
+        let task2 = async move {
+            UserAuth::request_connection(t2, &cc2, |_| h2, &client, USER, HOST, &agent).await
+        };
+        let task1 = tokio::spawn(task1);
+        let task2 = tokio::spawn(task2);
+        Ok((task1.await??, task2.await??))
+    }
+}
@@ -0,0 +1,74 @@
+use super::USER;
+use shellfish::user_auth::*;
+use shellfish::util::secret::constant_time_eq;
+use shellfish::util::BoxFuture;
+use std::sync::Mutex;
+
+#[derive(Debug)]
+pub struct PasswordPromptForTesting {
+    passwords: Mutex<Vec<&'static str>>,
+    pub new_password: Option<&'static str>,
+}
+
+impl PasswordPromptForTesting {
+    pub fn new(passwords: Vec<&'static str>) -> Self {
+        Self {
+            passwords: Mutex::new(passwords),
+            new_password: None,
+        }
+    }
+}
+
+impl PasswordPrompt for PasswordPromptForTesting {
+    fn password(&self, _: &str, _: &str) -> BoxFuture<Option<String>> {
+        let pw = self.passwords.lock().unwrap().pop().map(String::from);
+        Box::pin(async move { pw })
+    }
+
+    fn new_password(&self, _: &str, _: &str, _: &str) -> BoxFuture<Option<String>> {
+        let pw = self.new_password.map(String::from);
+        Box::pin(async move { pw })
+    }
+}
+
+#[derive(Debug)]
+pub struct PasswordVerifierForTesting {
+    password: Mutex<&'static str>,
+    pub expired: Mutex<bool>,
+}
+
+impl PasswordVerifierForTesting {
+    pub fn new(password: &'static str) -> Self {
+        Self {
+            password: Mutex::new(password),
+            expired: Mutex::new(false),
+        }
+    }
+}
+
+impl PasswordVerifier for PasswordVerifierForTesting {
+    fn verify(&self, user: &str, password: &str) -> BoxFuture<PasswordVerification> {
+        let ok = user == USER
+            && constant_time_eq(password.as_ref(), self.password.lock().unwrap().as_ref());
+        let result = if !ok {
+            PasswordVerification::Rejected
+        } else if *self.expired.lock().unwrap() {
+            PasswordVerification::ChangeRequired("Password expired".into())
+        } else {
+            PasswordVerification::Accepted
+        };
+        Box::pin(async move { result })
+    }
+
+    fn change(&self, user: &str, password: &str, new: &str) -> BoxFuture<PasswordVerification> {
+        let ok = user == USER
+            && constant_time_eq(password.as_ref(), self.password.lock().unwrap().as_ref());
+        let result = if ok && new.len() >= 4 {
+            *self.expired.lock().unwrap() = false;
+            PasswordVerification::Accepted
+        } else {
+            PasswordVerification::Rejected
+        };
+        Box::pin(async move { result })
+    }
+}
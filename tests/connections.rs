@@ -1,3 +1,46 @@
+mod common;
+
+use common::*;
+use shellfish::connection::{ConnectionError, ConnectionHandler, GlobalRequestWantReply};
+use std::time::Duration;
+
+/// Holds back all requests so that the peer never gets a reply.
+#[derive(Debug, Default)]
+struct SilentHandler(Vec<GlobalRequestWantReply>);
+
+impl ConnectionHandler for SilentHandler {
+    fn on_request_want_reply(&mut self, request: GlobalRequestWantReply) {
+        self.0.push(request)
+    }
+}
+
+#[tokio::test]
+async fn test_keepalive() -> Result<(), Box<dyn std::error::Error>> {
+    let mut pair = Pair::new().await?;
+    pair.client_config.keepalive_interval = Some(Duration::from_millis(10));
+    pair.client_config.keepalive_count_max = 2;
+    let (c1, c2) = pair.connect().await?;
+
+    // The server answers each request, so the client survives many intervals
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(c1.check().is_ok());
+    assert!(c2.check().is_ok());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_keepalive_timeout() -> Result<(), Box<dyn std::error::Error>> {
+    let mut pair = Pair::new().await?;
+    pair.client_config.keepalive_interval = Some(Duration::from_millis(10));
+    pair.client_config.keepalive_count_max = 2;
+    pair.server_handler = Box::new(SilentHandler::default());
+    let (_c1, mut c2) = pair.connect().await?;
+
+    tokio::time::timeout(Duration::from_secs(5), c2.closed()).await?;
+    assert!(matches!(c2.check(), Err(ConnectionError::KeepAliveTimeout)));
+    Ok(())
+}
+
 // use shellfish::connection::Connection;
 // use shellfish::connection::ConnectionConfig;
 // use shellfish::connection::ConnectionError;
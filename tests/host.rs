@@ -1,42 +1,38 @@
-use shellfish::host::*;
-use shellfish::identity::*;
-use shellfish::util::BoxFuture;
+mod common;
 
-#[derive(Debug)]
-pub struct HostVerifierForTesting {
-    delay: std::time::Duration,
-    known: Vec<(String, u16, Identity)>,
-}
+use common::*;
+use shellfish::agent::*;
+use shellfish::host::KnownHosts;
+use shellfish::identity::ssh_ed25519::SshEd25519PrivateKey;
+use std::sync::Arc;
+use std::time::Duration;
 
-impl HostVerifierForTesting {
-    pub fn new(name: &str, port: u16, identity: &Identity) -> Self {
-        Self {
-            delay: std::time::Duration::from_millis(3),
-            known: vec![(name.into(), port, identity.clone())]
-        }
+#[tokio::test]
+async fn test_host_keys_update() -> Result<(), Box<dyn std::error::Error>> {
+    let host_keys = KeyStore::new();
+    for (key, comment) in [(1, "one"), (2, "two")] {
+        host_keys.add(SshEd25519PrivateKey::new([key; 32]).into(), comment);
     }
-}
+    let ids = host_keys.identities().await?;
+    let path = std::env::temp_dir().join(format!("shellfish-hostkeys-{}", std::process::id()));
+    std::fs::write(&path, format!("{} {}\n", HOST, ids[0].0))?;
 
-impl HostVerifier for HostVerifierForTesting {
-    fn verify(
-        &self,
-        name: &str,
-        port: u16,
-        identity: &Identity,
-    ) -> BoxFuture<Result<(), HostVerificationError>> {
-        let delay = self.delay;
-        let found = self
-            .known
-            .iter()
-            .any(|x| x.0 == name && x.1 == port && &x.2 == identity);
-        let result = if found {
-            Ok(())
-        } else {
-            Err(HostVerificationError::Unverifiable)
-        };
-        Box::pin(async move {
-            tokio::time::sleep(delay).await;
-            result
-        })
+    let mut pair = Pair::new().await?;
+    pair.host_keys = Arc::new(host_keys);
+    pair.host_verifier = Arc::new(KnownHosts::new(vec![path.clone()]));
+    let _connections = pair.connect().await?;
+
+    // The client learns the second key after authentication
+    let expected = format!("{} {}\n{} {}\n", HOST, ids[0].0, HOST, ids[1].0);
+    let mut text = String::new();
+    for _ in 0..100 {
+        text = std::fs::read_to_string(&path)?;
+        if text == expected {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
     }
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(text, expected);
+    Ok(())
 }
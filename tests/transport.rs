@@ -1,16 +1,14 @@
-mod agent;
-mod host;
-mod socket;
+mod common;
 
-use agent::*;
-use host::*;
+use common::agent::*;
+use common::host::*;
+use common::socket::*;
 use shellfish::agent::AuthAgent;
 use shellfish::host::HostVerifier;
 use shellfish::transport::Transport;
 use shellfish::transport::TransportConfig;
 use shellfish::transport::TransportError;
 use shellfish::transport::DisconnectReason;
-use socket::*;
 use std::sync::Arc;
 
 const HOST: &'static str = "localhost";
@@ -33,7 +31,7 @@ async fn test_connect_ok() -> Result<(), Box<dyn std::error::Error>> {
     let verifier: Arc<dyn HostVerifier> = Arc::new(verifier);
 
     let task1 = async move { Transport::accept(sock1, &conf, &agent, SRV).await };
-    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, SRV).await };
+    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, None, SRV).await };
 
     let task1 = tokio::spawn(task1);
     let task2 = tokio::spawn(task2);
@@ -64,7 +62,7 @@ async fn test_connect_service_not_available() -> Result<(), Box<dyn std::error::
     let verifier: Arc<dyn HostVerifier> = Arc::new(verifier);
 
     let task1 = async move { Transport::accept(sock1, &conf, &agent, "invalid-service").await };
-    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, SRV).await };
+    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, None, SRV).await };
 
     let task1 = tokio::spawn(task1);
     let task2 = tokio::spawn(task2);
@@ -101,7 +99,7 @@ async fn test_connect_agent_no_identities() -> Result<(), Box<dyn std::error::Er
     let verifier: Arc<dyn HostVerifier> = Arc::new(verifier);
 
     let task1 = async move { Transport::accept(sock1, &conf, &agent, SRV).await };
-    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, SRV).await };
+    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, None, SRV).await };
 
     let task1 = tokio::spawn(task1);
     let task2 = tokio::spawn(task2);
@@ -138,7 +136,7 @@ async fn test_connect_agent_unable_to_sign() -> Result<(), Box<dyn std::error::E
     let verifier: Arc<dyn HostVerifier> = Arc::new(verifier);
 
     let task1 = async move { Transport::accept(sock1, &conf, &agent, SRV).await };
-    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, SRV).await };
+    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, None, SRV).await };
 
     let task1 = tokio::spawn(task1);
     let task2 = tokio::spawn(task2);
@@ -175,7 +173,7 @@ async fn test_connect_agent_invalid_signature() -> Result<(), Box<dyn std::error
     let verifier: Arc<dyn HostVerifier> = Arc::new(verifier);
 
     let task1 = async move { Transport::accept(sock1, &conf, &agent, SRV).await };
-    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, SRV).await };
+    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, None, SRV).await };
 
     let task1 = tokio::spawn(task1);
     let task2 = tokio::spawn(task2);
@@ -212,7 +210,7 @@ async fn test_connect_agent_sign_error() -> Result<(), Box<dyn std::error::Error
     let verifier: Arc<dyn HostVerifier> = Arc::new(verifier);
 
     let task1 = async move { Transport::accept(sock1, &conf, &agent, SRV).await };
-    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, SRV).await };
+    let task2 = async move { Transport::connect(sock2, &conf_, &verifier, HOST, PORT, None, SRV).await };
 
     let task1 = tokio::spawn(task1);
     let task2 = tokio::spawn(task2);